@@ -5,9 +5,11 @@ use std::time::{Duration, SystemTime};
 use chrono::{DateTime, TimeDelta, Utc};
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 
+use ccsesh::dedupe;
 use ccsesh::discover;
 use ccsesh::display;
 use ccsesh::parse;
+use ccsesh::shell::ResumeTemplate;
 use ccsesh::types::SessionInfo;
 
 // ---------------------------------------------------------------------------
@@ -94,6 +96,12 @@ fn make_session(
         last_active: now - TimeDelta::seconds(index as i64 * 137),
         first_prompt: prompt.map(String::from),
         slug: slug.map(String::from),
+        active: false,
+        active_pid: None,
+        active_match: None,
+        continuation: Default::default(),
+        earlier: vec![],
+        duplicates: vec![],
     }
 }
 
@@ -203,22 +211,24 @@ fn bench_display(c: &mut Criterion) {
         })
         .collect();
 
+    let template = ResumeTemplate::default();
+
     let mut group = c.benchmark_group("display");
 
     group.bench_function("format_default_5", |b| {
-        b.iter(|| display::format_default(&sessions_5, now));
+        b.iter(|| display::format_default(&sessions_5, now, &display::ListingOptions::default()));
     });
     group.bench_function("format_short_5", |b| {
-        b.iter(|| display::format_short(&sessions_5, now));
+        b.iter(|| display::format_short(&sessions_5, now, &display::ListingOptions::default()));
     });
     group.bench_function("format_json_5", |b| {
-        b.iter(|| display::format_json(&sessions_5, now));
+        b.iter(|| display::format_json(&sessions_5, now, &template, &[]));
     });
     group.bench_function("format_default_20", |b| {
-        b.iter(|| display::format_default(&sessions_20, now));
+        b.iter(|| display::format_default(&sessions_20, now, &display::ListingOptions::default()));
     });
     group.bench_function("format_json_20", |b| {
-        b.iter(|| display::format_json(&sessions_20, now));
+        b.iter(|| display::format_json(&sessions_20, now, &template, &[]));
     });
 
     group.finish();
@@ -239,6 +249,32 @@ fn bench_truncate_prompt(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_dedupe(c: &mut Criterion) {
+    let a = "fix the pagination bug in the users endpoint for the api server module";
+    let b = "fix the pagination bugs in the users endpoint of the api server module";
+
+    let mut group = c.benchmark_group("dedupe");
+    group.bench_function("similarity", |bench| {
+        bench.iter(|| dedupe::similarity(a, b));
+    });
+
+    let now = Utc::now();
+    let sessions: Vec<SessionInfo> = (0..50)
+        .map(|i| {
+            make_session(
+                i,
+                now,
+                Some(&format!("Prompt number {} for a project", i % 20)),
+                None,
+            )
+        })
+        .collect();
+    group.bench_function("collapse_50", |bench| {
+        bench.iter(|| dedupe::collapse(sessions.clone()));
+    });
+    group.finish();
+}
+
 fn bench_relative_time(c: &mut Criterion) {
     let durations = [
         ("30s", TimeDelta::seconds(30)),
@@ -268,6 +304,7 @@ criterion_group!(
     bench_extract_text,
     bench_display,
     bench_truncate_prompt,
+    bench_dedupe,
     bench_relative_time,
 );
 criterion_main!(benches);
@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::types::{ActiveMatch, SessionInfo};
+
+/// Sessions modified within this many seconds are considered open when
+/// process scanning is unavailable (non-Linux platforms).
+pub const ACTIVE_WINDOW_SECS: i64 = 120;
+
+/// A running Claude Code process found under `/proc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaudeProcess {
+    pub pid: u32,
+    pub cwd: Option<PathBuf>,
+    /// Session ID from `--resume <id>` / `--session-id <id>`, if present.
+    pub session_id: Option<String>,
+}
+
+/// Scan a procfs root (normally `/proc`) for `claude` processes.
+///
+/// Unreadable entries (other users' processes, races with exiting processes)
+/// are skipped silently.
+pub fn scan_processes(proc_root: &Path) -> Vec<ClaudeProcess> {
+    let entries = match std::fs::read_dir(proc_root) {
+        Ok(e) => e,
+        Err(_) => return vec![],
+    };
+
+    let mut processes = Vec::new();
+    for entry in entries.flatten() {
+        let pid: u32 = match entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        let cmdline = match std::fs::read(entry.path().join("cmdline")) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        let args: Vec<String> = cmdline
+            .split(|&b| b == 0)
+            .filter(|a| !a.is_empty())
+            .map(|a| String::from_utf8_lossy(a).into_owned())
+            .collect();
+
+        if !is_claude_command(&args) {
+            continue;
+        }
+
+        processes.push(ClaudeProcess {
+            pid,
+            cwd: std::fs::read_link(entry.path().join("cwd")).ok(),
+            session_id: resume_session_id(&args),
+        });
+    }
+
+    processes.sort_by_key(|p| p.pid);
+    processes
+}
+
+/// Whether an argv belongs to Claude Code: either the `claude` binary itself,
+/// or an interpreter (`node`, `sh`) running a `claude` script.
+fn is_claude_command(args: &[String]) -> bool {
+    args.iter().take(2).any(|arg| {
+        let base = Path::new(arg)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("");
+        base == "claude" || arg.contains("@anthropic-ai/claude-code/")
+    })
+}
+
+fn resume_session_id(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--resume" | "-r" | "--session-id" => return iter.next().cloned(),
+            _ => {
+                if let Some(id) = arg
+                    .strip_prefix("--resume=")
+                    .or_else(|| arg.strip_prefix("--session-id="))
+                {
+                    return Some(id.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Mark sessions that belong to a running process, including the `earlier`
+/// and `duplicates` members nested in each one.
+///
+/// A process started with an explicit session ID claims that session. Any
+/// other process (`claude`, `claude --continue`) claims the most recently
+/// active unclaimed session whose project dir matches its cwd.
+pub fn mark_active(sessions: &mut [SessionInfo], processes: &[ClaudeProcess]) {
+    let all = nested(sessions);
+    let mut claims: HashMap<String, (u32, ActiveMatch)> = HashMap::new();
+    for process in processes {
+        if let Some(id) = &process.session_id
+            && all.iter().any(|s| &s.session_id == id)
+        {
+            claims.insert(id.clone(), (process.pid, ActiveMatch::SessionId));
+        }
+    }
+    // Explicit IDs first, so a cwd match never takes a session that a
+    // later process names.
+    for process in processes.iter().filter(|p| p.session_id.is_none()) {
+        let Some(cwd) = &process.cwd else { continue };
+        let newest = all
+            .iter()
+            .filter(|s| {
+                !s.active
+                    && !claims.contains_key(&s.session_id)
+                    && !s.project_dir.as_os_str().is_empty()
+                    && &s.project_dir == cwd
+            })
+            .max_by_key(|s| s.last_active);
+        if let Some(session) = newest {
+            claims.insert(session.session_id.clone(), (process.pid, ActiveMatch::Cwd));
+        }
+    }
+    apply_claims(sessions, &claims);
+}
+
+/// `sessions` and every session nested in them, depth first.
+fn nested(sessions: &[SessionInfo]) -> Vec<&SessionInfo> {
+    sessions
+        .iter()
+        .flat_map(|s| {
+            std::iter::once(s)
+                .chain(nested(&s.earlier))
+                .chain(nested(&s.duplicates))
+        })
+        .collect()
+}
+
+fn apply_claims(sessions: &mut [SessionInfo], claims: &HashMap<String, (u32, ActiveMatch)>) {
+    for session in sessions {
+        if let Some(&(pid, how)) = claims.get(&session.session_id) {
+            session.active = true;
+            session.active_pid = Some(pid);
+            session.active_match = Some(how);
+        }
+        apply_claims(&mut session.earlier, claims);
+        apply_claims(&mut session.duplicates, claims);
+    }
+}
+
+/// Mark sessions modified within `ACTIVE_WINDOW_SECS` of `now` as active,
+/// nested members included.
+pub fn mark_recently_modified(sessions: &mut [SessionInfo], now: DateTime<Utc>) {
+    for session in sessions {
+        if (now - session.last_active).num_seconds() < ACTIVE_WINDOW_SECS {
+            session.active = true;
+            session.active_match = Some(ActiveMatch::RecentlyModified);
+        }
+        mark_recently_modified(&mut session.earlier, now);
+        mark_recently_modified(&mut session.duplicates, now);
+    }
+}
+
+/// Detect open sessions: via `/proc` where available, otherwise by mtime.
+pub fn detect_active(sessions: &mut [SessionInfo], now: DateTime<Utc>) {
+    let proc_root = Path::new("/proc");
+    if cfg!(target_os = "linux") && proc_root.is_dir() {
+        mark_active(sessions, &scan_processes(proc_root));
+    } else {
+        mark_recently_modified(sessions, now);
+    }
+}
+
+/// Parent PIDs of `pid`, nearest first, read from `<proc_root>/<pid>/stat`.
+pub fn ancestor_pids(proc_root: &Path, pid: u32) -> Vec<u32> {
+    let mut ancestors = Vec::new();
+    let mut current = pid;
+    // Bounded walk in case of a corrupt or cyclic procfs view.
+    for _ in 0..64 {
+        let stat = match std::fs::read_to_string(proc_root.join(current.to_string()).join("stat")) {
+            Ok(s) => s,
+            Err(_) => break,
+        };
+        // Format: "pid (comm) state ppid ..." -- comm may contain spaces/parens,
+        // so parse from the last ')'.
+        let ppid = stat
+            .rsplit_once(')')
+            .and_then(|(_, rest)| rest.split_whitespace().nth(1))
+            .and_then(|p| p.parse::<u32>().ok());
+        match ppid {
+            Some(p) if p > 1 => {
+                ancestors.push(p);
+                current = p;
+            }
+            _ => break,
+        }
+    }
+    ancestors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use std::fs;
+
+    fn make_session(id: &str, dir: &str, last_active: DateTime<Utc>) -> SessionInfo {
+        SessionInfo {
+            last_active,
+            first_prompt: Some("prompt".to_string()),
+            ..SessionInfo::fixture(id, dir)
+        }
+    }
+
+    /// Create a fake `<root>/<pid>/` entry with the given argv and cwd.
+    #[cfg(unix)]
+    fn fake_process(root: &Path, pid: u32, args: &[&str], cwd: &Path) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cmdline"), args.join("\0") + "\0").unwrap();
+        std::os::unix::fs::symlink(cwd, dir.join("cwd")).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_finds_claude_processes() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let root = tmp.path();
+        fake_process(
+            root,
+            100,
+            &["claude", "--resume", "eb53d999-8692-42ce-a376-4f82206a086d"],
+            Path::new("/home/user/a"),
+        );
+        fake_process(
+            root,
+            200,
+            &[
+                "node",
+                "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js",
+                "--continue",
+            ],
+            Path::new("/home/user/b"),
+        );
+        fake_process(root, 300, &["vim", "claude.md"], Path::new("/home/user/c"));
+        fs::create_dir_all(root.join("self")).unwrap();
+
+        let processes = scan_processes(root);
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].pid, 100);
+        assert_eq!(
+            processes[0].session_id.as_deref(),
+            Some("eb53d999-8692-42ce-a376-4f82206a086d")
+        );
+        assert_eq!(processes[1].pid, 200);
+        assert_eq!(processes[1].session_id, None);
+        assert_eq!(processes[1].cwd, Some(PathBuf::from("/home/user/b")));
+    }
+
+    #[test]
+    fn scan_missing_root_is_empty() {
+        assert!(scan_processes(Path::new("/nonexistent/proc")).is_empty());
+    }
+
+    #[test]
+    fn resume_id_forms() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            resume_session_id(&args(&["claude", "-r", "abc"])),
+            Some("abc".into())
+        );
+        assert_eq!(
+            resume_session_id(&args(&["claude", "--resume=abc"])),
+            Some("abc".into())
+        );
+        assert_eq!(
+            resume_session_id(&args(&["claude", "--session-id", "abc"])),
+            Some("abc".into())
+        );
+        assert_eq!(resume_session_id(&args(&["claude", "-c"])), None);
+    }
+
+    #[test]
+    fn mark_active_by_session_id() {
+        let now = Utc::now();
+        let mut sessions = vec![
+            make_session("a", "/p", now),
+            make_session("b", "/p", now - TimeDelta::seconds(60)),
+        ];
+        let processes = vec![ClaudeProcess {
+            pid: 42,
+            cwd: Some(PathBuf::from("/elsewhere")),
+            session_id: Some("b".to_string()),
+        }];
+        mark_active(&mut sessions, &processes);
+        assert!(!sessions[0].active);
+        assert!(sessions[1].active);
+        assert_eq!(sessions[1].active_pid, Some(42));
+        assert_eq!(sessions[1].active_match, Some(ActiveMatch::SessionId));
+    }
+
+    #[test]
+    fn mark_active_by_cwd_claims_newest_in_dir() {
+        let now = Utc::now();
+        let mut sessions = vec![
+            make_session("old", "/p", now - TimeDelta::seconds(600)),
+            make_session("new", "/p", now - TimeDelta::seconds(10)),
+            make_session("other", "/q", now),
+        ];
+        let processes = vec![ClaudeProcess {
+            pid: 7,
+            cwd: Some(PathBuf::from("/p")),
+            session_id: None,
+        }];
+        mark_active(&mut sessions, &processes);
+        assert!(!sessions[0].active);
+        assert!(sessions[1].active);
+        assert_eq!(sessions[1].active_match, Some(ActiveMatch::Cwd));
+        assert!(!sessions[2].active);
+    }
+
+    #[test]
+    fn mark_active_two_processes_same_dir() {
+        let now = Utc::now();
+        let mut sessions = vec![
+            make_session("old", "/p", now - TimeDelta::seconds(600)),
+            make_session("new", "/p", now - TimeDelta::seconds(10)),
+        ];
+        let processes = vec![
+            ClaudeProcess {
+                pid: 7,
+                cwd: Some(PathBuf::from("/p")),
+                session_id: None,
+            },
+            ClaudeProcess {
+                pid: 8,
+                cwd: Some(PathBuf::from("/p")),
+                session_id: None,
+            },
+        ];
+        mark_active(&mut sessions, &processes);
+        assert_eq!(sessions[1].active_pid, Some(7));
+        assert_eq!(sessions[0].active_pid, Some(8));
+    }
+
+    #[test]
+    fn mark_active_ignores_sessions_without_cwd() {
+        let now = Utc::now();
+        let mut sessions = vec![make_session("a", "", now)];
+        let processes = vec![ClaudeProcess {
+            pid: 7,
+            cwd: Some(PathBuf::from("")),
+            session_id: None,
+        }];
+        mark_active(&mut sessions, &processes);
+        assert!(!sessions[0].active);
+    }
+
+    #[test]
+    fn mark_active_reaches_chain_members_and_duplicates() {
+        let now = Utc::now();
+        let mut duplicate = make_session("d", "/p", now - TimeDelta::hours(2));
+        duplicate.earlier = vec![make_session("d0", "/p", now - TimeDelta::hours(3))];
+        let mut row = make_session("r", "/p", now - TimeDelta::hours(1));
+        row.duplicates = vec![
+            duplicate,
+            make_session("e", "/p", now - TimeDelta::hours(4)),
+        ];
+        let mut sessions = vec![row];
+        let processes = vec![
+            ClaudeProcess {
+                pid: 10,
+                cwd: Some(PathBuf::from("/p")),
+                session_id: None,
+            },
+            ClaudeProcess {
+                pid: 11,
+                cwd: Some(PathBuf::from("/p")),
+                session_id: Some("d0".to_string()),
+            },
+            ClaudeProcess {
+                pid: 12,
+                cwd: Some(PathBuf::from("/p")),
+                session_id: None,
+            },
+        ];
+        mark_active(&mut sessions, &processes);
+
+        let row = &sessions[0];
+        assert_eq!(row.active_pid, Some(10));
+        // The named session is claimed before the cwd matches look.
+        assert_eq!(row.duplicates[0].earlier[0].active_pid, Some(11));
+        assert_eq!(row.duplicates[0].active_pid, Some(12));
+        assert!(!row.duplicates[1].active);
+    }
+
+    #[test]
+    fn recently_modified_heuristic() {
+        let now = Utc::now();
+        let mut sessions = vec![
+            make_session("fresh", "/p", now - TimeDelta::seconds(30)),
+            make_session("stale", "/p", now - TimeDelta::seconds(3600)),
+        ];
+        mark_recently_modified(&mut sessions, now);
+        assert!(sessions[0].active);
+        assert_eq!(sessions[0].active_pid, None);
+        assert_eq!(
+            sessions[0].active_match,
+            Some(ActiveMatch::RecentlyModified)
+        );
+        assert!(!sessions[1].active);
+    }
+
+    #[test]
+    fn ancestor_pids_walks_stat() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let root = tmp.path();
+        for (pid, ppid) in [(300, 200), (200, 100), (100, 1)] {
+            let dir = root.join(pid.to_string());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("stat"),
+                format!("{} (weird (name)) S {} 0 0", pid, ppid),
+            )
+            .unwrap();
+        }
+        assert_eq!(ancestor_pids(root, 300), vec![200, 100]);
+    }
+}
@@ -0,0 +1,898 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::discover::project_folder_name;
+use crate::errors::CcseshError;
+use crate::parse::is_valid_uuid;
+use crate::relocate::write_atomic;
+use crate::types::SessionInfo;
+
+/// `format` field of every bundle manifest.
+pub const BUNDLE_FORMAT: &str = "ccsesh-bundle";
+/// Manifest version written by this build; import rejects newer ones.
+pub const BUNDLE_VERSION: u32 = 1;
+/// File extension for bundles.
+pub const BUNDLE_EXTENSION: &str = "ccsesh";
+
+const MANIFEST_PATH: &str = "manifest.json";
+
+/// `manifest.json`, the first entry of a bundle.
+///
+/// Archive paths are relative to `~/.claude/`, with the project folder left
+/// out so import can choose it:
+/// - `projects/<id>.jsonl` -- the transcript
+/// - `projects/<id>/...` -- subagent transcripts and saved tool results
+/// - `todos/<id>-*.json` -- todo lists
+/// - `file-history/<id>/...` -- file snapshots for rewind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub session_id: String,
+    /// The session's working directory on the source machine.
+    pub project_dir: String,
+    /// Its folder under `~/.claude/projects/` on the source machine.
+    pub project_folder: String,
+    pub slug: Option<String>,
+    pub last_active: String,
+    pub created_at: String,
+    pub created_by: String,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the contents.
+    pub sha256: String,
+    /// Modification time, seconds since the Unix epoch. Restored on import,
+    /// since the listing ranks sessions by mtime.
+    pub mtime: u64,
+}
+
+struct BundleFile {
+    path: String,
+    contents: Vec<u8>,
+    mtime: u64,
+}
+
+/// Package `session` and its per-session files into a gzip-compressed tar
+/// at `out`.
+///
+/// Files are read into memory first, so the archive matches its manifest
+/// even if Claude Code appends to the transcript meanwhile.
+pub fn create_bundle(home_dir: &str, session: &SessionInfo, out: &Path) -> Result<Manifest> {
+    let files = collect_files(home_dir, session)?;
+    let manifest = Manifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        session_id: session.session_id.clone(),
+        project_dir: session.project_dir.to_string_lossy().to_string(),
+        project_folder: session
+            .path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        slug: session.slug.clone(),
+        last_active: session.last_active.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        created_at: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        created_by: format!("ccsesh {}", env!("CARGO_PKG_VERSION")),
+        files: files
+            .iter()
+            .map(|f| ManifestFile {
+                path: f.path.clone(),
+                size: f.contents.len() as u64,
+                sha256: sha256_hex(&f.contents),
+                mtime: f.mtime,
+            })
+            .collect(),
+    };
+
+    let write = || -> std::io::Result<()> {
+        let file = fs::File::create(out)?;
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        append(&mut builder, MANIFEST_PATH, &manifest_json, now)?;
+        for file in &files {
+            append(&mut builder, &file.path, &file.contents, file.mtime)?;
+        }
+        builder.into_inner()?.finish()?.flush()
+    };
+    write().with_context(|| format!("Failed to write bundle {}", out.display()))?;
+    Ok(manifest)
+}
+
+fn append<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    contents: &[u8],
+    mtime: u64,
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(contents.len() as u64);
+    // Transcripts are private; keep them owner-only inside the archive too.
+    header.set_mode(0o600);
+    header.set_mtime(mtime);
+    builder.append_data(&mut header, path, contents)
+}
+
+fn collect_files(home_dir: &str, session: &SessionInfo) -> Result<Vec<BundleFile>> {
+    let id = &session.session_id;
+    let claude_dir = Path::new(home_dir).join(".claude");
+    let mut files = vec![read_file(&session.path, format!("projects/{}.jsonl", id))?];
+
+    if let Some(project) = session.path.parent() {
+        collect_dir(&project.join(id), &format!("projects/{}", id), &mut files)?;
+    }
+    if let Ok(entries) = fs::read_dir(claude_dir.join("todos")) {
+        let mut todos: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&format!("{}-", id)) && n.ends_with(".json"))
+            })
+            .filter(|p| is_regular_file(p))
+            .collect();
+        todos.sort();
+        for path in todos {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            let archive_path = format!("todos/{}", name);
+            files.push(read_file(&path, archive_path)?);
+        }
+    }
+    collect_dir(
+        &claude_dir.join("file-history").join(id),
+        &format!("file-history/{}", id),
+        &mut files,
+    )?;
+    Ok(files)
+}
+
+/// Every regular file under `dir`, recursively, in name order. Symlinks and
+/// names that are not UTF-8 are skipped.
+fn collect_dir(dir: &Path, prefix: &str, files: &mut Vec<BundleFile>) -> Result<()> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let archive_path = format!("{}/{}", prefix, name);
+        if file_type.is_dir() {
+            collect_dir(&entry.path(), &archive_path, files)?;
+        } else if file_type.is_file() {
+            files.push(read_file(&entry.path(), archive_path)?);
+        }
+    }
+    Ok(())
+}
+
+fn is_regular_file(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.is_file())
+}
+
+fn read_file(path: &Path, archive_path: String) -> Result<BundleFile> {
+    let read_error = |source| CcseshError::SessionReadError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let contents = fs::read(path).map_err(read_error)?;
+    let mtime = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(read_error)?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    Ok(BundleFile {
+        path: archive_path,
+        contents,
+        mtime,
+    })
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// An `OLD=NEW` path prefix rewrite for `ccsesh import --map-path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMapping {
+    pub from: String,
+    pub to: String,
+}
+
+impl PathMapping {
+    pub fn parse(spec: &str) -> Result<Self, CcseshError> {
+        let invalid = || CcseshError::InvalidPathMapping {
+            spec: spec.to_string(),
+        };
+        let (from, to) = spec.split_once('=').ok_or_else(invalid)?;
+        let from = from.trim_end_matches('/');
+        let to = to.trim_end_matches('/');
+        if !from.starts_with('/') || !to.starts_with('/') {
+            return Err(invalid());
+        }
+        Ok(PathMapping {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    /// `path` with the `from` prefix replaced, when `path` is `from` or lies
+    /// under it. `/home/al` does not match `/home/alice`.
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let rest = path.strip_prefix(&self.from)?;
+        (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", self.to, rest))
+    }
+}
+
+/// The first mapping that applies to `path`.
+fn map_path(path: &str, mappings: &[PathMapping]) -> Option<String> {
+    mappings.iter().find_map(|m| m.apply(path))
+}
+
+/// Rewrite the top-level `cwd` of one JSONL record through `mappings`,
+/// returning `None` when nothing changes. Only that value is replaced; the
+/// rest of the line is kept byte for byte.
+pub fn rewrite_cwd(line: &str, mappings: &[PathMapping]) -> Option<String> {
+    if mappings.is_empty() || !line.contains("\"cwd\"") {
+        return None;
+    }
+    let record: Value = serde_json::from_str(line).ok()?;
+    let mapped = map_path(record.get("cwd")?.as_str()?, mappings)?;
+    let span = top_level_string(line, "cwd")?;
+    Some(format!(
+        "{}{}{}",
+        &line[..span.start],
+        serde_json::to_string(&mapped).ok()?,
+        &line[span.end..]
+    ))
+}
+
+/// Byte range, quotes included, of the string value of `key` in the
+/// top-level object of the JSON text `line`. Keys of nested objects are
+/// skipped.
+fn top_level_string(line: &str, key: &str) -> Option<std::ops::Range<usize>> {
+    let bytes = line.as_bytes();
+    let skip_space = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    };
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let end = string_end(bytes, i)?;
+                let colon = skip_space(end);
+                if depth == 1
+                    && bytes.get(colon) == Some(&b':')
+                    && serde_json::from_str::<String>(&line[i..end]).ok()? == key
+                {
+                    let start = skip_space(colon + 1);
+                    if bytes.get(start) != Some(&b'"') {
+                        return None;
+                    }
+                    return Some(start..string_end(bytes, start)?);
+                }
+                i = end;
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Index just past the closing quote of the JSON string starting at `start`.
+fn string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Rewrite the `cwd` of every record in a JSONL file's contents, returning
+/// the new contents and how many records changed.
+pub(crate) fn rewrite_jsonl(contents: Vec<u8>, mappings: &[PathMapping]) -> (Vec<u8>, usize) {
+    let text = match String::from_utf8(contents) {
+        Ok(text) => text,
+        Err(e) => return (e.into_bytes(), 0),
+    };
+    let mut rewritten = 0;
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| match rewrite_cwd(line, mappings) {
+            Some(new) => {
+                rewritten += 1;
+                new
+            }
+            None => line.to_string(),
+        })
+        .collect();
+    (lines.join("\n").into_bytes(), rewritten)
+}
+
+/// What `import_bundle` installed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportSummary {
+    pub session_id: String,
+    /// The working directory after `--map-path`.
+    pub project_dir: String,
+    /// The installed transcript.
+    pub session_path: PathBuf,
+    pub files: usize,
+    /// Records whose `cwd` was rewritten.
+    pub rewritten: usize,
+}
+
+/// Verify a bundle and install its files under `~/.claude/`.
+///
+/// Nothing is written until every file has been checked against the
+/// manifest, and each file is replaced atomically. The transcript goes into
+/// the project folder for its (mapped) working directory. An existing copy
+/// of the session anywhere under `~/.claude/projects/` is an error unless
+/// `force` is set, in which case copies in other project folders are removed
+/// once the new files are in place, and the session's `<id>/` directory in
+/// the target folder is replaced as a whole rather than merged.
+pub fn import_bundle(
+    home_dir: &str,
+    bundle: &Path,
+    mappings: &[PathMapping],
+    force: bool,
+) -> Result<ImportSummary> {
+    let (manifest, mut contents) = read_bundle(bundle)?;
+    let invalid = |reason: String| CcseshError::InvalidBundle { reason };
+
+    let project_dir =
+        map_path(&manifest.project_dir, mappings).unwrap_or_else(|| manifest.project_dir.clone());
+    let folder = if project_dir.is_empty() {
+        manifest.project_folder.clone()
+    } else {
+        project_folder_name(&project_dir)
+    };
+    if folder.is_empty() || folder.contains(['/', '\\']) || folder == "." || folder == ".." {
+        return Err(invalid(format!("bad project folder '{}'", folder)).into());
+    }
+
+    let claude_dir = Path::new(home_dir).join(".claude");
+    let id = &manifest.session_id;
+    let session_path = claude_dir
+        .join("projects")
+        .join(&folder)
+        .join(format!("{}.jsonl", id));
+    let existing = existing_sessions(&claude_dir, id);
+    if !force && let Some(path) = existing.first() {
+        return Err(CcseshError::SessionExists { path: path.clone() }.into());
+    }
+
+    let mut rewritten = 0;
+    let mut plan = Vec::new();
+    for file in &manifest.files {
+        let destination = destination(&claude_dir, &folder, id, &file.path)?;
+        let mut data = contents.remove(&file.path).unwrap_or_default();
+        if file.path.starts_with("projects/") && file.path.ends_with(".jsonl") {
+            let (new, count) = rewrite_jsonl(data, mappings);
+            data = new;
+            rewritten += count;
+        }
+        plan.push((destination, data, file.mtime));
+    }
+
+    // Set the old subagent directory aside so files the bundle no longer has
+    // do not linger, and put it back if a write fails.
+    let session_dir = session_path.with_extension("");
+    let replaced = session_dir.with_extension(format!("replaced-{}", std::process::id()));
+    let set_aside = force && session_dir.is_dir();
+    if set_aside {
+        fs::rename(&session_dir, &replaced)
+            .with_context(|| format!("Failed to move {} aside", session_dir.display()))?;
+    }
+    let written = plan.iter().try_for_each(|(destination, data, mtime)| {
+        let modified = UNIX_EPOCH + Duration::from_secs(*mtime);
+        write_atomic(destination, data, None, modified)
+            .with_context(|| format!("Failed to write {}", destination.display()))
+    });
+    if set_aside {
+        if written.is_err() {
+            let _ = fs::remove_dir_all(&session_dir);
+            let _ = fs::rename(&replaced, &session_dir);
+        } else {
+            fs::remove_dir_all(&replaced)
+                .with_context(|| format!("Failed to remove {}", replaced.display()))?;
+        }
+    }
+    written?;
+
+    // A copy in another project folder would list the session twice.
+    for old in existing.iter().filter(|p| **p != session_path) {
+        fs::remove_file(old).with_context(|| format!("Failed to remove {}", old.display()))?;
+        let old_dir = old.with_extension("");
+        if old_dir.is_dir() {
+            fs::remove_dir_all(&old_dir)
+                .with_context(|| format!("Failed to remove {}", old_dir.display()))?;
+        }
+    }
+
+    Ok(ImportSummary {
+        session_id: id.clone(),
+        project_dir,
+        session_path,
+        files: plan.len(),
+        rewritten,
+    })
+}
+
+/// Read and verify a bundle: the manifest, then every file against its size
+/// and checksum. Files missing from the manifest, or listed but absent, make
+/// the bundle invalid.
+fn read_bundle(bundle: &Path) -> Result<(Manifest, BTreeMap<String, Vec<u8>>)> {
+    let invalid = |reason: String| CcseshError::InvalidBundle { reason };
+    let file = fs::File::open(bundle).map_err(|source| CcseshError::SessionReadError {
+        path: bundle.to_path_buf(),
+        source,
+    })?;
+
+    let mut manifest: Option<Manifest> = None;
+    let mut contents = BTreeMap::new();
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let not_a_bundle = |e: std::io::Error| invalid(format!("not a ccsesh bundle ({})", e));
+    for entry in archive.entries().map_err(not_a_bundle)? {
+        let mut entry = entry.map_err(not_a_bundle)?;
+        let path = entry
+            .path()
+            .ok()
+            .and_then(|p| p.to_str().map(str::to_string))
+            .ok_or_else(|| invalid("entry with a non-UTF-8 name".into()))?;
+        if entry.header().entry_type() != tar::EntryType::Regular {
+            return Err(invalid(format!("'{}' is not a regular file", path)).into());
+        }
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).map_err(not_a_bundle)?;
+
+        if path == MANIFEST_PATH {
+            manifest = Some(
+                serde_json::from_slice(&data)
+                    .map_err(|e| invalid(format!("bad manifest.json ({})", e)))?,
+            );
+        } else if contents.insert(path.clone(), data).is_some() {
+            return Err(invalid(format!("'{}' appears twice", path)).into());
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| invalid("missing manifest.json".into()))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(invalid(format!("unknown format '{}'", manifest.format)).into());
+    }
+    if manifest.version > BUNDLE_VERSION {
+        return Err(invalid(format!(
+            "made by a newer ccsesh (bundle version {}, this build reads {})",
+            manifest.version, BUNDLE_VERSION
+        ))
+        .into());
+    }
+    if !is_valid_uuid(&manifest.session_id) {
+        return Err(invalid(format!("bad session ID '{}'", manifest.session_id)).into());
+    }
+
+    for file in &manifest.files {
+        let data = contents
+            .get(&file.path)
+            .ok_or_else(|| invalid(format!("'{}' is listed but missing", file.path)))?;
+        if data.len() as u64 != file.size || sha256_hex(data) != file.sha256 {
+            return Err(invalid(format!("checksum mismatch for '{}'", file.path)).into());
+        }
+    }
+    if let Some(extra) = contents
+        .keys()
+        .find(|path| !manifest.files.iter().any(|f| &f.path == *path))
+    {
+        return Err(invalid(format!("'{}' is not in the manifest", extra)).into());
+    }
+    let transcript = format!("projects/{}.jsonl", manifest.session_id);
+    if !contents.contains_key(&transcript) {
+        return Err(invalid(format!("missing '{}'", transcript)).into());
+    }
+    Ok((manifest, contents))
+}
+
+/// Where an archive path is installed. Only the layouts `create_bundle`
+/// writes are accepted, and each must belong to session `id`, so a crafted
+/// bundle cannot write outside its own session's files.
+fn destination(claude_dir: &Path, folder: &str, id: &str, path: &str) -> Result<PathBuf> {
+    let invalid = || CcseshError::InvalidBundle {
+        reason: format!("unexpected path '{}'", path),
+    };
+    let parts: Vec<&str> = path.split('/').collect();
+    if parts
+        .iter()
+        .any(|p| p.is_empty() || *p == "." || *p == ".." || p.contains('\\'))
+    {
+        return Err(invalid().into());
+    }
+    let rest = || parts[1..].iter().collect::<PathBuf>();
+    let ok = match parts.as_slice() {
+        ["projects", name] => *name == format!("{}.jsonl", id),
+        ["projects", dir, _, ..] => *dir == id,
+        ["todos", name] => name.starts_with(&format!("{}-", id)) && name.ends_with(".json"),
+        ["file-history", dir, _, ..] => *dir == id,
+        _ => false,
+    };
+    if !ok {
+        return Err(invalid().into());
+    }
+    Ok(match parts[0] {
+        "projects" => claude_dir.join("projects").join(folder).join(rest()),
+        root => claude_dir.join(root).join(rest()),
+    })
+}
+
+/// The transcripts of session `id` in every project folder.
+fn existing_sessions(claude_dir: &Path, id: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(claude_dir.join("projects")) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().join(format!("{}.jsonl", id)))
+        .filter(|p| p.exists())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "eb53d999-8692-42ce-a376-4f82206a086d";
+
+    /// A home with one session in `/home/u/dev/app`, plus a subagent
+    /// transcript, a todo list, and a file-history snapshot.
+    fn source_home() -> (assert_fs::TempDir, SessionInfo) {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let claude = tmp.path().join(".claude");
+        let project = claude.join("projects").join("-home-u-dev-app");
+        fs::create_dir_all(project.join(ID).join("subagents")).unwrap();
+        fs::write(
+            project.join(format!("{}.jsonl", ID)),
+            format!(
+                "{{\"type\":\"user\",\"cwd\":\"/home/u/dev/app\",\"sessionId\":\"{}\",\"message\":{{\"content\":\"hi\"}}}}\n\
+                 {{\"type\":\"user\",\"cwd\":\"/home/u/dev/app/sub\",\"message\":{{\"content\":\"/home/u/dev/app stays\"}}}}\n",
+                ID
+            ),
+        )
+        .unwrap();
+        fs::write(
+            project.join(ID).join("subagents").join("agent-1.jsonl"),
+            "{\"cwd\":\"/home/u/dev/app\"}\n",
+        )
+        .unwrap();
+        fs::create_dir_all(claude.join("todos")).unwrap();
+        fs::write(
+            claude
+                .join("todos")
+                .join(format!("{}-agent-{}.json", ID, ID)),
+            "[]",
+        )
+        .unwrap();
+        fs::write(claude.join("todos").join("other-agent.json"), "[]").unwrap();
+        fs::create_dir_all(claude.join("file-history").join(ID)).unwrap();
+        fs::write(claude.join("file-history").join(ID).join("abc@v1"), "old").unwrap();
+
+        let session = SessionInfo {
+            path: project.join(format!("{}.jsonl", ID)),
+            project_dir_display: "~/dev/app".to_string(),
+            first_prompt: Some("hi".to_string()),
+            ..SessionInfo::fixture(ID, "/home/u/dev/app")
+        };
+        (tmp, session)
+    }
+
+    fn mapping(spec: &str) -> PathMapping {
+        PathMapping::parse(spec).unwrap()
+    }
+
+    #[test]
+    fn bundle_round_trip_with_path_mapping() {
+        let (source, session) = source_home();
+        let bundle = source.path().join("s.ccsesh");
+        let manifest = create_bundle(source.path().to_str().unwrap(), &session, &bundle).unwrap();
+        assert_eq!(
+            manifest
+                .files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                format!("projects/{}.jsonl", ID).as_str(),
+                &format!("projects/{}/subagents/agent-1.jsonl", ID),
+                &format!("todos/{}-agent-{}.json", ID, ID),
+                &format!("file-history/{}/abc@v1", ID),
+            ]
+        );
+        assert_eq!(manifest.project_folder, "-home-u-dev-app");
+
+        let target = assert_fs::TempDir::new().unwrap();
+        let summary = import_bundle(
+            target.path().to_str().unwrap(),
+            &bundle,
+            &[mapping("/home/u/dev=/Users/u/src")],
+            false,
+        )
+        .unwrap();
+        assert_eq!(summary.project_dir, "/Users/u/src/app");
+        assert_eq!(summary.files, 4);
+        assert_eq!(summary.rewritten, 3);
+
+        let claude = target.path().join(".claude");
+        let project = claude.join("projects").join("-Users-u-src-app");
+        assert_eq!(summary.session_path, project.join(format!("{}.jsonl", ID)));
+        let transcript = fs::read_to_string(&summary.session_path).unwrap();
+        assert!(
+            transcript.starts_with("{\"type\":\"user\",\"cwd\":\"/Users/u/src/app\",\"sessionId\"")
+        );
+        assert!(transcript.contains("\"cwd\":\"/Users/u/src/app/sub\""));
+        // Only the cwd field is rewritten.
+        assert!(transcript.contains("\"/home/u/dev/app stays\""));
+        assert!(
+            project
+                .join(ID)
+                .join("subagents")
+                .join("agent-1.jsonl")
+                .is_file()
+        );
+        assert!(
+            claude
+                .join("file-history")
+                .join(ID)
+                .join("abc@v1")
+                .is_file()
+        );
+        assert!(!claude.join("todos").join("other-agent.json").exists());
+
+        // Importing again is refused unless forced.
+        let err = import_bundle(target.path().to_str().unwrap(), &bundle, &[], false).unwrap_err();
+        assert!(err.to_string().starts_with("Session already exists at"));
+        // Forced into another folder, the session moves there.
+        let forced = import_bundle(target.path().to_str().unwrap(), &bundle, &[], true).unwrap();
+        assert!(forced.session_path.is_file());
+        assert!(!summary.session_path.exists());
+        assert!(!project.join(ID).exists());
+        assert!(
+            forced
+                .session_path
+                .with_extension("")
+                .join("subagents")
+                .join("agent-1.jsonl")
+                .is_file()
+        );
+
+        // Forced into the same folder, files the bundle lacks are dropped.
+        let subagents = forced.session_path.with_extension("").join("subagents");
+        fs::write(subagents.join("agent-stale.jsonl"), "{}\n").unwrap();
+        let again = import_bundle(target.path().to_str().unwrap(), &bundle, &[], true).unwrap();
+        assert_eq!(again.session_path, forced.session_path);
+        assert!(subagents.join("agent-1.jsonl").is_file());
+        assert!(!subagents.join("agent-stale.jsonl").exists());
+        let leftovers: Vec<_> = fs::read_dir(forced.session_path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers.len(), 2, "{:?}", leftovers);
+    }
+
+    #[test]
+    fn import_restores_mtime() {
+        let (source, session) = source_home();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_771_147_800);
+        fs::File::options()
+            .write(true)
+            .open(&session.path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        let bundle = source.path().join("s.ccsesh");
+        create_bundle(source.path().to_str().unwrap(), &session, &bundle).unwrap();
+
+        let target = assert_fs::TempDir::new().unwrap();
+        let summary = import_bundle(target.path().to_str().unwrap(), &bundle, &[], false).unwrap();
+        assert_eq!(
+            fs::metadata(&summary.session_path)
+                .unwrap()
+                .modified()
+                .unwrap(),
+            mtime
+        );
+        assert!(
+            summary
+                .session_path
+                .ends_with(format!("-home-u-dev-app/{}.jsonl", ID))
+        );
+    }
+
+    /// Write a bundle by hand: `manifest` plus raw `(path, contents)` entries.
+    fn craft_bundle(path: &Path, manifest: &Manifest, entries: &[(&str, &[u8])]) {
+        let file = fs::File::create(path).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        append(
+            &mut builder,
+            MANIFEST_PATH,
+            &serde_json::to_vec(manifest).unwrap(),
+            0,
+        )
+        .unwrap();
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o600);
+            // `append_data` refuses `..`; write the name directly.
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        builder
+            .into_inner()
+            .unwrap()
+            .finish()
+            .unwrap()
+            .flush()
+            .unwrap();
+    }
+
+    fn manifest_for(entries: &[(&str, &[u8])]) -> Manifest {
+        Manifest {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            session_id: ID.to_string(),
+            project_dir: "/home/u/app".to_string(),
+            project_folder: "-home-u-app".to_string(),
+            slug: None,
+            last_active: String::new(),
+            created_at: String::new(),
+            created_by: String::new(),
+            files: entries
+                .iter()
+                .map(|(path, data)| ManifestFile {
+                    path: path.to_string(),
+                    size: data.len() as u64,
+                    sha256: sha256_hex(data),
+                    mtime: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_tampered_and_unsafe_bundles() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let home = tmp.path().join("home");
+        let bundle = tmp.path().join("b.ccsesh");
+        let transcript = format!("projects/{}.jsonl", ID);
+        let good: (&str, &[u8]) = (&transcript, b"{}\n");
+        let import = || import_bundle(home.to_str().unwrap(), &bundle, &[], false);
+
+        // Contents changed after the manifest was written.
+        let mut manifest = manifest_for(&[good]);
+        craft_bundle(&bundle, &manifest, &[(&transcript, b"{\"x\":1}\n")]);
+        assert!(
+            import()
+                .unwrap_err()
+                .to_string()
+                .contains("checksum mismatch")
+        );
+
+        // Paths outside the session's own files.
+        let escape = format!("projects/{}/../../../../.bashrc", ID);
+        for bad in [
+            escape.as_str(),
+            "todos/other-session.json",
+            "settings.json",
+            "projects/-other/x.jsonl",
+        ] {
+            let entries: [(&str, &[u8]); 2] = [good, (bad, b"x")];
+            craft_bundle(&bundle, &manifest_for(&entries), &entries);
+            let err = import().unwrap_err().to_string();
+            assert!(err.contains("unexpected path"), "{}: {}", bad, err);
+        }
+
+        // A file the manifest does not list.
+        craft_bundle(&bundle, &manifest, &[good, ("todos/extra.json", b"[]")]);
+        assert!(
+            import()
+                .unwrap_err()
+                .to_string()
+                .contains("not in the manifest")
+        );
+
+        manifest.session_id = "../../etc".to_string();
+        craft_bundle(&bundle, &manifest, &[good]);
+        assert!(import().unwrap_err().to_string().contains("bad session ID"));
+
+        fs::write(&bundle, "not a tarball").unwrap();
+        assert!(
+            import()
+                .unwrap_err()
+                .to_string()
+                .starts_with("Invalid bundle")
+        );
+
+        // Nothing was installed by any of the above.
+        assert!(!home.join(".claude").exists());
+    }
+
+    #[test]
+    fn path_mappings() {
+        let m = mapping("/home/u/dev/=/Users/u/src");
+        assert_eq!(m.apply("/home/u/dev"), Some("/Users/u/src".to_string()));
+        assert_eq!(
+            m.apply("/home/u/dev/app"),
+            Some("/Users/u/src/app".to_string())
+        );
+        assert_eq!(m.apply("/home/u/devtools"), None);
+        for bad in ["/a", "a=/b", "/a=b", "/=/b", ""] {
+            assert!(PathMapping::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn rewrite_cwd_keeps_spacing_and_nested_copies() {
+        let mappings = [mapping("/a=/b")];
+        assert_eq!(
+            rewrite_cwd(r#"{"cwd": "/a/x", "n": 1.50}"#, &mappings).unwrap(),
+            r#"{"cwd": "/b/x", "n": 1.50}"#
+        );
+        assert_eq!(
+            rewrite_cwd(
+                r#"{"result": {"cwd": "/a/x"}, "say": "\"cwd\": \u00e9", "cwd" : "/a/x"}"#,
+                &mappings
+            )
+            .unwrap(),
+            r#"{"result": {"cwd": "/a/x"}, "say": "\"cwd\": \u00e9", "cwd" : "/b/x"}"#
+        );
+        assert_eq!(rewrite_cwd(r#"{"cwd":"/c"}"#, &mappings), None);
+        assert_eq!(rewrite_cwd("not json \"cwd\"", &mappings), None);
+    }
+}
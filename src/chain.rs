@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::types::SessionInfo;
+
+/// Groups sessions into continuation chains.
+///
+/// Resuming a conversation with `--continue` / `--resume`, or carrying it over
+/// after compaction, starts a new JSONL file that points back at the old one
+/// through `ContinuationRefs`. Sessions are pushed newest first (listing
+/// order), so a chain's newest session is seen first and becomes its head;
+/// each older session is either claimed by a head that references it or starts
+/// a chain of its own.
+#[derive(Debug, Default)]
+pub struct ChainLinker {
+    heads: Vec<SessionInfo>,
+    /// Session ID referenced by a chain -> index of its head.
+    wanted_ids: HashMap<String, usize>,
+    /// Record uuid referenced by a chain -> project folder it should be in,
+    /// and index of the chain's head.
+    wanted_uuids: HashMap<String, (PathBuf, usize)>,
+}
+
+impl ChainLinker {
+    /// Number of chains so far.
+    pub fn chain_count(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// Whether any chain still references a session that has not been seen.
+    pub fn has_pending(&self) -> bool {
+        !self.wanted_ids.is_empty() || !self.wanted_uuids.is_empty()
+    }
+
+    /// Add the next-older session. It joins the chain that references it, or
+    /// else starts a new chain if `allow_new_chain` is set. Returns whether the
+    /// session was kept.
+    pub fn push(&mut self, session: SessionInfo, allow_new_chain: bool) -> bool {
+        let head = match self
+            .wanted_ids
+            .remove(&session.session_id)
+            .or_else(|| self.claim_by_uuid(&session.path))
+        {
+            Some(head) => head,
+            None if allow_new_chain => {
+                self.heads.push(SessionInfo {
+                    earlier: vec![],
+                    ..session.clone()
+                });
+                self.heads.len() - 1
+            }
+            None => return false,
+        };
+
+        self.want(&session, head);
+        if self.heads[head].session_id != session.session_id {
+            self.heads[head].earlier.insert(0, session);
+        }
+        true
+    }
+
+    /// The chains, one entry per chain: its newest session, with the older
+    /// members in `earlier`.
+    pub fn finish(self) -> Vec<SessionInfo> {
+        self.heads
+    }
+
+    fn want(&mut self, session: &SessionInfo, head: usize) {
+        let chain = &self.heads[head];
+        let in_chain = |id: &String| {
+            chain.session_id == *id || chain.earlier.iter().any(|s| s.session_id == *id)
+        };
+        let ids: Vec<String> = session
+            .continuation
+            .session_ids
+            .iter()
+            .filter(|id| !in_chain(id))
+            .cloned()
+            .collect();
+        for id in ids {
+            self.wanted_ids.entry(id).or_insert(head);
+        }
+        let folder = project_folder(&session.path);
+        for uuid in &session.continuation.uuids {
+            self.wanted_uuids
+                .entry(uuid.clone())
+                .or_insert_with(|| (folder.clone(), head));
+        }
+    }
+
+    /// If a chain wants a uuid that is defined in the file at `path`, remove
+    /// that want and return the chain's head. Only files in the same project
+    /// folder as the referencing session are read.
+    fn claim_by_uuid(&mut self, path: &Path) -> Option<usize> {
+        let folder = project_folder(path);
+        let wanted: Vec<&str> = self
+            .wanted_uuids
+            .iter()
+            .filter(|(_, (f, _))| *f == folder)
+            .map(|(uuid, _)| uuid.as_str())
+            .collect();
+        if wanted.is_empty() {
+            return None;
+        }
+
+        let found = find_uuid(path, &wanted)?.to_string();
+        self.wanted_uuids.remove(&found).map(|(_, head)| head)
+    }
+}
+
+/// Undo the grouping: every chain member becomes its own entry, right after
+/// its chain's head and newest first. Each entry's `earlier` lists the members
+/// older than it, so the "continued" count still reads correctly.
+pub fn expand(sessions: Vec<SessionInfo>) -> Vec<SessionInfo> {
+    let mut out = Vec::new();
+    for head in sessions {
+        let earlier = head.earlier.clone();
+        out.push(head);
+        for i in (0..earlier.len()).rev() {
+            out.push(SessionInfo {
+                earlier: earlier[..i].to_vec(),
+                ..earlier[i].clone()
+            });
+        }
+    }
+    out
+}
+
+fn project_folder(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// The first of `uuids` that some record in the file at `path` is keyed by.
+/// A plain substring check on each line keeps this cheap for long files.
+fn find_uuid<'a>(path: &Path, uuids: &[&'a str]) -> Option<&'a str> {
+    let file = std::fs::File::open(path).ok()?;
+    let needles: Vec<(String, &str)> = uuids
+        .iter()
+        .map(|uuid| (format!("\"uuid\":\"{}\"", uuid), *uuid))
+        .collect();
+    for line in std::io::BufReader::new(file).lines() {
+        let Ok(line) = line else { continue };
+        if let Some((_, uuid)) = needles.iter().find(|(needle, _)| line.contains(needle)) {
+            return Some(uuid);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContinuationRefs;
+    use std::fs;
+
+    fn session(dir: &Path, id: &str, refs: ContinuationRefs, lines: &[&str]) -> SessionInfo {
+        let path = dir.join(format!("{}.jsonl", id));
+        fs::write(&path, lines.join("\n")).unwrap();
+        SessionInfo {
+            path,
+            first_prompt: Some(format!("prompt {}", id)),
+            continuation: refs,
+            ..SessionInfo::fixture(id, "/p")
+        }
+    }
+
+    fn link(sessions: Vec<SessionInfo>) -> Vec<SessionInfo> {
+        let mut linker = ChainLinker::default();
+        for session in sessions {
+            linker.push(session, true);
+        }
+        linker.finish()
+    }
+
+    fn ids(sessions: &[SessionInfo]) -> Vec<&str> {
+        sessions.iter().map(|s| s.session_id.as_str()).collect()
+    }
+
+    fn by_session(id: &str) -> ContinuationRefs {
+        ContinuationRefs {
+            session_ids: vec![id.to_string()],
+            uuids: vec![],
+        }
+    }
+
+    fn by_uuid(uuid: &str) -> ContinuationRefs {
+        ContinuationRefs {
+            session_ids: vec![],
+            uuids: vec![uuid.to_string()],
+        }
+    }
+
+    #[test]
+    fn links_by_session_id_and_uuid() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let sessions = vec![
+            session(tmp.path(), "c", by_uuid("u-b"), &[]),
+            session(tmp.path(), "x", ContinuationRefs::default(), &[]),
+            session(tmp.path(), "b", by_session("a"), &[r#"{"uuid":"u-b"}"#]),
+            session(tmp.path(), "a", ContinuationRefs::default(), &[]),
+        ];
+
+        let chains = link(sessions);
+        assert_eq!(ids(&chains), vec!["c", "x"]);
+        assert_eq!(ids(&chains[0].earlier), vec!["a", "b"]);
+        assert!(chains[1].earlier.is_empty());
+    }
+
+    #[test]
+    fn uuid_refs_only_match_the_same_project_folder() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let other = tmp.path().join("other");
+        fs::create_dir_all(&other).unwrap();
+        let sessions = vec![
+            session(tmp.path(), "b", by_uuid("u-a"), &[]),
+            session(
+                &other,
+                "a",
+                ContinuationRefs::default(),
+                &[r#"{"uuid":"u-a"}"#],
+            ),
+        ];
+        assert_eq!(ids(&link(sessions)), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn push_without_new_chains_only_keeps_members() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let mut linker = ChainLinker::default();
+        assert!(linker.push(session(tmp.path(), "b", by_session("a"), &[]), true));
+        assert!(linker.has_pending());
+        assert!(!linker.push(
+            session(tmp.path(), "x", ContinuationRefs::default(), &[]),
+            false
+        ));
+        assert!(linker.push(
+            session(tmp.path(), "a", ContinuationRefs::default(), &[]),
+            false
+        ));
+        assert!(!linker.has_pending());
+        assert_eq!(linker.chain_count(), 1);
+    }
+
+    #[test]
+    fn self_and_cyclic_references_terminate() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let sessions = vec![
+            session(tmp.path(), "a", by_session("b"), &[]),
+            session(tmp.path(), "b", by_session("a"), &[]),
+        ];
+        let mut linker = ChainLinker::default();
+        for session in sessions {
+            linker.push(session, true);
+        }
+        assert!(!linker.has_pending());
+        let chains = linker.finish();
+        assert_eq!(ids(&chains), vec!["a"]);
+        assert_eq!(ids(&chains[0].earlier), vec!["b"]);
+    }
+
+    #[test]
+    fn expand_lists_members_newest_first() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let sessions = vec![
+            session(tmp.path(), "c", by_session("b"), &[]),
+            session(tmp.path(), "b", by_session("a"), &[]),
+            session(tmp.path(), "a", ContinuationRefs::default(), &[]),
+        ];
+        let expanded = expand(link(sessions));
+        assert_eq!(ids(&expanded), vec!["c", "b", "a"]);
+        assert_eq!(
+            expanded.iter().map(|s| s.earlier.len()).collect::<Vec<_>>(),
+            vec![2, 1, 0]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::errors::CcseshError;
+use crate::hyperlink::HyperlinkMode;
+use crate::theme::ColorMode;
+use crate::timefmt::TimeMode;
+
+/// User configuration loaded from `~/.config/ccsesh/config.toml`.
+///
+/// Every field is optional; a missing config file is equivalent to an empty one.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Resume command template (see `shell::ResumeTemplate`).
+    pub resume_template: Option<String>,
+    /// Named output templates for `--template <name>` (see
+    /// `template::OutputTemplate`).
+    pub templates: BTreeMap<String, String>,
+    /// When to emit terminal hyperlinks; `--hyperlinks` overrides it.
+    pub hyperlinks: Option<HyperlinkMode>,
+    /// URL template for session links (see `hyperlink::SessionUrlTemplate`).
+    pub session_url: Option<String>,
+    /// When to colour output; `--color` overrides it.
+    pub color: Option<ColorMode>,
+    /// Named colour theme (see `theme::Theme::named`).
+    pub theme: Option<String>,
+    /// Per-element style overrides, e.g. `path = "blue underline"`.
+    pub colors: BTreeMap<String, String>,
+    /// Relative, absolute, or both; `--time` overrides it.
+    pub time: Option<TimeMode>,
+    /// strftime format for absolute times (see `timefmt::DEFAULT_TIME_FORMAT`).
+    pub time_format: Option<String>,
+    /// Zone for absolute times; `--tz` overrides it.
+    pub timezone: Option<String>,
+    /// Show absolute times for sessions older than this age, e.g. `7d`.
+    pub absolute_after: Option<String>,
+    /// Extra regexes whose matches `--redact` replaces (see
+    /// `redact::Redactor`).
+    pub redact_patterns: Vec<String>,
+}
+
+/// Resolve the config file path. `$CCSESH_CONFIG` overrides the default location.
+pub fn config_path(home_dir: &str) -> PathBuf {
+    match std::env::var_os("CCSESH_CONFIG") {
+        Some(p) if !p.is_empty() => PathBuf::from(p),
+        _ => Path::new(home_dir)
+            .join(".config")
+            .join("ccsesh")
+            .join("config.toml"),
+    }
+}
+
+/// Load the config file, returning `Config::default()` when it does not exist.
+pub fn load_config(home_dir: &str) -> Result<Config> {
+    load_config_from(&config_path(home_dir))
+}
+
+pub fn load_config_from(path: &Path) -> Result<Config> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => {
+            return Err(CcseshError::ConfigReadError {
+                path: path.to_path_buf(),
+                source: e,
+            }
+            .into());
+        }
+    };
+
+    toml::from_str(&contents).map_err(|e| {
+        CcseshError::ConfigParseError {
+            path: path.to_path_buf(),
+            detail: e.message().to_string(),
+        }
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_is_default() {
+        let config = load_config_from(Path::new("/nonexistent/ccsesh/config.toml")).unwrap();
+        assert!(config.resume_template.is_none());
+    }
+
+    #[test]
+    fn parses_resume_template() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(&path, "resume_template = \"npx claude --resume {id}\"\n").unwrap();
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(
+            config.resume_template.as_deref(),
+            Some("npx claude --resume {id}")
+        );
+    }
+
+    #[test]
+    fn parses_named_templates() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[templates]\nstatus = \"{index} {prompt:30}\"\nids = \"{id}\"\n",
+        )
+        .unwrap();
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.templates.len(), 2);
+        assert_eq!(config.templates["status"], "{index} {prompt:30}");
+    }
+
+    #[test]
+    fn parses_hyperlink_settings() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "hyperlinks = \"auto\"\nsession_url = \"http://localhost:7777/sessions/{id}\"\n",
+        )
+        .unwrap();
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.hyperlinks, Some(HyperlinkMode::Auto));
+        assert_eq!(
+            config.session_url.as_deref(),
+            Some("http://localhost:7777/sessions/{id}")
+        );
+    }
+
+    #[test]
+    fn parses_color_settings() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "color = \"always\"\ntheme = \"light\"\n[colors]\npath = \"blue\"\n",
+        )
+        .unwrap();
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.color, Some(ColorMode::Always));
+        assert_eq!(config.theme.as_deref(), Some("light"));
+        assert_eq!(config.colors["path"], "blue");
+    }
+
+    #[test]
+    fn parses_time_settings() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "time = \"both\"\ntime_format = \"%d.%m. %H:%M\"\ntimezone = \"utc\"\nabsolute_after = \"7d\"\n",
+        )
+        .unwrap();
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.time, Some(TimeMode::Both));
+        assert_eq!(config.time_format.as_deref(), Some("%d.%m. %H:%M"));
+        assert_eq!(config.timezone.as_deref(), Some("utc"));
+        assert_eq!(config.absolute_after.as_deref(), Some("7d"));
+    }
+
+    #[test]
+    fn parses_redact_patterns() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "redact_patterns = ['ACME-\\d+', 'internal\\.example\\.com']\n",
+        )
+        .unwrap();
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(
+            config.redact_patterns,
+            vec![
+                r"ACME-\d+".to_string(),
+                r"internal\.example\.com".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_toml_is_error() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(&path, "resume_template = [unterminated").unwrap();
+        let err = load_config_from(&path).unwrap_err();
+        assert!(err.to_string().contains("Failed to parse config file"));
+    }
+}
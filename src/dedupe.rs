@@ -0,0 +1,523 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::display;
+use crate::errors::CcseshError;
+use crate::parse;
+use crate::timefmt::TimeDisplay;
+use crate::types::SessionInfo;
+
+/// Bigram similarity from which two first prompts count as the same.
+pub const SIMILARITY_THRESHOLD: f64 = 0.85;
+
+/// Directory under `~/.claude` that `ccsesh dedupe` moves duplicates to.
+/// It mirrors `projects/`, and being outside it hides them from Claude Code
+/// and ccsesh alike.
+pub const ARCHIVE_DIR: &str = "ccsesh-archive";
+
+/// Longest first prompt shown by `dedupe` before it is truncated.
+const TITLE_WIDTH: usize = 60;
+
+/// A first prompt as compared: markup stripped, whitespace collapsed, and
+/// lowercased.
+pub fn normalize_prompt(prompt: &str) -> String {
+    parse::strip_xml_tags(prompt).to_lowercase()
+}
+
+/// Sørensen–Dice coefficient of the character bigrams of `a` and `b`: 1.0
+/// for equal strings, 0.0 for ones with no bigram in common. Strings too
+/// short to have a bigram only match themselves.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    dice(&bigrams(a), &bigrams(b))
+}
+
+/// The character bigrams of `s`, sorted.
+fn bigrams(s: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut pairs: Vec<(char, char)> = chars.windows(2).map(|w| (w[0], w[1])).collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Dice coefficient of two sorted bigram lists, repeats included.
+fn dice(a: &[(char, char)], b: &[(char, char)]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    2.0 * shared as f64 / (a.len() + b.len()) as f64
+}
+
+/// A row's normalized prompt and its bigrams, computed once.
+#[derive(Debug)]
+struct RowPrompt {
+    text: String,
+    bigrams: Vec<(char, char)>,
+}
+
+impl RowPrompt {
+    fn matches(&self, other: &RowPrompt) -> bool {
+        if self.text == other.text {
+            return true;
+        }
+        // Dice can be no higher than this, so most pairs are ruled out by
+        // length alone.
+        let (a, b) = (self.bigrams.len(), other.bigrams.len());
+        2.0 * a.min(b) as f64 / (a + b).max(1) as f64 >= SIMILARITY_THRESHOLD
+            && dice(&self.bigrams, &other.bigrams) >= SIMILARITY_THRESHOLD
+    }
+}
+
+/// Groups listing entries by project and first prompt. Entries are added
+/// newest first, so each group's row is its newest session.
+#[derive(Debug, Default)]
+pub struct Collapser {
+    /// Project directory and prompt of each row.
+    rows: Vec<(PathBuf, Option<RowPrompt>)>,
+}
+
+impl Collapser {
+    /// Number of rows so far.
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Add the next-older entry. Returns the index of the row it duplicates,
+    /// or `None` when it becomes a row of its own. Sessions without a prompt
+    /// are never duplicates.
+    pub fn add(&mut self, project_dir: &Path, first_prompt: Option<&str>) -> Option<usize> {
+        let prompt = first_prompt
+            .map(normalize_prompt)
+            .filter(|p| !p.is_empty())
+            .map(|text| RowPrompt {
+                bigrams: bigrams(&text),
+                text,
+            });
+        if let Some(prompt) = &prompt {
+            let found = self.rows.iter().position(|(dir, row)| {
+                dir == project_dir && row.as_ref().is_some_and(|row| row.matches(prompt))
+            });
+            if found.is_some() {
+                return found;
+            }
+        }
+        self.rows.push((project_dir.to_path_buf(), prompt));
+        None
+    }
+}
+
+/// Collapse listing entries (newest first) whose first prompt duplicates a
+/// newer entry's in the same project into that entry's `duplicates`.
+pub fn collapse(sessions: Vec<SessionInfo>) -> Vec<SessionInfo> {
+    let mut collapser = Collapser::default();
+    let mut rows: Vec<SessionInfo> = Vec::new();
+    for session in sessions {
+        match collapser.add(&session.project_dir, session.first_prompt.as_deref()) {
+            Some(row) => rows[row].duplicates.push(session),
+            None => rows.push(session),
+        }
+    }
+    rows
+}
+
+/// Undo `collapse`: every duplicate becomes its own entry again, in
+/// last-active order.
+pub fn expand(sessions: Vec<SessionInfo>) -> Vec<SessionInfo> {
+    let mut out = Vec::new();
+    for mut session in sessions {
+        let duplicates = std::mem::take(&mut session.duplicates);
+        out.push(session);
+        out.extend(duplicates);
+    }
+    out.sort_by_key(|s| std::cmp::Reverse(s.last_active));
+    out
+}
+
+/// A session `ccsesh dedupe` moved, or would move, out of the way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Archived {
+    pub session_id: String,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Where `session` goes in the archive under `home_dir`: the same project
+/// folder name and file name, under `~/.claude/ccsesh-archive/`.
+pub fn archive_path(home_dir: &str, session: &SessionInfo) -> PathBuf {
+    let folder = session
+        .path
+        .parent()
+        .and_then(Path::file_name)
+        .unwrap_or_default();
+    let file = session.path.file_name().unwrap_or_default();
+    Path::new(home_dir)
+        .join(".claude")
+        .join(ARCHIVE_DIR)
+        .join(folder)
+        .join(file)
+}
+
+/// The sessions to archive for the listing `rows`: every duplicate and the
+/// earlier sessions of its continuation chain, skipping open ones unless
+/// `force`. The row itself, the newest attempt, is kept.
+pub fn plan(home_dir: &str, rows: &[SessionInfo], force: bool) -> Vec<Archived> {
+    rows.iter()
+        .flat_map(|row| &row.duplicates)
+        .filter(|duplicate| force || !is_open(duplicate))
+        .flat_map(|duplicate| std::iter::once(duplicate).chain(&duplicate.earlier))
+        .map(|session| Archived {
+            session_id: session.session_id.clone(),
+            from: session.path.clone(),
+            to: archive_path(home_dir, session),
+        })
+        .collect()
+}
+
+/// Whether a process has the session, or an earlier part of its chain, open.
+fn is_open(session: &SessionInfo) -> bool {
+    session.active || session.earlier.iter().any(|s| s.active)
+}
+
+/// Move each transcript in `plan` into the archive, along with its
+/// `<id>/` directory of subagent transcripts if it has one. Every
+/// destination is checked before anything is moved.
+pub fn archive(plan: &[Archived]) -> Result<()> {
+    let session_dir = |path: &Path, id: &str| path.parent().map(|folder| folder.join(id));
+    for item in plan {
+        if item.to.exists() {
+            return Err(CcseshError::SessionExists {
+                path: item.to.clone(),
+            }
+            .into());
+        }
+        if let (Some(from_dir), Some(to_dir)) = (
+            session_dir(&item.from, &item.session_id),
+            session_dir(&item.to, &item.session_id),
+        ) && from_dir.is_dir()
+            && to_dir.exists()
+        {
+            return Err(CcseshError::SessionExists { path: to_dir }.into());
+        }
+    }
+
+    for item in plan {
+        if let Some(parent) = item.to.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let moves = [
+            (item.from.clone(), item.to.clone()),
+            (
+                session_dir(&item.from, &item.session_id).unwrap_or_default(),
+                session_dir(&item.to, &item.session_id).unwrap_or_default(),
+            ),
+        ];
+        for (from, to) in moves {
+            if from.as_os_str().is_empty() || !from.exists() {
+                continue;
+            }
+            fs::rename(&from, &to).with_context(|| {
+                format!("Failed to move {} to {}", from.display(), to.display())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Text report of the duplicate groups in `rows`, marking what was archived
+/// (or, with `dry_run`, would be) and what was kept.
+pub fn format_report(
+    rows: &[SessionInfo],
+    archived: &[Archived],
+    dry_run: bool,
+    time: &TimeDisplay,
+    now: DateTime<Utc>,
+) -> String {
+    let groups: Vec<&SessionInfo> = rows.iter().filter(|r| !r.duplicates.is_empty()).collect();
+    if groups.is_empty() {
+        return "No duplicate sessions found.\n".to_string();
+    }
+
+    let is_archived = |id: &str| archived.iter().any(|a| a.session_id == id);
+    let mut out = String::new();
+    for row in &groups {
+        let prompt = row.first_prompt.as_deref().unwrap_or("");
+        out.push_str(&format!(
+            "{}  \"{}\"\n",
+            row.project_dir_display,
+            display::truncate_prompt(prompt, TITLE_WIDTH)
+        ));
+
+        let members: Vec<&SessionInfo> = std::iter::once(*row).chain(&row.duplicates).collect();
+        let times: Vec<String> = members
+            .iter()
+            .map(|s| time.long(s.last_active, now))
+            .collect();
+        let time_width = times
+            .iter()
+            .map(|t| display::display_width(t))
+            .max()
+            .unwrap_or(0);
+        let row_prompt = normalize_prompt(prompt);
+        for (i, (session, when)) in members.iter().zip(&times).enumerate() {
+            let (action, note) = if i == 0 {
+                ("keep", "newest".to_string())
+            } else if !is_archived(&session.session_id) {
+                ("keep", "open in another terminal".to_string())
+            } else {
+                let other = normalize_prompt(session.first_prompt.as_deref().unwrap_or(""));
+                let similar = similarity(&row_prompt, &other);
+                let note = if similar >= 1.0 {
+                    "same prompt".to_string()
+                } else {
+                    format!("{:.0}% similar", (similar * 100.0).floor())
+                };
+                (if dry_run { "would archive" } else { "archive" }, note)
+            };
+            let chain = if session.earlier.is_empty() || i == 0 {
+                String::new()
+            } else {
+                format!(", continued \u{d7}{}", session.earlier.len())
+            };
+            out.push_str(&format!(
+                "  {}  {}  {}  ({}{})\n",
+                display::pad(action, if dry_run { 13 } else { 7 }, true),
+                session.session_id,
+                display::pad(when, time_width, true),
+                note,
+                chain
+            ));
+        }
+    }
+
+    let duplicates: usize = groups.iter().map(|r| r.duplicates.len()).sum();
+    let sessions = |n: usize| display::plural(n, "session");
+    out.push_str(&format!(
+        "\n{} in {}.\n",
+        display::plural(duplicates, "duplicate session"),
+        display::plural(groups.len(), "group")
+    ));
+    if archived.is_empty() {
+        out.push_str("Nothing to archive.\n");
+    } else if dry_run {
+        out.push_str(&format!(
+            "Would archive {} (dry run).\n",
+            sessions(archived.len())
+        ));
+    } else {
+        let to = archived[0]
+            .to
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        out.push_str(&format!(
+            "Archived {} to {}.\n",
+            sessions(archived.len()),
+            to.display()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+    use chrono::TimeDelta;
+
+    use crate::active::{self, ClaudeProcess};
+    use crate::timefmt::{TimeMode, Zone};
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-02-18T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn session(id: &str, dir: &str, hours_ago: i64, prompt: Option<&str>) -> SessionInfo {
+        SessionInfo {
+            path: PathBuf::from(format!("/h/.claude/projects/-work-api/{}.jsonl", id)),
+            last_active: now() - TimeDelta::hours(hours_ago),
+            first_prompt: prompt.map(str::to_string),
+            ..SessionInfo::fixture(id, dir)
+        }
+    }
+
+    fn resumed_by(id: &str) -> ClaudeProcess {
+        ClaudeProcess {
+            pid: 4242,
+            cwd: Some(PathBuf::from("/work/api")),
+            session_id: Some(id.to_string()),
+        }
+    }
+
+    fn ids(sessions: &[SessionInfo]) -> Vec<&str> {
+        sessions.iter().map(|s| s.session_id.as_str()).collect()
+    }
+
+    fn time() -> TimeDisplay {
+        TimeDisplay {
+            mode: TimeMode::Relative,
+            format: crate::timefmt::DEFAULT_TIME_FORMAT.to_string(),
+            zone: Zone::Utc,
+            absolute_after: None,
+        }
+    }
+
+    #[test]
+    fn similarity_scores() {
+        assert_eq!(similarity("fix the tests", "fix the tests"), 1.0);
+        assert!(similarity("fix the tests", "fix the test") > 0.95);
+        assert!(similarity("add cursor pagination", "add cursor paginaton") > 0.9);
+        assert!(similarity("fix the tests", "write the docs") < 0.5);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        // Too short for a bigram: only equality counts.
+        assert_eq!(similarity("y", "n"), 0.0);
+        assert_eq!(similarity("", "y"), 0.0);
+    }
+
+    #[test]
+    fn normalizes_prompts() {
+        assert_eq!(
+            normalize_prompt("<command-name>/fix</command-name>  Fix   THE\ntests"),
+            "/fix fix the tests"
+        );
+    }
+
+    #[test]
+    fn collapses_into_newest() {
+        let rows = collapse(vec![
+            session("c", "/work/api", 1, Some("Fix the flaky login test")),
+            session("x", "/work/web", 2, Some("Fix the flaky login test")),
+            session("b", "/work/api", 3, Some("fix the flaky  login test.")),
+            session("y", "/work/api", 4, Some("Add a logout button")),
+            session("a", "/work/api", 5, Some("<b>Fix the flaky login test</b>")),
+            session("e", "/work/api", 6, None),
+            session("f", "/work/api", 7, None),
+        ]);
+        assert_eq!(ids(&rows), vec!["c", "x", "y", "e", "f"]);
+        assert_eq!(ids(&rows[0].duplicates), vec!["b", "a"]);
+        assert!(rows[1].duplicates.is_empty());
+
+        let expanded = expand(rows);
+        assert_eq!(ids(&expanded), vec!["c", "x", "b", "y", "a", "e", "f"]);
+        assert!(expanded.iter().all(|s| s.duplicates.is_empty()));
+    }
+
+    #[test]
+    fn collapser_counts_rows() {
+        let mut collapser = Collapser::default();
+        let dir = Path::new("/work/api");
+        assert_eq!(collapser.add(dir, Some("Fix it")), None);
+        assert_eq!(collapser.add(dir, Some("Ship it")), None);
+        assert_eq!(collapser.add(dir, Some("fix it")), Some(0));
+        assert_eq!(collapser.add(dir, Some("   ")), None);
+        assert_eq!(collapser.row_count(), 3);
+    }
+
+    #[test]
+    fn plans_duplicates_and_their_chains() {
+        let mut chained = session("b", "/work/api", 3, Some("Fix it"));
+        chained.earlier = vec![session("b0", "/work/api", 4, Some("Carry on"))];
+        let mut resumed = session("r", "/work/api", 5, Some("Fix it"));
+        resumed.earlier = vec![session("r0", "/work/api", 6, Some("Carry on"))];
+        let mut row = session("c", "/work/api", 1, Some("Fix it"));
+        row.duplicates = vec![chained, resumed];
+        // Open in another terminal: `claude --resume r0`.
+        active::mark_active(std::slice::from_mut(&mut row), &[resumed_by("r0")]);
+
+        let plan_ids = |force| -> Vec<String> {
+            plan("/h", std::slice::from_ref(&row), force)
+                .into_iter()
+                .map(|a| a.session_id)
+                .collect()
+        };
+        assert_eq!(plan_ids(false), vec!["b", "b0"]);
+        assert_eq!(plan_ids(true), vec!["b", "b0", "r", "r0"]);
+        assert_eq!(
+            plan("/h", std::slice::from_ref(&row), false)[0].to,
+            PathBuf::from("/h/.claude/ccsesh-archive/-work-api/b.jsonl")
+        );
+    }
+
+    #[test]
+    fn archive_moves_transcript_and_session_dir() {
+        let home = TempDir::new().unwrap();
+        let folder = home.child(".claude/projects/-work-api");
+        folder.child("b.jsonl").write_str("{}\n").unwrap();
+        folder
+            .child("b/subagents/agent-1.jsonl")
+            .write_str("{}\n")
+            .unwrap();
+        let home_dir = home.path().to_str().unwrap();
+        let mut duplicate = session("b", "/work/api", 3, Some("Fix it"));
+        duplicate.path = folder.child("b.jsonl").path().to_path_buf();
+        let mut row = session("c", "/work/api", 1, Some("Fix it"));
+        row.duplicates = vec![duplicate];
+
+        let plan = plan(home_dir, std::slice::from_ref(&row), false);
+        archive(&plan).unwrap();
+        let archived = home.child(".claude/ccsesh-archive/-work-api");
+        archived.child("b.jsonl").assert("{}\n");
+        archived.child("b/subagents/agent-1.jsonl").assert("{}\n");
+        assert!(!folder.child("b.jsonl").exists());
+        assert!(!folder.child("b").exists());
+
+        // Archiving it again would overwrite: refused before anything moves.
+        folder.child("b.jsonl").write_str("{}\n").unwrap();
+        let err = archive(&plan).unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+        folder.child("b.jsonl").assert("{}\n");
+    }
+
+    #[test]
+    fn formats_report() {
+        let mut row = session("c", "/work/api", 1, Some("Fix the flaky login test"));
+        row.duplicates = vec![
+            session("b", "/work/api", 3, Some("Fix the flaky login tests")),
+            session("o", "/work/api", 30, Some("Fix the flaky login test")),
+        ];
+        let mut rows = vec![row, session("y", "/work/api", 4, Some("Other"))];
+        active::mark_active(&mut rows, &[resumed_by("o")]);
+        let plan = plan("/h", &rows, false);
+
+        assert_eq!(
+            format_report(&rows, &plan, true, &time(), now()),
+            "\
+/work/api  \"Fix the flaky login test\"
+  keep           c  1h ago  (newest)
+  would archive  b  3h ago  (97% similar)
+  keep           o  1d ago  (open in another terminal)
+
+2 duplicate sessions in 1 group.
+Would archive 1 session (dry run).
+"
+        );
+        assert!(
+            format_report(&rows, &plan, false, &time(), now())
+                .ends_with("Archived 1 session to /h/.claude/ccsesh-archive.\n")
+        );
+        assert_eq!(
+            format_report(&rows[1..], &[], false, &time(), now()),
+            "No duplicate sessions found.\n"
+        );
+    }
+}
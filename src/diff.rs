@@ -0,0 +1,791 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
+
+use crate::display;
+use crate::errors::CcseshError;
+use crate::files;
+use crate::parse;
+use crate::sessions;
+use crate::thread::ConversationGraph;
+use crate::transcript::{self, Role};
+use crate::types::{ContentBlock, TranscriptRecord};
+
+/// Width the prompt columns are fitted to when the terminal's is unknown.
+pub const DEFAULT_WIDTH: usize = 100;
+
+/// Most lines a prompt is wrapped to in its column.
+const PROMPT_LINES: usize = 3;
+
+/// Longest tool call detail shown before it is truncated.
+const DETAIL_WIDTH: usize = 72;
+
+/// Unchanged tool calls kept around each change; longer runs are collapsed.
+const CONTEXT: usize = 2;
+
+/// Which turns of a session to compare: `<N|id>`, `<N|id>@FROM` for the
+/// turns from FROM on, or `<N|id>@FROM-TO`. Turns are counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Point {
+    pub target: String,
+    pub from: usize,
+    pub to: Option<usize>,
+}
+
+impl Point {
+    pub fn parse(spec: &str) -> Result<Point, CcseshError> {
+        let invalid = |reason: &str| CcseshError::InvalidDiffPoint {
+            spec: spec.to_string(),
+            reason: reason.to_string(),
+        };
+        let Some((target, turns)) = spec.rsplit_once('@') else {
+            return Ok(Point {
+                target: spec.to_string(),
+                from: 1,
+                to: None,
+            });
+        };
+        let turn = |s: &str| {
+            s.parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| invalid("expected <N|id>@TURN or <N|id>@FROM-TO, counted from 1"))
+        };
+        let (from, to) = match turns.split_once('-') {
+            Some((from, to)) => (turn(from)?, Some(turn(to)?)),
+            None => (turn(turns)?, None),
+        };
+        if target.is_empty() {
+            return Err(invalid("missing session index or ID"));
+        }
+        if to.is_some_and(|to| to < from) {
+            return Err(invalid("the range ends before it starts"));
+        }
+        Ok(Point {
+            target: target.to_string(),
+            from,
+            to,
+        })
+    }
+
+    /// The turns this point covers, out of all of a session's turns.
+    pub fn select(&self, turns: Vec<Turn>) -> Result<Vec<Turn>, CcseshError> {
+        if self.from > turns.len() {
+            return Err(CcseshError::InvalidDiffPoint {
+                spec: self.to_string(),
+                reason: format!("the session has {}", display::plural(turns.len(), "turn")),
+            });
+        }
+        let to = self.to.unwrap_or(usize::MAX);
+        Ok(turns
+            .into_iter()
+            .filter(|t| t.number >= self.from && t.number <= to)
+            .collect())
+    }
+}
+
+impl std::fmt::Display for Point {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.target, self.from)?;
+        if let Some(to) = self.to {
+            write!(f, "-{}", to)?;
+        }
+        Ok(())
+    }
+}
+
+/// A tool call, compared by name and its main argument.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolCall {
+    pub name: String,
+    /// The command, path (relative to the project directory), pattern, URL
+    /// or description the call was made with, if the tool has one.
+    pub detail: Option<String>,
+}
+
+impl ToolCall {
+    fn label(&self) -> String {
+        match &self.detail {
+            Some(detail) => format!(
+                "{} {}",
+                self.name,
+                display::truncate_prompt(detail, DETAIL_WIDTH)
+            ),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A file a turn read or modified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TurnFile {
+    /// Relative to the project directory when under it.
+    pub path: String,
+    pub modified: bool,
+}
+
+impl TurnFile {
+    fn label(&self) -> String {
+        let action = if self.modified { "modified" } else { "read" };
+        format!("{:<8}  {}", action, self.path)
+    }
+}
+
+/// A prompt and everything Claude did in response, up to the next prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    /// Position among the session's prompts, from 1.
+    pub number: usize,
+    /// With markup stripped and whitespace collapsed.
+    pub prompt: String,
+    pub tools: Vec<ToolCall>,
+    /// Sorted by path.
+    pub files: Vec<TurnFile>,
+}
+
+/// The turns of the conversation the user ended up on. Prompts are non-meta
+/// user messages with text; tool results and interruption notices are part
+/// of the turn they occur in. Anything before the first prompt is dropped.
+pub fn turns(records: Vec<TranscriptRecord>, project_dir: &Path) -> Vec<Turn> {
+    let graph = ConversationGraph::new(records);
+    let records = graph.records();
+
+    let mut spans: Vec<(String, Vec<usize>)> = Vec::new();
+    for i in graph.main_thread() {
+        let prompt = transcript::message_from_record(&records[i])
+            .filter(|m| m.role == Role::User)
+            .map(|m| parse::strip_xml_tags(&m.text))
+            .filter(|p| !p.is_empty() && !p.starts_with("[Request interrupted"));
+        match (prompt, spans.last_mut()) {
+            (Some(prompt), _) => spans.push((prompt, vec![i])),
+            (None, Some((_, span))) => span.push(i),
+            (None, None) => {}
+        }
+    }
+
+    spans
+        .into_iter()
+        .enumerate()
+        .map(|(n, (prompt, span))| {
+            let tools = span
+                .iter()
+                .filter_map(|&i| match &records[i] {
+                    TranscriptRecord::Assistant(entry) => Some(entry),
+                    _ => None,
+                })
+                .flat_map(|entry| entry.message.content.blocks())
+                .filter_map(|block| match block {
+                    ContentBlock::ToolUse(tool) => Some(ToolCall {
+                        name: tool.name.clone(),
+                        detail: tool_detail(&tool.name, &tool.input, project_dir),
+                    }),
+                    _ => None,
+                })
+                .collect();
+            let mut files: Vec<TurnFile> = files::files_touched(span.iter().map(|&i| &records[i]))
+                .into_iter()
+                .map(|f| TurnFile {
+                    path: files::relative_to(&f.path, project_dir),
+                    modified: f.modified,
+                })
+                .collect();
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            Turn {
+                number: n + 1,
+                prompt,
+                tools,
+                files,
+            }
+        })
+        .collect()
+}
+
+/// The argument that tells two calls of the same tool apart.
+fn tool_detail(name: &str, input: &Value, project_dir: &Path) -> Option<String> {
+    let keys: &[&str] = match name {
+        "Bash" => &["command"],
+        "Grep" | "Glob" => &["pattern"],
+        "WebFetch" => &["url"],
+        "WebSearch" => &["query"],
+        "Task" => &["description"],
+        _ => &["file_path", "notebook_path"],
+    };
+    let value = keys
+        .iter()
+        .find_map(|key| input.get(key).and_then(Value::as_str))?
+        .trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(match name {
+        "Bash" => value.lines().next().unwrap_or(value).to_string(),
+        _ if value.starts_with('/') => files::relative_to(value, project_dir),
+        _ => value.to_string(),
+    })
+}
+
+/// One side of a diff.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Side {
+    /// Index in the session listing.
+    pub index: usize,
+    pub session_id: String,
+    pub project_dir: String,
+    /// First and last turn compared.
+    pub from: usize,
+    pub to: usize,
+    /// Turns in the whole session.
+    pub turns: usize,
+}
+
+/// Load the turns `point` selects, with the side they describe.
+pub fn load_side(home_dir: &str, point: &Point, expand: bool) -> Result<(Side, Vec<Turn>)> {
+    let (index, session) = sessions::resolve_session(home_dir, &point.target, expand)?;
+    let all = turns(
+        transcript::read_records(&session.path)?,
+        &session.project_dir,
+    );
+    let total = all.len();
+    let selected = point.select(all)?;
+    let side = Side {
+        index,
+        session_id: session.session_id,
+        project_dir: session.project_dir_display,
+        from: selected.first().map_or(0, |t| t.number),
+        to: selected.last().map_or(0, |t| t.number),
+        turns: total,
+    };
+    Ok((side, selected))
+}
+
+/// How an item of one side relates to the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Same,
+    /// Only in A.
+    Removed,
+    /// Only in B.
+    Added,
+}
+
+impl Change {
+    fn marker(self) -> char {
+        match self {
+            Change::Same => ' ',
+            Change::Removed => '-',
+            Change::Added => '+',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Changed<T> {
+    pub change: Change,
+    #[serde(flatten)]
+    pub item: T,
+}
+
+/// A turn of A set against the turn of B in the same position. One side is
+/// missing when the other has more turns.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TurnDiff {
+    pub a: Option<usize>,
+    pub b: Option<usize>,
+    pub prompt_a: Option<String>,
+    pub prompt_b: Option<String>,
+    pub same_prompt: bool,
+    pub tools: Vec<Changed<ToolCall>>,
+    pub files: Vec<Changed<TurnFile>>,
+    /// Same prompt, tool calls and files.
+    pub identical: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionDiff {
+    pub a: Side,
+    pub b: Side,
+    pub turns: Vec<TurnDiff>,
+}
+
+/// Pair up the turns of A and B by position and diff each pair.
+pub fn diff_turns(a: &[Turn], b: &[Turn]) -> Vec<TurnDiff> {
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let (ta, tb) = (a.get(i), b.get(i));
+            let tools = diff(
+                ta.map_or(&[][..], |t| &t.tools),
+                tb.map_or(&[][..], |t| &t.tools),
+            );
+            let files = diff(
+                ta.map_or(&[][..], |t| &t.files),
+                tb.map_or(&[][..], |t| &t.files),
+            );
+            let same_prompt = ta.zip(tb).is_some_and(|(a, b)| a.prompt == b.prompt);
+            let identical = same_prompt
+                && tools.iter().all(|t| t.change == Change::Same)
+                && files.iter().all(|f| f.change == Change::Same);
+            TurnDiff {
+                a: ta.map(|t| t.number),
+                b: tb.map(|t| t.number),
+                prompt_a: ta.map(|t| t.prompt.clone()),
+                prompt_b: tb.map(|t| t.prompt.clone()),
+                same_prompt,
+                tools,
+                files,
+                identical,
+            }
+        })
+        .collect()
+}
+
+/// `a` and `b` merged along their longest common subsequence, removals
+/// before additions at each change.
+fn diff<T: PartialEq + Clone>(a: &[T], b: &[T]) -> Vec<Changed<T>> {
+    // lcs[i][j]: length of the longest common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let (change, item) = if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+            (Change::Same, &a[i - 1])
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            i += 1;
+            (Change::Removed, &a[i - 1])
+        } else {
+            j += 1;
+            (Change::Added, &b[j - 1])
+        };
+        out.push(Changed {
+            change,
+            item: item.clone(),
+        });
+    }
+    out
+}
+
+/// The diff as printed by `ccsesh diff`: a header per turn pair, the prompts
+/// side by side when they differ, then the tool calls and files as unified
+/// diff lines.
+pub fn format_diff(diff: &SessionDiff, width: usize) -> String {
+    let mut out = format!(
+        "--- A: {}\n+++ B: {}\n",
+        side_header(&diff.a),
+        side_header(&diff.b)
+    );
+
+    for turn in &diff.turns {
+        let number = |n: Option<usize>| n.map_or("-".to_string(), |n| format!("turn {}", n));
+        out.push_str(&format!(
+            "\n@@ {} | {} @@{}\n",
+            number(turn.a),
+            number(turn.b),
+            if turn.identical { " identical" } else { "" }
+        ));
+
+        if turn.same_prompt {
+            let prompt = turn.prompt_a.as_deref().unwrap_or("");
+            out.push_str(&format!(
+                "  {}\n",
+                display::truncate_prompt(prompt, width.saturating_sub(2))
+            ));
+        } else {
+            out.push_str(&side_by_side(
+                turn.prompt_a.as_deref(),
+                turn.prompt_b.as_deref(),
+                width,
+            ));
+        }
+        if turn.identical {
+            continue;
+        }
+
+        let tools: Vec<(Change, String)> = turn
+            .tools
+            .iter()
+            .map(|t| (t.change, t.item.label()))
+            .collect();
+        push_section(&mut out, "tool calls", &tools);
+        let files: Vec<(Change, String)> = turn
+            .files
+            .iter()
+            .map(|f| (f.change, f.item.label()))
+            .collect();
+        push_section(&mut out, "files", &files);
+    }
+
+    let count = |f: &dyn Fn(&TurnDiff) -> bool| diff.turns.iter().filter(|t| f(t)).count();
+    let paired = |t: &TurnDiff| t.a.is_some() && t.b.is_some();
+    out.push_str(&format!(
+        "\n{} identical, {} different, {} only in A, {} only in B\n",
+        count(&|t| t.identical),
+        count(&|t| paired(t) && !t.identical),
+        count(&|t| t.b.is_none()),
+        count(&|t| t.a.is_none()),
+    ));
+    out
+}
+
+fn side_header(side: &Side) -> String {
+    let id = side.session_id.get(..8).unwrap_or(&side.session_id);
+    let turns = if side.from == 1 && side.to == side.turns {
+        display::plural(side.turns, "turn")
+    } else {
+        format!("turns {}-{} of {}", side.from, side.to, side.turns)
+    };
+    format!("{}  {}  {}  ({})", side.index, id, side.project_dir, turns)
+}
+
+/// Two prompts in columns split by `│`, each wrapped to a few lines.
+fn side_by_side(a: Option<&str>, b: Option<&str>, width: usize) -> String {
+    let column = width.saturating_sub(5).max(20) / 2;
+    let left = a.map_or_else(Vec::new, |p| wrap(p, column));
+    let right = b.map_or_else(Vec::new, |p| wrap(p, column));
+    let mut out = String::new();
+    for i in 0..left.len().max(right.len()) {
+        let l = left.get(i).map_or("", String::as_str);
+        let r = right.get(i).map_or("", String::as_str);
+        let line = format!("  {} \u{2502} {}", display::pad(l, column, true), r);
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// `text` word-wrapped to `width` columns, at most `PROMPT_LINES` lines with
+/// the last truncated.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut words = text.split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        if lines.len() == PROMPT_LINES {
+            let rest: Vec<&str> = std::iter::once(word).chain(words).collect();
+            let last = lines.pop().unwrap_or_default();
+            lines.push(display::truncate_prompt(
+                &format!("{} {}", last, rest.join(" ")),
+                width,
+            ));
+            break;
+        }
+        let mut line = display::truncate_prompt(word, width);
+        while let Some(next) = words.peek() {
+            if line.width() + 1 + next.width() > width {
+                break;
+            }
+            line.push(' ');
+            line.push_str(next);
+            words.next();
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// A labelled list of unified diff lines, with long unchanged runs
+/// collapsed. A list without changes is summarised on one line.
+fn push_section(out: &mut String, title: &str, lines: &[(Change, String)]) {
+    if lines.iter().all(|(change, _)| *change == Change::Same) {
+        if !lines.is_empty() {
+            out.push_str(&format!("  {}: same ({})\n", title, lines.len()));
+        }
+        return;
+    }
+
+    out.push_str(&format!("  {}:\n", title));
+    let near_change = |i: usize| {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(lines.len());
+        lines[start..end].iter().any(|(c, _)| *c != Change::Same)
+    };
+    let mut skipped = 0;
+    for (i, (change, label)) in lines.iter().enumerate() {
+        if *change == Change::Same && !near_change(i) {
+            skipped += 1;
+            continue;
+        }
+        if skipped > 0 {
+            out.push_str(&format!("      ... {} unchanged\n", skipped));
+            skipped = 0;
+        }
+        out.push_str(&format!("  {}   {}\n", change.marker(), label));
+    }
+    if skipped > 0 {
+        out.push_str(&format!("      ... {} unchanged\n", skipped));
+    }
+}
+
+/// The diff as pretty-printed JSON.
+pub fn format_diff_json(diff: &SessionDiff) -> String {
+    serde_json::to_string_pretty(diff).unwrap_or_else(|_| "{}".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn prompt(text: &str) -> String {
+        format!(
+            r#"{{"type":"user","message":{{"role":"user","content":{}}}}}"#,
+            serde_json::to_string(text).unwrap()
+        )
+    }
+
+    fn tool(name: &str, input: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","cwd":"/work/api","message":{{"role":"assistant","content":[{{"type":"tool_use","id":"t","name":"{}","input":{}}}]}}}}"#,
+            name, input
+        )
+    }
+
+    fn parse_turns(lines: &[String]) -> Vec<Turn> {
+        let records = lines
+            .iter()
+            .map(|l| TranscriptRecord::parse(l).unwrap())
+            .collect();
+        turns(records, &PathBuf::from("/work/api"))
+    }
+
+    fn turn(number: usize, prompt: &str, tools: &[&str], files: &[&str]) -> Turn {
+        Turn {
+            number,
+            prompt: prompt.to_string(),
+            tools: tools
+                .iter()
+                .map(|t| ToolCall {
+                    name: "Bash".to_string(),
+                    detail: Some(t.to_string()),
+                })
+                .collect(),
+            files: files
+                .iter()
+                .map(|f| TurnFile {
+                    path: f.to_string(),
+                    modified: true,
+                })
+                .collect(),
+        }
+    }
+
+    fn side(index: usize, turns: usize) -> Side {
+        Side {
+            index,
+            session_id: format!("{}b53d999-8692-42ce-a376-4f82206a086d", index),
+            project_dir: "~/work/api".to_string(),
+            from: 1,
+            to: turns,
+            turns,
+        }
+    }
+
+    #[test]
+    fn parses_points() {
+        let point = |target: &str, from, to| Point {
+            target: target.to_string(),
+            from,
+            to,
+        };
+        assert_eq!(Point::parse("3").unwrap(), point("3", 1, None));
+        assert_eq!(Point::parse("0@4").unwrap(), point("0", 4, None));
+        assert_eq!(Point::parse("0@2-5").unwrap(), point("0", 2, Some(5)));
+        for bad in ["0@", "0@0", "0@x", "@2", "0@5-2", "0@1-"] {
+            assert!(Point::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn selects_turns() {
+        let all: Vec<Turn> = (1..=4).map(|n| turn(n, "p", &[], &[])).collect();
+        let numbers = |spec: &str| -> Vec<usize> {
+            Point::parse(spec)
+                .unwrap()
+                .select(all.clone())
+                .unwrap()
+                .iter()
+                .map(|t| t.number)
+                .collect()
+        };
+        assert_eq!(numbers("0"), vec![1, 2, 3, 4]);
+        assert_eq!(numbers("0@3"), vec![3, 4]);
+        assert_eq!(numbers("0@2-3"), vec![2, 3]);
+        assert_eq!(numbers("0@4-9"), vec![4]);
+        let err = Point::parse("0@5").unwrap().select(all).unwrap_err();
+        assert!(err.to_string().contains("has 4 turns"), "{}", err);
+    }
+
+    #[test]
+    fn splits_turns_at_prompts() {
+        let found = parse_turns(&[
+            tool("Bash", r#"{"command":"ls"}"#),
+            prompt("Fix the <b>flaky</b>\n test"),
+            tool("Read", r#"{"file_path":"/work/api/src/lib.rs"}"#),
+            tool("Bash", r#"{"command":"cargo test\necho done"}"#),
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t","content":"ok"}]}}"#.to_string(),
+            prompt("[Request interrupted by user]"),
+            tool("Edit", r#"{"file_path":"src/lib.rs"}"#),
+            prompt("Now commit"),
+            tool("Grep", r#"{"pattern":"TODO"}"#),
+            tool("TodoWrite", r#"{"todos":[]}"#),
+        ]);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].number, 1);
+        assert_eq!(found[0].prompt, "Fix the flaky test");
+        let labels: Vec<String> = found[0].tools.iter().map(ToolCall::label).collect();
+        assert_eq!(
+            labels,
+            vec!["Read src/lib.rs", "Bash cargo test", "Edit src/lib.rs"]
+        );
+        assert_eq!(
+            found[0].files,
+            vec![TurnFile {
+                path: "src/lib.rs".to_string(),
+                modified: true
+            }]
+        );
+        let labels: Vec<String> = found[1].tools.iter().map(ToolCall::label).collect();
+        assert_eq!(labels, vec!["Grep TODO", "TodoWrite"]);
+        assert!(found[1].files.is_empty());
+    }
+
+    #[test]
+    fn diffs_sequences() {
+        let changes: Vec<(Change, char)> = diff(&['a', 'b', 'c', 'd'], &['a', 'x', 'c', 'd', 'e'])
+            .into_iter()
+            .map(|c| (c.change, c.item))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (Change::Same, 'a'),
+                (Change::Removed, 'b'),
+                (Change::Added, 'x'),
+                (Change::Same, 'c'),
+                (Change::Same, 'd'),
+                (Change::Added, 'e'),
+            ]
+        );
+        assert!(diff::<char>(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn pairs_turns_by_position() {
+        let a = [
+            turn(1, "Add pagination", &["cargo test"], &["src/users.rs"]),
+            turn(2, "Commit it", &["git commit"], &[]),
+        ];
+        let b = [
+            turn(1, "Add pagination", &["cargo test"], &["src/users.rs"]),
+            turn(2, "Commit it", &["cargo fmt", "git commit"], &[]),
+            turn(3, "Push", &["git push"], &[]),
+        ];
+        let turns = diff_turns(&a, &b);
+        assert_eq!(turns.len(), 3);
+        assert!(turns[0].identical);
+        assert!(turns[1].same_prompt);
+        assert!(!turns[1].identical);
+        assert_eq!(turns[1].tools[0].change, Change::Added);
+        assert_eq!((turns[2].a, turns[2].b), (None, Some(3)));
+        assert!(!turns[2].same_prompt);
+        assert_eq!(turns[2].tools[0].change, Change::Added);
+    }
+
+    #[test]
+    fn formats_diff() {
+        let a = [
+            turn(1, "Add pagination", &["cargo test"], &["src/users.rs"]),
+            turn(2, "Commit it", &["git commit"], &[]),
+        ];
+        let b = [
+            turn(1, "Add pagination", &["cargo test"], &["src/users.rs"]),
+            turn(
+                2,
+                "Commit it and push",
+                &["git commit", "git push"],
+                &["CHANGELOG.md"],
+            ),
+        ];
+        let diff = SessionDiff {
+            a: side(0, 2),
+            b: side(1, 2),
+            turns: diff_turns(&a, &b),
+        };
+        assert_eq!(
+            format_diff(&diff, 60),
+            "\
+--- A: 0  0b53d999  ~/work/api  (2 turns)
++++ B: 1  1b53d999  ~/work/api  (2 turns)
+
+@@ turn 1 | turn 1 @@ identical
+  Add pagination
+
+@@ turn 2 | turn 2 @@
+  Commit it                   \u{2502} Commit it and push
+  tool calls:
+      Bash git commit
+  +   Bash git push
+  files:
+  +   modified  CHANGELOG.md
+
+1 identical, 1 different, 0 only in A, 0 only in B
+"
+        );
+    }
+
+    #[test]
+    fn collapses_unchanged_tool_calls() {
+        let mut lines: Vec<(Change, String)> =
+            (0..8).map(|i| (Change::Same, i.to_string())).collect();
+        lines.push((Change::Added, "new".to_string()));
+        let mut out = String::new();
+        push_section(&mut out, "tool calls", &lines);
+        assert_eq!(
+            out,
+            "  tool calls:\n      ... 6 unchanged\n      6\n      7\n  +   new\n"
+        );
+
+        let mut out = String::new();
+        push_section(&mut out, "files", &lines[..3]);
+        assert_eq!(out, "  files: same (3)\n");
+    }
+
+    #[test]
+    fn wraps_prompts() {
+        assert_eq!(wrap("one two three", 8), vec!["one two", "three"]);
+        let long = wrap("a b c d e f g h i j k l", 4);
+        assert_eq!(long.len(), PROMPT_LINES);
+        assert!(long[2].ends_with("..."), "{:?}", long);
+        assert_eq!(wrap("abcdefghij", 6), vec!["abc..."]);
+    }
+
+    #[test]
+    fn formats_json() {
+        let a = [turn(1, "Go", &["ls"], &[])];
+        let diff = SessionDiff {
+            a: side(0, 1),
+            b: side(1, 0),
+            turns: diff_turns(&a, &[]),
+        };
+        let json: Value = serde_json::from_str(&format_diff_json(&diff)).unwrap();
+        assert_eq!(json["a"]["turns"], 1);
+        assert_eq!(json["turns"][0]["a"], 1);
+        assert_eq!(json["turns"][0]["b"], Value::Null);
+        assert_eq!(json["turns"][0]["tools"][0]["change"], "removed");
+        assert_eq!(json["turns"][0]["tools"][0]["name"], "Bash");
+        assert_eq!(json["turns"][0]["tools"][0]["detail"], "ls");
+    }
+}
@@ -87,6 +87,16 @@ pub fn discover_sessions(home_dir: &str, limit: usize) -> Result<Vec<SessionCand
     Ok(candidates)
 }
 
+/// The folder name Claude Code uses under `~/.claude/projects/` for a
+/// working directory: every character other than an ASCII letter or digit
+/// becomes `-`, so `/home/u/my.app` is `-home-u-my-app`.
+pub fn project_folder_name(project_dir: &str) -> String {
+    project_dir
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +123,16 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn project_folder_names() {
+        assert_eq!(
+            project_folder_name("/Users/testuser/dev/myproject"),
+            "-Users-testuser-dev-myproject"
+        );
+        assert_eq!(project_folder_name("/home/u/my.app_2"), "-home-u-my-app-2");
+        assert_eq!(project_folder_name("/srv/café"), "-srv-caf-");
+    }
+
     #[test]
     fn sorting_by_mtime_descending() {
         let tmp = assert_fs::TempDir::new().unwrap();
@@ -317,6 +337,7 @@ mod tests {
 
     #[cfg(unix)]
     #[test]
+    #[allow(clippy::len_zero)]
     fn unreadable_files_skipped_silently() {
         use std::os::unix::fs::PermissionsExt;
 
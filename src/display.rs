@@ -1,22 +1,65 @@
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
-use owo_colors::{OwoColorize, Stream, Style};
 use serde::Serialize;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::files::FileTouched;
+use crate::hyperlink::{self, Hyperlinks};
+use crate::search::SearchHit;
+use crate::shell::ResumeTemplate;
+use crate::theme::{Element, Theme};
+use crate::timefmt::TimeDisplay;
+use crate::transcript::{BranchTranscript, TranscriptMessage};
+use crate::types::{SessionField, SessionInfo};
+
+/// Default prompt width (in columns) for `format_default` when the terminal
+/// width is unknown or roomy.
+const DEFAULT_PROMPT_WIDTH: usize = 72;
+
+/// Default prompt width for `format_short`.
+const SHORT_PROMPT_WIDTH: usize = 52;
+
+/// Narrowest prompt column before paths start being shortened.
+const MIN_PROMPT_WIDTH: usize = 20;
+
+/// Narrowest a path is shortened to when fitting the terminal.
+const MIN_PATH_WIDTH: usize = 12;
+
+/// Display width of `s` in terminal columns. East Asian wide characters and
+/// most emoji take two columns; combining marks take none.
+pub fn display_width(s: &str) -> usize {
+    s.width()
+}
 
-use crate::types::{SessionInfo, shell_escape_single_quote};
+/// Pad `s` with spaces to `width` columns, on the right (`left_align`) or the
+/// left. Strings already at least that wide are returned unchanged.
+pub fn pad(s: &str, width: usize, left_align: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(s.width()));
+    if left_align {
+        format!("{}{}", s, fill)
+    } else {
+        format!("{}{}", fill, s)
+    }
+}
 
-/// Truncate a prompt at word boundaries, appending "..." if truncated.
-pub fn truncate_prompt(prompt: &str, max_chars: usize) -> String {
-    let char_count = prompt.chars().count();
-    if char_count <= max_chars {
+/// Truncate a prompt at word boundaries to at most `max_width` columns,
+/// appending "..." if truncated.
+pub fn truncate_prompt(prompt: &str, max_width: usize) -> String {
+    if prompt.width() <= max_width {
         return prompt.to_string();
     }
 
-    let limit = max_chars.saturating_sub(3);
+    let limit = max_width.saturating_sub(3);
 
-    // Find byte offset of character at position `limit`
+    // Byte offset of the first character that would overflow `limit` columns
+    let mut used = 0;
     let byte_limit = prompt
         .char_indices()
-        .nth(limit)
+        .find(|(_, c)| {
+            used += c.width().unwrap_or(0);
+            used > limit
+        })
         .map(|(i, _)| i)
         .unwrap_or(prompt.len());
 
@@ -35,6 +78,106 @@ pub fn truncate_prompt(prompt: &str, max_chars: usize) -> String {
     }
 }
 
+/// Shorten `s` to at most `max_width` columns by replacing its middle with
+/// `…`. Two thirds of the room goes to the end, which for a path is the
+/// project name.
+pub fn shorten_middle(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
+        return s.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    let budget = max_width - 1;
+    let tail_budget = budget - budget / 3;
+
+    let mut tail_width = 0;
+    let tail_start = s
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| {
+            tail_width += c.width().unwrap_or(0);
+            tail_width <= tail_budget
+        })
+        .last()
+        .map_or(s.len(), |(i, _)| i);
+    let tail = &s[tail_start..];
+
+    let head_budget = budget - tail.width();
+    let mut head_width = 0;
+    let head_end = s[..tail_start]
+        .char_indices()
+        .find(|(_, c)| {
+            head_width += c.width().unwrap_or(0);
+            head_width > head_budget
+        })
+        .map_or(tail_start, |(i, _)| i);
+
+    format!("{}\u{2026}{}", &s[..head_end], tail)
+}
+
+/// Width of the terminal the listing is printed to: `$COLUMNS` if set, else
+/// the size of stdout when it is a terminal. `None` when neither is known
+/// (e.g. output piped to another program), in which case nothing is fitted.
+pub fn terminal_width() -> Option<usize> {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse::<usize>().ok())
+        .filter(|&c| c > 0)
+        .or_else(stdout_columns)
+}
+
+#[cfg(unix)]
+fn stdout_columns() -> Option<usize> {
+    // SAFETY: TIOCGWINSZ only writes a `winsize` into the provided struct,
+    // which lives for the duration of the call.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    (ok && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn stdout_columns() -> Option<usize> {
+    None
+}
+
+/// Path and prompt column widths for one listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Columns {
+    path: usize,
+    prompt: usize,
+}
+
+/// Fit the path and prompt columns into `term_width` columns, of which
+/// `fixed` are taken by everything else on the line. The prompt shrinks from
+/// `default_prompt` down to `MIN_PROMPT_WIDTH` first; after that, paths are
+/// shortened (down to `MIN_PATH_WIDTH`). Without a terminal width, paths keep
+/// their full width and prompts get `default_prompt`.
+fn fit_columns(
+    widest_path: usize,
+    fixed: usize,
+    default_prompt: usize,
+    term_width: Option<usize>,
+) -> Columns {
+    let Some(term_width) = term_width else {
+        return Columns {
+            path: widest_path,
+            prompt: default_prompt,
+        };
+    };
+    let available = term_width.saturating_sub(fixed);
+    let path = widest_path.min(
+        available
+            .saturating_sub(MIN_PROMPT_WIDTH)
+            .max(MIN_PATH_WIDTH),
+    );
+    let prompt = available
+        .saturating_sub(path)
+        .clamp(MIN_PROMPT_WIDTH.min(default_prompt), default_prompt);
+    Columns { path, prompt }
+}
+
 /// Format a duration into a compact relative time string like "2m ago", "1h ago".
 pub fn format_relative_time(duration: chrono::Duration) -> String {
     let secs = duration.num_seconds().max(0);
@@ -55,6 +198,11 @@ pub fn format_relative_time(duration: chrono::Duration) -> String {
     }
 }
 
+/// `n` followed by `word`, with an "s" unless `n` is 1: "1 session", "3 days".
+pub fn plural(n: usize, word: &str) -> String {
+    format!("{} {}{}", n, word, if n == 1 { "" } else { "s" })
+}
+
 /// Short relative time: "2m", "1h", "3d" — no "ago" suffix.
 pub fn format_relative_time_short(duration: chrono::Duration) -> String {
     let secs = duration.num_seconds().max(0);
@@ -89,219 +237,533 @@ enum DisplaySummary {
     Empty,
 }
 
-// Style constants
-fn style_index() -> Style {
-    Style::new().cyan().bold()
+/// Marker shown next to sessions that are currently open.
+const ACTIVE_MARKER: &str = "\u{25cf}";
+
+/// The active marker (or a blank of the same width) for `session`.
+fn active_marker(session: &SessionInfo, theme: &Theme) -> String {
+    if session.active {
+        theme.paint(Element::Active, ACTIVE_MARKER)
+    } else {
+        " ".to_string()
+    }
+}
+
+/// " (continued ×N)" for a session that continues N earlier ones and
+/// " (+N duplicates)" for one with N older duplicates collapsed into it, else
+/// "", along with its display width.
+fn continued_marker(session: &SessionInfo, theme: &Theme) -> (String, usize) {
+    let mut markers = Vec::new();
+    if !session.earlier.is_empty() {
+        markers.push(format!("(continued \u{d7}{})", session.earlier.len()));
+    }
+    if !session.duplicates.is_empty() {
+        let n = session.duplicates.len();
+        markers.push(format!("(+{})", plural(n, "duplicate")));
+    }
+    if markers.is_empty() {
+        return (String::new(), 0);
+    }
+    let marker = markers.join(" ");
+    let width = marker.width() + 1;
+    (
+        format!(" {}", theme.paint(Element::Continued, &marker)),
+        width,
+    )
+}
+
+/// Layout options shared by the text listings.
+#[derive(Debug, Clone, Default)]
+pub struct ListingOptions<'a> {
+    /// Fit lines to this many columns (see `terminal_width`).
+    pub term_width: Option<usize>,
+    /// Emit OSC 8 links on the path and summary (see `hyperlink`).
+    pub hyperlinks: Option<&'a Hyperlinks>,
+    /// Colours for each part of the line.
+    pub theme: Theme,
+    /// Relative or absolute times, and in which zone.
+    pub time: TimeDisplay,
+}
+
+/// The path column: shortened in the middle if it is wider than `width`,
+/// then padded to it. The link, if any, covers the text but not the padding.
+fn path_cell(session: &SessionInfo, width: usize, options: &ListingOptions) -> String {
+    let text = shorten_middle(&session.project_dir_display, width);
+    let padding = " ".repeat(width.saturating_sub(text.width()));
+    match options.hyperlinks {
+        Some(links) => format!(
+            "{}{}",
+            hyperlink::link(&links.project_url(session), &text),
+            padding
+        ),
+        None => text + &padding,
+    }
 }
 
-fn style_dim_italic() -> Style {
-    Style::new().dimmed().italic()
+/// Link an (already styled) summary to the session's transcript.
+fn summary_link(session: &SessionInfo, summary: String, options: &ListingOptions) -> String {
+    match options.hyperlinks {
+        Some(links) => hyperlink::link(&links.session_url(session), &summary),
+        None => summary,
+    }
 }
 
 /// Default format output with header, aligned columns, footer.
-pub fn format_default(sessions: &[SessionInfo], now: DateTime<Utc>) -> String {
+/// Lines are fitted, linked and coloured according to `options`.
+pub fn format_default(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    options: &ListingOptions,
+) -> String {
+    let theme = &options.theme;
     let mut out = String::new();
 
     // Header
-    let header = "Recent Claude Code sessions:";
-    out.push_str(
-        &header
-            .if_supports_color(Stream::Stdout, |s| s.dimmed())
-            .to_string(),
-    );
+    out.push_str(&theme.paint(Element::Header, "Recent Claude Code sessions:"));
     out.push_str("\n\n");
 
+    push_default_rows(&mut out, sessions, now, &HashSet::new(), options);
+
+    // Footer
+    out.push_str(&theme.paint(Element::Header, "Resume: ccsesh <number>"));
+    out.push('\n');
+
+    out
+}
+
+/// Session rows shared by `format_default` and `format_watch`. Sessions whose
+/// IDs are in `updated` are highlighted.
+fn push_default_rows(
+    out: &mut String,
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    updated: &HashSet<String>,
+    options: &ListingOptions,
+) {
     if !sessions.is_empty() {
+        let theme = &options.theme;
+
         // Compute column widths
         let index_width = if sessions.len() <= 10 { 1 } else { 2 };
         let max_path_width = sessions
             .iter()
-            .map(|s| s.project_dir_display.len())
+            .map(|s| s.project_dir_display.width())
             .max()
             .unwrap_or(0);
+        // Relative times fit in 7 columns; absolute ones widen the column.
+        let times: Vec<String> = sessions
+            .iter()
+            .map(|s| options.time.long(s.last_active, now))
+            .collect();
+        let time_width = times.iter().map(|t| t.width()).max().unwrap_or(0).max(7);
+        // "  {idx}  {time} {marker} {path}  \"{prompt}\""
+        let fixed = 2 + index_width + 2 + time_width + 3 + 2 + 2;
+        let columns = fit_columns(
+            max_path_width,
+            fixed,
+            DEFAULT_PROMPT_WIDTH,
+            options.term_width,
+        );
 
-        let idx_style = style_index();
-        let dim_it = style_dim_italic();
-
-        for (i, session) in sessions.iter().enumerate() {
-            let duration = now - session.last_active;
-            let time_str = format_relative_time(duration);
+        for (i, (session, time_str)) in sessions.iter().zip(&times).enumerate() {
+            let is_updated = updated.contains(&session.session_id);
 
-            // Index: right-aligned, cyan bold
+            // Index: right-aligned (reversed when just updated)
+            let idx_style = if is_updated {
+                theme.index.reversed()
+            } else {
+                theme.index
+            };
             let idx_str = format!("{:>width$}", i, width = index_width);
-            let idx_colored = idx_str
-                .if_supports_color(Stream::Stdout, |s| s.style(idx_style))
-                .to_string();
-
-            // Time: right-aligned 7 chars, yellow
-            let time_padded = format!("{:>7}", time_str);
-            let time_colored = time_padded
-                .if_supports_color(Stream::Stdout, |s| s.yellow())
-                .to_string();
-
-            // Path: left-aligned padded, green
-            let path_padded = format!(
-                "{:<width$}",
-                session.project_dir_display,
-                width = max_path_width
-            );
-            let path_colored = path_padded
-                .if_supports_color(Stream::Stdout, |s| s.green())
-                .to_string();
-
-            // Summary
+            let idx_colored = theme.paint_with(idx_style, &idx_str);
+
+            // Time: right-aligned (bold when just updated)
+            let time_style = if is_updated {
+                theme.time.bold()
+            } else {
+                theme.time
+            };
+            let time_colored = theme.paint_with(time_style, &pad(time_str, time_width, false));
+
+            // Path: left-aligned padded
+            let path_colored =
+                theme.paint(Element::Path, &path_cell(session, columns.path, options));
+
+            // Summary, leaving room for the continued marker
+            let (continued, continued_width) = continued_marker(session, theme);
+            let prompt_width = columns.prompt.saturating_sub(continued_width);
             let summary = display_summary(session);
             let summary_str = match &summary {
                 DisplaySummary::Prompt(p) => {
-                    let truncated = truncate_prompt(p, 72);
-                    let quoted = format!("\"{}\"", truncated);
-                    quoted
-                        .if_supports_color(Stream::Stdout, |s| s.white())
-                        .to_string()
+                    let quoted = format!("\"{}\"", truncate_prompt(p, prompt_width));
+                    theme.paint(Element::Prompt, &quoted)
                 }
                 DisplaySummary::Slug(s) => {
-                    let quoted = format!("\"{}\"", s);
-                    quoted
-                        .if_supports_color(Stream::Stdout, |s| s.style(dim_it))
-                        .to_string()
+                    let quoted = format!("\"{}\"", truncate_prompt(s, prompt_width));
+                    theme.paint(Element::Slug, &quoted)
                 }
-                DisplaySummary::Empty => "(empty session)"
-                    .if_supports_color(Stream::Stdout, |s| s.style(dim_it))
-                    .to_string(),
+                DisplaySummary::Empty => theme.paint(Element::Slug, "(empty session)"),
             };
+            let summary_str = summary_link(session, summary_str, options);
 
+            // Active marker sits in the middle of the time/path gap, so the
+            // column layout is identical whether or not anything is active.
             out.push_str(&format!(
-                "  {}  {}   {}  {}\n",
-                idx_colored, time_colored, path_colored, summary_str
+                "  {}  {} {} {}  {}{}\n",
+                idx_colored,
+                time_colored,
+                active_marker(session, theme),
+                path_colored,
+                summary_str,
+                continued
             ));
         }
 
         out.push('\n');
     }
+}
 
-    // Footer
-    let footer = "Resume: ccsesh <number>";
-    out.push_str(
-        &footer
-            .if_supports_color(Stream::Stdout, |s| s.dimmed())
-            .to_string(),
+/// Watch mode output: default layout under a "watching" header, with
+/// sessions in `updated` highlighted. No footer, since the screen is redrawn.
+pub fn format_watch(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    updated: &HashSet<String>,
+    options: &ListingOptions,
+) -> String {
+    let mut out = String::new();
+
+    let header = format!(
+        "Watching Claude Code sessions (updated {}, Ctrl-C to exit):",
+        options.time.clock(now)
     );
-    out.push('\n');
+    out.push_str(&options.theme.paint(Element::Header, &header));
+    out.push_str("\n\n");
+
+    push_default_rows(&mut out, sessions, now, updated, options);
 
     out
 }
 
-/// Short format output — compact single-line, no header/footer.
-pub fn format_short(sessions: &[SessionInfo], now: DateTime<Utc>) -> String {
+/// Short format output — compact single-line, no header/footer. Fitted,
+/// linked and coloured like `format_default`.
+pub fn format_short(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    options: &ListingOptions,
+) -> String {
     if sessions.is_empty() {
         return String::new();
     }
 
+    let theme = &options.theme;
     let mut out = String::new();
 
     let max_path_width = sessions
         .iter()
-        .map(|s| s.project_dir_display.len())
+        .map(|s| s.project_dir_display.width())
         .max()
         .unwrap_or(0);
 
-    let idx_style = style_index();
-    let dim_it = style_dim_italic();
-
-    for (i, session) in sessions.iter().enumerate() {
-        let duration = now - session.last_active;
-        let time_str = format_relative_time_short(duration);
-
-        // Index: right-aligned 2 chars, cyan bold
-        let idx_str = format!("{:>2}", i);
-        let idx_colored = idx_str
-            .if_supports_color(Stream::Stdout, |s| s.style(idx_style))
-            .to_string();
-
-        // Time: right-aligned 3 chars, yellow
-        let time_padded = format!("{:>3}", time_str);
-        let time_colored = time_padded
-            .if_supports_color(Stream::Stdout, |s| s.yellow())
-            .to_string();
-
-        // Path: left-aligned padded, green
-        let path_padded = format!(
-            "{:<width$}",
-            session.project_dir_display,
-            width = max_path_width
-        );
-        let path_colored = path_padded
-            .if_supports_color(Stream::Stdout, |s| s.green())
-            .to_string();
+    let any_active = sessions.iter().any(|s| s.active);
+
+    let times: Vec<String> = sessions
+        .iter()
+        .map(|s| options.time.short(s.last_active, now))
+        .collect();
+    let time_width = times.iter().map(|t| t.width()).max().unwrap_or(0).max(3);
+
+    // "{idx:2} {time}{gap}{path}  {prompt}"
+    let fixed = 2 + 1 + time_width + if any_active { 3 } else { 2 } + 2;
+    let columns = fit_columns(
+        max_path_width,
+        fixed,
+        SHORT_PROMPT_WIDTH,
+        options.term_width,
+    );
+
+    for (i, (session, time_str)) in sessions.iter().zip(&times).enumerate() {
+        // Index: right-aligned 2 chars
+        let idx_colored = theme.paint(Element::Index, &format!("{:>2}", i));
 
-        // Summary
+        // Time: right-aligned
+        let time_colored = theme.paint(Element::Time, &pad(time_str, time_width, false));
+
+        // Path: left-aligned padded
+        let path_colored = theme.paint(Element::Path, &path_cell(session, columns.path, options));
+
+        // Summary, leaving room for the continued marker
+        let (continued, continued_width) = continued_marker(session, theme);
+        let prompt_width = columns.prompt.saturating_sub(continued_width);
         let summary = display_summary(session);
         let summary_str = match &summary {
             DisplaySummary::Prompt(p) => {
-                let truncated = truncate_prompt(p, 52);
-                truncated
-                    .if_supports_color(Stream::Stdout, |s| s.white())
-                    .to_string()
+                theme.paint(Element::Prompt, &truncate_prompt(p, prompt_width))
+            }
+            DisplaySummary::Slug(s) => {
+                theme.paint(Element::Slug, &truncate_prompt(s, prompt_width))
             }
-            DisplaySummary::Slug(s) => s
-                .if_supports_color(Stream::Stdout, |s| s.style(dim_it))
-                .to_string(),
-            DisplaySummary::Empty => "(empty session)"
-                .if_supports_color(Stream::Stdout, |s| s.style(dim_it))
-                .to_string(),
+            DisplaySummary::Empty => theme.paint(Element::Slug, "(empty session)"),
+        };
+        let summary_str = summary_link(session, summary_str, options);
+
+        // Only widen the time/path gap when there is a marker to show.
+        let gap = if any_active {
+            format!(" {} ", active_marker(session, theme))
+        } else {
+            "  ".to_string()
         };
 
         out.push_str(&format!(
-            "{} {}  {}  {}\n",
-            idx_colored, time_colored, path_colored, summary_str
+            "{} {}{}{}  {}{}\n",
+            idx_colored, time_colored, gap, path_colored, summary_str, continued
         ));
     }
 
     out
 }
 
-/// JSON output format.
-#[derive(Serialize)]
-struct JsonSession {
+/// JSON output schema, shared by `--json` and the machine-readable interfaces.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSession {
+    pub index: usize,
+    pub session_id: String,
+    pub project_dir: String,
+    pub project_dir_display: String,
+    pub last_active: String,
+    pub last_active_relative: String,
+    pub first_prompt: Option<String>,
+    pub slug: Option<String>,
+    pub active: bool,
+    /// IDs of the earlier sessions this one continues, oldest first.
+    pub continued_from: Vec<String>,
+    /// IDs of the older sessions with the same first prompt that were
+    /// collapsed into this one, newest first.
+    pub duplicates: Vec<String>,
+    pub resume_command: String,
+}
+
+/// Build the JSON representation of `session` at list position `index`.
+pub fn json_session(
     index: usize,
-    session_id: String,
-    project_dir: String,
-    project_dir_display: String,
-    last_active: String,
-    last_active_relative: String,
-    first_prompt: Option<String>,
-    slug: Option<String>,
-    resume_command: String,
+    session: &SessionInfo,
+    now: DateTime<Utc>,
+    template: &ResumeTemplate,
+    args: &[String],
+) -> JsonSession {
+    let duration = now - session.last_active;
+    JsonSession {
+        index,
+        session_id: session.session_id.clone(),
+        project_dir: session.project_dir.to_string_lossy().to_string(),
+        project_dir_display: session.project_dir_display.clone(),
+        last_active: session.last_active.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        last_active_relative: format_relative_time(duration),
+        first_prompt: session.first_prompt.clone(),
+        slug: session.slug.clone(),
+        active: session.active,
+        continued_from: session
+            .earlier
+            .iter()
+            .map(|s| s.session_id.clone())
+            .collect(),
+        duplicates: session
+            .duplicates
+            .iter()
+            .map(|s| s.session_id.clone())
+            .collect(),
+        resume_command: template.render(session, args),
+    }
+}
+
+/// A search hit as returned by the HTTP API and MCP server.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSearchHit {
+    pub session: JsonSession,
+    pub snippets: Vec<String>,
+}
+
+/// Build the JSON representation of a search hit.
+pub fn json_search_hit(
+    hit: &SearchHit,
+    now: DateTime<Utc>,
+    template: &ResumeTemplate,
+    args: &[String],
+) -> JsonSearchHit {
+    JsonSearchHit {
+        session: json_session(hit.index, &hit.session, now, template, args),
+        snippets: hit.snippets.clone(),
+    }
+}
+
+/// A session's transcript as printed by `ccsesh export`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonExport<'a> {
+    pub session: JsonSession,
+    pub messages: &'a [TranscriptMessage],
+    pub files_touched: &'a [FileTouched],
+    /// Only present when branches were requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branches: Option<&'a [BranchTranscript]>,
+}
+
+/// Pretty-printed JSON for `ccsesh export`.
+pub fn format_export(export: &JsonExport) -> String {
+    serde_json::to_string_pretty(export).unwrap_or_else(|_| "{}".to_string())
 }
 
-pub fn format_json(sessions: &[SessionInfo], now: DateTime<Utc>) -> String {
+/// `resume_command` is rendered from `template` with the pass-through `args`,
+/// exactly as the exec protocol would emit it.
+pub fn format_json(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    template: &ResumeTemplate,
+    args: &[String],
+) -> String {
     let json_sessions: Vec<JsonSession> = sessions
         .iter()
         .enumerate()
-        .map(|(i, session)| {
-            let duration = now - session.last_active;
-            let project_dir_str = session.project_dir.to_string_lossy().to_string();
-            let escaped_dir = shell_escape_single_quote(&project_dir_str);
-            JsonSession {
-                index: i,
-                session_id: session.session_id.clone(),
-                project_dir: project_dir_str,
-                project_dir_display: session.project_dir_display.clone(),
-                last_active: session.last_active.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
-                last_active_relative: format_relative_time(duration),
-                first_prompt: session.first_prompt.clone(),
-                slug: session.slug.clone(),
-                resume_command: format!(
-                    "cd {} && claude --resume {}",
-                    escaped_dir, session.session_id
-                ),
-            }
-        })
+        .map(|(i, session)| json_session(i, session, now, template, args))
         .collect();
 
     serde_json::to_string_pretty(&json_sessions).unwrap_or_else(|_| "[]".to_string())
 }
 
+/// `JsonSession` of each session as a JSON object, keyed by field name.
+fn session_objects(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    template: &ResumeTemplate,
+    args: &[String],
+) -> Vec<serde_json::Map<String, serde_json::Value>> {
+    sessions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, session)| {
+            match serde_json::to_value(json_session(i, session, now, template, args)) {
+                Ok(serde_json::Value::Object(map)) => Some(map),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// One compact JSON object per line, with only `fields`, in that order.
+pub fn format_ndjson(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    template: &ResumeTemplate,
+    args: &[String],
+    fields: &[SessionField],
+) -> String {
+    let mut out = String::new();
+    for object in session_objects(sessions, now, template, args) {
+        // Written by hand because `serde_json::Map` sorts its keys.
+        let members: Vec<String> = fields
+            .iter()
+            .map(|field| {
+                let value = object.get(field.name()).unwrap_or(&serde_json::Value::Null);
+                format!("\"{}\":{}", field.name(), value)
+            })
+            .collect();
+        out.push('{');
+        out.push_str(&members.join(","));
+        out.push_str("}\n");
+    }
+    out
+}
+
+/// RFC 4180 CSV with `fields` as columns: values containing a comma, quote,
+/// or line break are quoted. Lines end in `\n`.
+pub fn format_csv(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    template: &ResumeTemplate,
+    args: &[String],
+    fields: &[SessionField],
+    header: bool,
+) -> String {
+    let objects = session_objects(sessions, now, template, args);
+    format_delimited(&objects, fields, header, ',', csv_escape)
+}
+
+/// Tab-separated values with `fields` as columns. Backslash, tab, and line
+/// breaks inside values are written as `\\`, `\t`, `\n`, and `\r`.
+pub fn format_tsv(
+    sessions: &[SessionInfo],
+    now: DateTime<Utc>,
+    template: &ResumeTemplate,
+    args: &[String],
+    fields: &[SessionField],
+    header: bool,
+) -> String {
+    let objects = session_objects(sessions, now, template, args);
+    format_delimited(&objects, fields, header, '\t', tsv_escape)
+}
+
+fn format_delimited(
+    objects: &[serde_json::Map<String, serde_json::Value>],
+    fields: &[SessionField],
+    header: bool,
+    separator: char,
+    escape: fn(&str) -> String,
+) -> String {
+    let mut out = String::new();
+    let mut push_row = |cells: Vec<String>| {
+        out.push_str(&cells.join(&separator.to_string()));
+        out.push('\n');
+    };
+
+    if header {
+        push_row(fields.iter().map(|f| escape(f.name())).collect());
+    }
+    for object in objects {
+        push_row(
+            fields
+                .iter()
+                .map(|f| escape(&cell_text(object.get(f.name()))))
+                .collect(),
+        );
+    }
+    out
+}
+
+/// Plain text for a table cell: empty for null, list items space-separated.
+fn cell_text(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .map(|item| cell_text(Some(item)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn tsv_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,13 +779,12 @@ mod tests {
         slug: Option<&str>,
     ) -> SessionInfo {
         SessionInfo {
-            session_id: id.to_string(),
             path: PathBuf::from(format!("/home/user/.claude/projects/test/{}.jsonl", id)),
-            project_dir: PathBuf::from(dir),
             project_dir_display: display.to_string(),
             last_active,
             first_prompt: prompt.map(|s| s.to_string()),
             slug: slug.map(|s| s.to_string()),
+            ..SessionInfo::fixture(id, dir)
         }
     }
 
@@ -333,6 +794,13 @@ mod tests {
             .with_timezone(&Utc)
     }
 
+    fn fitted(term_width: usize) -> ListingOptions<'static> {
+        ListingOptions {
+            term_width: Some(term_width),
+            ..Default::default()
+        }
+    }
+
     // --- format_relative_time ---
 
     #[test]
@@ -512,7 +980,7 @@ mod tests {
     #[test]
     fn default_empty_sessions() {
         let now = fixed_now();
-        let result = format_default(&[], now);
+        let result = format_default(&[], now, &ListingOptions::default());
         assert!(result.contains("Recent Claude Code sessions:"));
         assert!(result.contains("Resume: ccsesh <number>"));
         // No session lines
@@ -530,7 +998,7 @@ mod tests {
             Some("Fix the bug"),
             None,
         )];
-        let result = format_default(&sessions, now);
+        let result = format_default(&sessions, now, &ListingOptions::default());
         assert!(result.contains("Recent Claude Code sessions:"));
         assert!(result.contains("Resume: ccsesh <number>"));
         assert!(result.contains("2m ago"));
@@ -559,7 +1027,7 @@ mod tests {
                 None,
             ),
         ];
-        let result = format_default(&sessions, now);
+        let result = format_default(&sessions, now, &ListingOptions::default());
         let lines: Vec<&str> = result.lines().collect();
 
         // Find session lines (contain index 0 and 1)
@@ -584,7 +1052,7 @@ mod tests {
             Some("User prompt text"),
             Some("some-slug"),
         )];
-        let result = format_default(&sessions, now);
+        let result = format_default(&sessions, now, &ListingOptions::default());
         // Prompt takes priority over slug
         assert!(result.contains("\"User prompt text\""));
         assert!(!result.contains("some-slug"));
@@ -601,7 +1069,7 @@ mod tests {
             None,
             Some("woolly-conjuring-journal"),
         )];
-        let result = format_default(&sessions, now);
+        let result = format_default(&sessions, now, &ListingOptions::default());
         assert!(result.contains("woolly-conjuring-journal"));
     }
 
@@ -616,7 +1084,7 @@ mod tests {
             None,
             None,
         )];
-        let result = format_default(&sessions, now);
+        let result = format_default(&sessions, now, &ListingOptions::default());
         assert!(result.contains("(empty session)"));
     }
 
@@ -625,7 +1093,7 @@ mod tests {
     #[test]
     fn short_empty_sessions() {
         let now = fixed_now();
-        let result = format_short(&[], now);
+        let result = format_short(&[], now, &ListingOptions::default());
         assert_eq!(result, "");
     }
 
@@ -640,7 +1108,7 @@ mod tests {
             Some("Fix the bug"),
             None,
         )];
-        let result = format_short(&sessions, now);
+        let result = format_short(&sessions, now, &ListingOptions::default());
         assert!(!result.contains("Recent Claude Code sessions:"));
         assert!(!result.contains("Resume:"));
         assert!(result.contains("2m"));
@@ -661,7 +1129,7 @@ mod tests {
             Some("Test"),
             None,
         )];
-        let result = format_short(&sessions, now);
+        let result = format_short(&sessions, now, &ListingOptions::default());
         assert!(!result.contains("Recent"));
         assert!(!result.contains("Resume"));
     }
@@ -677,7 +1145,7 @@ mod tests {
             None,
             Some("my-slug"),
         )];
-        let result = format_short(&sessions, now);
+        let result = format_short(&sessions, now, &ListingOptions::default());
         assert!(result.contains("my-slug"));
     }
 
@@ -692,7 +1160,7 @@ mod tests {
             None,
             None,
         )];
-        let result = format_short(&sessions, now);
+        let result = format_short(&sessions, now, &ListingOptions::default());
         assert!(result.contains("(empty session)"));
     }
 
@@ -701,7 +1169,7 @@ mod tests {
     #[test]
     fn json_empty_sessions() {
         let now = fixed_now();
-        let result = format_json(&[], now);
+        let result = format_json(&[], now, &ResumeTemplate::default(), &[]);
         assert_eq!(result, "[]");
     }
 
@@ -716,7 +1184,7 @@ mod tests {
             Some("Design technical approach"),
             Some("woolly-conjuring-journal"),
         )];
-        let result = format_json(&sessions, now);
+        let result = format_json(&sessions, now, &ResumeTemplate::default(), &[]);
         let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
         assert_eq!(parsed.len(), 1);
 
@@ -754,7 +1222,7 @@ mod tests {
             None,
             None,
         )];
-        let result = format_json(&sessions, now);
+        let result = format_json(&sessions, now, &ResumeTemplate::default(), &[]);
         let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
         assert!(parsed[0]["first_prompt"].is_null());
         assert!(parsed[0]["slug"].is_null());
@@ -772,7 +1240,7 @@ mod tests {
             Some(&long_prompt),
             None,
         )];
-        let result = format_json(&sessions, now);
+        let result = format_json(&sessions, now, &ResumeTemplate::default(), &[]);
         let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
         // Full prompt preserved in JSON — no truncation
         assert_eq!(parsed[0]["first_prompt"].as_str().unwrap().len(), 200);
@@ -789,7 +1257,7 @@ mod tests {
             Some("test"),
             None,
         )];
-        let result = format_json(&sessions, now);
+        let result = format_json(&sessions, now, &ResumeTemplate::default(), &[]);
         let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
         let cmd = parsed[0]["resume_command"].as_str().unwrap();
         // Uses absolute path with shell escaping, not ~ path
@@ -808,7 +1276,7 @@ mod tests {
             Some("test"),
             None,
         )];
-        let result = format_json(&sessions, now);
+        let result = format_json(&sessions, now, &ResumeTemplate::default(), &[]);
         let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
         let ts = parsed[0]["last_active"].as_str().unwrap();
         // Must be ISO 8601 UTC with Z suffix
@@ -839,7 +1307,7 @@ mod tests {
                 None,
             ),
         ];
-        let result = format_json(&sessions, now);
+        let result = format_json(&sessions, now, &ResumeTemplate::default(), &[]);
         let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
         assert_eq!(parsed.len(), 2);
         assert_eq!(parsed[0]["index"], 0);
@@ -900,4 +1368,502 @@ mod tests {
         assert!(result.ends_with("..."));
         assert!(result.chars().count() <= 72);
     }
+
+    #[test]
+    fn hyperlinks_wrap_path_and_summary() {
+        let now = fixed_now();
+        let sessions = vec![make_session(
+            "abc-123",
+            "/home/user/dev/project",
+            "~/dev/project",
+            now - TimeDelta::seconds(60),
+            Some("Fix the bug"),
+            None,
+        )];
+        let links = Hyperlinks::default();
+        let options = ListingOptions {
+            hyperlinks: Some(&links),
+            ..Default::default()
+        };
+        for result in [
+            format_default(&sessions, now, &options),
+            format_short(&sessions, now, &options),
+        ] {
+            assert!(result.contains(&hyperlink::link(
+                "file:///home/user/dev/project",
+                "~/dev/project"
+            )));
+            assert!(result.contains(&format!(
+                "\x1b]8;;{}\x1b\\",
+                links.session_url(&sessions[0])
+            )));
+        }
+        let plain = format_default(&sessions, now, &ListingOptions::default());
+        assert!(!plain.contains("\x1b]8;;"));
+    }
+
+    #[test]
+    fn absolute_times_widen_the_time_column() {
+        let now = fixed_now();
+        let sessions = vec![
+            make_session(
+                "id1",
+                "/home/user/a",
+                "~/a",
+                now - TimeDelta::seconds(120),
+                Some("First"),
+                None,
+            ),
+            make_session(
+                "id2",
+                "/home/user/b",
+                "~/b",
+                now - TimeDelta::days(3),
+                Some("Second"),
+                None,
+            ),
+        ];
+        let options = ListingOptions {
+            time: TimeDisplay {
+                zone: crate::timefmt::Zone::Utc,
+                absolute_after: Some(TimeDelta::days(1)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = format_default(&sessions, now, &options);
+        assert!(result.contains("  0            2m ago   ~/a  \"First\""));
+        assert!(result.contains("  1  2026-02-15 00:00   ~/b  \"Second\""));
+
+        let short = format_short(&sessions, now, &options);
+        assert!(short.contains(" 0               2m  ~/a  First"));
+        assert!(short.contains(" 1 2026-02-15 00:00  ~/b  Second"));
+    }
+
+    // --- display width and terminal fitting ---
+
+    #[test]
+    fn truncate_prompt_counts_display_width() {
+        let s = "这是一个很长的中文提示词需要被截断";
+        let result = truncate_prompt(s, 12);
+        assert!(result.ends_with("..."));
+        assert!(display_width(&result) <= 12);
+    }
+
+    #[test]
+    fn pad_uses_display_width() {
+        assert_eq!(pad("日本", 6, true), "日本  ");
+        assert_eq!(pad("ab", 4, false), "  ab");
+        assert_eq!(pad("toolong", 3, true), "toolong");
+    }
+
+    #[test]
+    fn shorten_middle_keeps_head_and_tail() {
+        let s = "~/dev/work/clients/acme/backend-service";
+        let result = shorten_middle(s, 20);
+        assert_eq!(display_width(&result), 20);
+        assert!(result.starts_with("~/dev"));
+        assert!(result.ends_with("-service"));
+        assert!(result.contains('\u{2026}'));
+        assert_eq!(shorten_middle("~/short", 20), "~/short");
+    }
+
+    #[test]
+    fn shorten_middle_wide_chars_stay_within_width() {
+        let result = shorten_middle("~/プロジェクト/とても長い名前のフォルダ", 15);
+        assert!(display_width(&result) <= 15);
+    }
+
+    #[test]
+    fn fit_columns_shrinks_prompt_before_path() {
+        let unfitted = fit_columns(30, 20, 72, None);
+        assert_eq!(
+            unfitted,
+            Columns {
+                path: 30,
+                prompt: 72
+            }
+        );
+
+        let roomy = fit_columns(30, 20, 72, Some(200));
+        assert_eq!(
+            roomy,
+            Columns {
+                path: 30,
+                prompt: 72
+            }
+        );
+
+        let medium = fit_columns(30, 20, 72, Some(100));
+        assert_eq!(
+            medium,
+            Columns {
+                path: 30,
+                prompt: 50
+            }
+        );
+
+        let narrow = fit_columns(30, 20, 72, Some(60));
+        assert_eq!(
+            narrow,
+            Columns {
+                path: 20,
+                prompt: 20
+            }
+        );
+
+        let tiny = fit_columns(30, 20, 72, Some(10));
+        assert_eq!(tiny.path, MIN_PATH_WIDTH);
+        assert_eq!(tiny.prompt, MIN_PROMPT_WIDTH);
+    }
+
+    #[test]
+    fn default_aligns_wide_character_paths() {
+        let now = fixed_now();
+        let sessions = vec![
+            make_session(
+                "id1",
+                "/home/user/日本語",
+                "~/日本語",
+                now - TimeDelta::seconds(120),
+                Some("First prompt"),
+                None,
+            ),
+            make_session(
+                "id2",
+                "/home/user/abcdefg",
+                "~/abcdefg",
+                now - TimeDelta::seconds(3600),
+                Some("Second prompt"),
+                None,
+            ),
+        ];
+        let result = format_default(&sessions, now, &ListingOptions::default());
+        let column = |needle: &str| {
+            let line = result.lines().find(|l| l.contains(needle)).unwrap();
+            display_width(&line[..line.find('"').unwrap()])
+        };
+        assert_eq!(column("First prompt"), column("Second prompt"));
+    }
+
+    #[test]
+    fn default_fits_terminal_width() {
+        let now = fixed_now();
+        let sessions = vec![make_session(
+            "id1",
+            "/home/user/dev/work/clients/acme/backend-service",
+            "~/dev/work/clients/acme/backend-service",
+            now - TimeDelta::seconds(120),
+            Some("Refactor the billing pipeline so invoices are generated in batches"),
+            None,
+        )];
+        let result = format_default(&sessions, now, &fitted(70));
+        let line = result.lines().find(|l| l.contains("backend")).unwrap();
+        assert!(display_width(line) <= 70, "{}", line);
+        assert!(line.contains('\u{2026}'));
+
+        let short = format_short(&sessions, now, &fitted(60));
+        assert!(short.lines().all(|l| display_width(l) <= 60), "{}", short);
+    }
+
+    #[test]
+    fn json_resume_command_reflects_template_and_args() {
+        let now = fixed_now();
+        let sessions = vec![make_session(
+            "abc-123",
+            "/home/user/dev",
+            "~/dev",
+            now - TimeDelta::seconds(60),
+            Some("test"),
+            None,
+        )];
+        let template =
+            ResumeTemplate::parse("cd {dir} && my-launcher claude -r {id} {args}").unwrap();
+        let args = vec!["--model".to_string(), "opus".to_string()];
+        let result = format_json(&sessions, now, &template, &args);
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            parsed[0]["resume_command"],
+            "cd '/home/user/dev' && my-launcher claude -r abc-123 '--model' 'opus'"
+        );
+    }
+
+    // --- active marker ---
+
+    #[test]
+    fn default_active_marker_keeps_alignment() {
+        let now = fixed_now();
+        let mut active = make_session(
+            "id1",
+            "/home/user/dev",
+            "~/dev",
+            now - TimeDelta::seconds(60),
+            Some("Open elsewhere"),
+            None,
+        );
+        active.active = true;
+        let idle = make_session(
+            "id2",
+            "/home/user/dev",
+            "~/dev",
+            now - TimeDelta::seconds(120),
+            Some("Idle"),
+            None,
+        );
+        let result = format_default(&[active, idle], now, &ListingOptions::default());
+        let line0 = result
+            .lines()
+            .find(|l| l.contains("Open elsewhere"))
+            .unwrap();
+        let line1 = result.lines().find(|l| l.contains("Idle")).unwrap();
+        assert!(line0.contains("\u{25cf} ~/dev"));
+        assert!(!line1.contains('\u{25cf}'));
+        assert_eq!(line0.find("~/dev"), line1.find("~/dev").map(|i| i + 2));
+    }
+
+    #[test]
+    fn short_no_marker_column_when_nothing_active() {
+        let now = fixed_now();
+        let sessions = vec![make_session(
+            "id1",
+            "/home/user/dev",
+            "~/dev",
+            now - TimeDelta::seconds(60),
+            Some("Test"),
+            None,
+        )];
+        let result = format_short(&sessions, now, &ListingOptions::default());
+        assert!(result.contains(" 1m  ~/dev"));
+    }
+
+    #[test]
+    fn short_active_marker() {
+        let now = fixed_now();
+        let mut session = make_session(
+            "id1",
+            "/home/user/dev",
+            "~/dev",
+            now - TimeDelta::seconds(60),
+            Some("Test"),
+            None,
+        );
+        session.active = true;
+        let result = format_short(&[session], now, &ListingOptions::default());
+        assert!(result.contains(" 1m \u{25cf} ~/dev"));
+    }
+
+    #[test]
+    fn json_active_field() {
+        let now = fixed_now();
+        let mut session = make_session(
+            "id1",
+            "/home/user/dev",
+            "~/dev",
+            now - TimeDelta::seconds(60),
+            Some("Test"),
+            None,
+        );
+        session.active = true;
+        let result = format_json(&[session], now, &ResumeTemplate::default(), &[]);
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed[0]["active"], true);
+    }
+
+    // --- continuation chains ---
+
+    fn chained_session(now: DateTime<Utc>) -> SessionInfo {
+        let mut session = make_session(
+            "id3",
+            "/home/user/dev",
+            "~/dev",
+            now - TimeDelta::seconds(60),
+            Some("Carry on"),
+            None,
+        );
+        session.earlier = vec![
+            make_session("id1", "/home/user/dev", "~/dev", now, Some("First"), None),
+            make_session("id2", "/home/user/dev", "~/dev", now, Some("Second"), None),
+        ];
+        session
+    }
+
+    #[test]
+    fn continued_marker_in_default_and_short() {
+        let now = fixed_now();
+        let plain = make_session("id4", "/home/user/dev", "~/dev", now, Some("Fresh"), None);
+        let sessions = vec![chained_session(now), plain];
+
+        let result = format_default(&sessions, now, &ListingOptions::default());
+        assert!(result.contains("\"Carry on\" (continued \u{d7}2)"));
+        assert!(
+            !result
+                .lines()
+                .any(|l| l.contains("Fresh") && l.contains("continued"))
+        );
+
+        let result = format_short(&sessions, now, &ListingOptions::default());
+        assert!(result.contains("Carry on (continued \u{d7}2)"));
+    }
+
+    #[test]
+    fn duplicates_marker_and_json() {
+        let now = fixed_now();
+        let mut session = chained_session(now);
+        session.duplicates = vec![make_session(
+            "id5",
+            "/home/user/dev",
+            "~/dev",
+            now,
+            Some("Carry on"),
+            None,
+        )];
+        let sessions = vec![session];
+
+        let result = format_default(&sessions, now, &ListingOptions::default());
+        assert!(result.contains("\"Carry on\" (continued \u{d7}2) (+1 duplicate)"));
+
+        let result = format_json(&sessions, now, &ResumeTemplate::default(), &[]);
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed[0]["duplicates"], serde_json::json!(["id5"]));
+    }
+
+    #[test]
+    fn json_continued_from_oldest_first() {
+        let now = fixed_now();
+        let result = format_json(
+            &[chained_session(now)],
+            now,
+            &ResumeTemplate::default(),
+            &[],
+        );
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            parsed[0]["continued_from"],
+            serde_json::json!(["id1", "id2"])
+        );
+        assert_eq!(parsed[0]["session_id"], "id3");
+    }
+
+    // --- ndjson / csv / tsv ---
+
+    fn prompt_session(prompt: &str) -> SessionInfo {
+        make_session(
+            "id1",
+            "/home/user/dev",
+            "~/dev",
+            fixed_now() - TimeDelta::seconds(60),
+            Some(prompt),
+            None,
+        )
+    }
+
+    #[test]
+    fn ndjson_one_object_per_line_in_field_order() {
+        let now = fixed_now();
+        let sessions = vec![prompt_session("First"), prompt_session("Second")];
+        let fields = [
+            SessionField::Slug,
+            SessionField::Index,
+            SessionField::FirstPrompt,
+        ];
+        let result = format_ndjson(&sessions, now, &ResumeTemplate::default(), &[], &fields);
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"slug":null,"index":0,"first_prompt":"First"}"#,
+                r#"{"slug":null,"index":1,"first_prompt":"Second"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn ndjson_all_fields_match_json_schema() {
+        let now = fixed_now();
+        let sessions = vec![prompt_session("Test")];
+        let template = ResumeTemplate::default();
+        let line = format_ndjson(&sessions, now, &template, &[], &SessionField::ALL);
+        let parsed: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+        let json: Vec<serde_json::Value> =
+            serde_json::from_str(&format_json(&sessions, now, &template, &[])).unwrap();
+        assert_eq!(parsed, json[0]);
+    }
+
+    #[test]
+    fn csv_quotes_commas_quotes_and_newlines() {
+        let now = fixed_now();
+        let sessions = vec![prompt_session("Fix a, b and \"c\"\nthen test")];
+        let fields = [
+            SessionField::Index,
+            SessionField::FirstPrompt,
+            SessionField::Slug,
+        ];
+        let result = format_csv(
+            &sessions,
+            now,
+            &ResumeTemplate::default(),
+            &[],
+            &fields,
+            true,
+        );
+        assert_eq!(
+            result,
+            "index,first_prompt,slug\n0,\"Fix a, b and \"\"c\"\"\nthen test\",\n"
+        );
+    }
+
+    #[test]
+    fn csv_without_header() {
+        let now = fixed_now();
+        let sessions = vec![prompt_session("Test")];
+        let fields = [SessionField::Index, SessionField::Active];
+        let result = format_csv(
+            &sessions,
+            now,
+            &ResumeTemplate::default(),
+            &[],
+            &fields,
+            false,
+        );
+        assert_eq!(result, "0,false\n");
+    }
+
+    #[test]
+    fn tsv_escapes_tabs_and_newlines() {
+        let now = fixed_now();
+        let mut session = prompt_session("a\tb\nc\\d");
+        session.earlier = vec![prompt_session("x"), prompt_session("y")];
+        session.earlier[0].session_id = "e0".to_string();
+        session.earlier[1].session_id = "e1".to_string();
+        let fields = [SessionField::FirstPrompt, SessionField::ContinuedFrom];
+        let result = format_tsv(
+            &[session],
+            now,
+            &ResumeTemplate::default(),
+            &[],
+            &fields,
+            true,
+        );
+        assert_eq!(
+            result,
+            "first_prompt\tcontinued_from\na\\tb\\nc\\\\d\te0 e1\n"
+        );
+    }
+
+    #[test]
+    fn session_field_names_match_json_keys() {
+        let now = fixed_now();
+        let result = format_json(
+            &[prompt_session("Test")],
+            now,
+            &ResumeTemplate::default(),
+            &[],
+        );
+        let parsed: Vec<serde_json::Map<String, serde_json::Value>> =
+            serde_json::from_str(&result).unwrap();
+        let keys: Vec<&str> = parsed[0].keys().map(String::as_str).collect();
+        let mut names: Vec<&str> = SessionField::ALL.iter().map(|f| f.name()).collect();
+        names.sort();
+        assert_eq!(keys, names);
+    }
 }
@@ -25,4 +25,63 @@ pub enum CcseshError {
 
     #[error("Unknown shell '{shell}'. Supported: fish, bash, zsh")]
     UnknownShell { shell: String },
+
+    #[error("Failed to read config file {path}: {source}")]
+    ConfigReadError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse config file {path}: {detail}")]
+    ConfigParseError { path: PathBuf, detail: String },
+
+    #[error("Invalid resume template: {reason}")]
+    InvalidResumeTemplate { reason: String },
+
+    #[error("Invalid output template: {reason}")]
+    InvalidOutputTemplate { reason: String },
+
+    #[error("No template named '{name}' in the config file")]
+    UnknownTemplate { name: String },
+
+    #[error("Invalid session URL: {reason}")]
+    InvalidSessionUrl { reason: String },
+
+    #[error("Invalid colour theme: {reason}")]
+    InvalidTheme { reason: String },
+
+    #[error("Invalid time setting: {reason}")]
+    InvalidTimeSetting { reason: String },
+
+    #[error("Invalid redact pattern: {reason}")]
+    InvalidRedactPattern { reason: String },
+
+    #[error("Invalid bundle: {reason}")]
+    InvalidBundle { reason: String },
+
+    #[error("Invalid path mapping '{spec}' (expected OLD=NEW with absolute paths)")]
+    InvalidPathMapping { spec: String },
+
+    #[error("Invalid diff point '{spec}': {reason}")]
+    InvalidDiffPoint { spec: String, reason: String },
+
+    #[error("Session already exists at {path} (use --force to replace it)")]
+    SessionExists { path: PathBuf },
+
+    #[error("Not running inside {name}")]
+    MultiplexerNotRunning { name: String },
+
+    #[error("{name} command failed: {detail}")]
+    MultiplexerFailed { name: String, detail: String },
+
+    #[error(
+        "Session {index} is already open in another terminal ({detail}). Use --force to resume anyway."
+    )]
+    SessionAlreadyActive { index: usize, detail: String },
+
+    #[error("No session with ID '{id}'")]
+    SessionNotFound { id: String },
+
+    #[error("Refusing to listen on non-loopback address {addr} without --token")]
+    UnsafeListenAddress { addr: String },
 }
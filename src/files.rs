@@ -0,0 +1,510 @@
+use std::io::BufRead;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::display;
+use crate::timefmt::TimeDisplay;
+use crate::transcript;
+use crate::types::{ContentBlock, MessageEntry, SessionInfo, TranscriptRecord};
+
+/// Tools whose path input is a file they change.
+pub const MODIFYING_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Tools whose path input is a file they only read.
+pub const READING_TOOLS: &[&str] = &["Read"];
+
+/// Longest first prompt shown by `who-touched` before it is truncated.
+const TITLE_WIDTH: usize = 60;
+
+/// A file named by one tool call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Touch {
+    /// Absolute and lexically normalized.
+    pub path: PathBuf,
+    pub modified: bool,
+}
+
+/// The files the tool calls of a user or assistant record read or change.
+/// Relative paths are resolved against the record's `cwd`.
+pub fn touches(entry: &MessageEntry) -> Vec<Touch> {
+    entry
+        .message
+        .content
+        .blocks()
+        .iter()
+        .filter_map(|block| {
+            let ContentBlock::ToolUse(tool) = block else {
+                return None;
+            };
+            let modified = MODIFYING_TOOLS.contains(&tool.name.as_str());
+            if !modified && !READING_TOOLS.contains(&tool.name.as_str()) {
+                return None;
+            }
+            let path = ["file_path", "notebook_path"]
+                .iter()
+                .find_map(|key| tool.input.get(key).and_then(Value::as_str))
+                .filter(|p| !p.is_empty())?;
+            let cwd = entry.meta.cwd.as_deref().unwrap_or("");
+            Some(Touch {
+                path: normalize(&Path::new(cwd).join(path)),
+                modified,
+            })
+        })
+        .collect()
+}
+
+/// `path` with `.` and `..` components resolved without touching the file
+/// system, so it compares equal to other spellings of the same path.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// A file a session read or modified.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileTouched {
+    pub path: String,
+    /// Read with the `Read` tool.
+    pub read: bool,
+    /// Changed with `Edit`, `MultiEdit`, `Write` or `NotebookEdit`.
+    pub modified: bool,
+    /// Timestamp of the last tool call naming it, if recorded.
+    pub last_touched: Option<String>,
+}
+
+/// Every file the session's tool calls read or modified, in the order they
+/// were first touched. Covers branches and sidechains too, since their tool
+/// calls ran all the same.
+pub fn files_touched<'a>(
+    records: impl IntoIterator<Item = &'a TranscriptRecord>,
+) -> Vec<FileTouched> {
+    let mut files: Vec<FileTouched> = Vec::new();
+    for record in records {
+        let (TranscriptRecord::User(entry) | TranscriptRecord::Assistant(entry)) = record else {
+            continue;
+        };
+        for touch in touches(entry) {
+            let path = touch.path.to_string_lossy().into_owned();
+            let index = match files.iter().position(|f| f.path == path) {
+                Some(i) => i,
+                None => {
+                    files.push(FileTouched {
+                        path,
+                        read: false,
+                        modified: false,
+                        last_touched: None,
+                    });
+                    files.len() - 1
+                }
+            };
+            let file = &mut files[index];
+            file.read |= !touch.modified;
+            file.modified |= touch.modified;
+            if entry.meta.timestamp.is_some() {
+                file.last_touched = entry.meta.timestamp.clone();
+            }
+        }
+    }
+    files
+}
+
+/// `files_touched()` of a session JSONL file.
+pub fn read_files_touched(path: &Path) -> Result<Vec<FileTouched>> {
+    Ok(files_touched(&transcript::read_records(path)?))
+}
+
+/// `path` relative to `dir` when it lies under it, for display.
+pub fn relative_to(path: &str, dir: &Path) -> String {
+    if dir.as_os_str().is_empty() {
+        return path.to_string();
+    }
+    match Path::new(path).strip_prefix(dir) {
+        Ok(rel) if !rel.as_os_str().is_empty() => rel.to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
+/// The file list printed by `ccsesh show --files`, paths relative to the
+/// session's project directory.
+pub fn format_files(files: &[FileTouched], project_dir: &Path) -> String {
+    if files.is_empty() {
+        return "No files read or modified.\n".to_string();
+    }
+    files
+        .iter()
+        .map(|f| {
+            format!(
+                "{:<8}  {}\n",
+                if f.modified { "modified" } else { "read" },
+                relative_to(&f.path, project_dir)
+            )
+        })
+        .collect()
+}
+
+/// A session that modified the file (or files under the directory) asked
+/// about.
+#[derive(Debug, Clone, Serialize)]
+pub struct Modifier {
+    pub session_id: String,
+    pub project_dir: String,
+    pub project_dir_display: String,
+    pub first_prompt: Option<String>,
+    pub slug: Option<String>,
+    /// When it last modified a matching file, or the file's mtime when its
+    /// records have no timestamps.
+    pub last_modified: DateTime<Utc>,
+    /// The matching files it modified.
+    pub files: Vec<String>,
+}
+
+/// Sessions whose tool calls modified `target` or, for a directory, any file
+/// under it, most recent modification first.
+pub fn who_touched(sessions: &[SessionInfo], target: &Path) -> Vec<Modifier> {
+    let needle = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut modifiers: Vec<Modifier> = sessions
+        .iter()
+        .filter_map(|session| modifier(session, target, &needle))
+        .collect();
+    modifiers.sort_by_key(|m| std::cmp::Reverse(m.last_modified));
+    modifiers
+}
+
+fn modifier(session: &SessionInfo, target: &Path, needle: &str) -> Option<Modifier> {
+    let file = std::fs::File::open(&session.path).ok()?;
+    let mut last_modified: Option<DateTime<Utc>> = None;
+    let mut files: Vec<String> = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let Ok(line) = line else { break };
+        // Most records never mention the file; skip them unparsed.
+        if !line.contains(needle) {
+            continue;
+        }
+        let Ok(TranscriptRecord::User(entry) | TranscriptRecord::Assistant(entry)) =
+            TranscriptRecord::parse(&line)
+        else {
+            continue;
+        };
+        for touch in touches(&entry) {
+            if !touch.modified || !touch.path.starts_with(target) {
+                continue;
+            }
+            let time = entry.meta.time_or(session.last_active);
+            last_modified = Some(last_modified.map_or(time, |t| t.max(time)));
+            let path = touch.path.to_string_lossy().into_owned();
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    Some(Modifier {
+        session_id: session.session_id.clone(),
+        project_dir: session.project_dir.to_string_lossy().into_owned(),
+        project_dir_display: session.project_dir_display.clone(),
+        first_prompt: session.first_prompt.clone(),
+        slug: session.slug.clone(),
+        last_modified: last_modified?,
+        files,
+    })
+}
+
+/// The listing printed by `ccsesh who-touched`: when, which session, and
+/// its first prompt, plus the matching files when `target` is a directory.
+pub fn format_modifiers(
+    modifiers: &[Modifier],
+    target: &Path,
+    time: &TimeDisplay,
+    now: DateTime<Utc>,
+) -> String {
+    if modifiers.is_empty() {
+        return format!("No session modified {}.\n", target.display());
+    }
+    let times: Vec<String> = modifiers
+        .iter()
+        .map(|m| time.long(m.last_modified, now))
+        .collect();
+    let time_width = times
+        .iter()
+        .map(|t| display::display_width(t))
+        .max()
+        .unwrap_or(0);
+    let project_width = modifiers
+        .iter()
+        .map(|m| display::display_width(&m.project_dir_display))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    for (modifier, when) in modifiers.iter().zip(&times) {
+        let title = modifier
+            .first_prompt
+            .as_deref()
+            .or(modifier.slug.as_deref())
+            .unwrap_or("");
+        let line = format!(
+            "{}  {}  {}  {}",
+            display::pad(when, time_width, true),
+            modifier.session_id,
+            display::pad(&modifier.project_dir_display, project_width, true),
+            display::truncate_prompt(title, TITLE_WIDTH)
+        );
+        out.push_str(line.trim_end());
+        out.push('\n');
+        if modifier.files.iter().any(|f| Path::new(f) != target) {
+            let files: Vec<String> = modifier
+                .files
+                .iter()
+                .map(|f| relative_to(f, target))
+                .collect();
+            out.push_str(&format!("    {}\n", files.join(", ")));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+
+    use super::*;
+
+    fn records(lines: &[&str]) -> Vec<TranscriptRecord> {
+        lines
+            .iter()
+            .map(|l| TranscriptRecord::parse(l).unwrap())
+            .collect()
+    }
+
+    fn tool_call(time: &str, tools: &[(&str, &str)]) -> String {
+        let blocks: Vec<String> = tools
+            .iter()
+            .enumerate()
+            .map(|(i, (name, path))| {
+                format!(
+                    r#"{{"type":"tool_use","id":"t{}","name":"{}","input":{{"file_path":"{}"}}}}"#,
+                    i, name, path
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"type":"assistant","cwd":"/work/api","timestamp":"{}","message":{{"role":"assistant","content":[{}]}}}}"#,
+            time,
+            blocks.join(",")
+        )
+    }
+
+    #[test]
+    fn collects_reads_and_modifications() {
+        let lines = [
+            tool_call(
+                "2026-02-17T09:00:00Z",
+                &[
+                    ("Read", "/work/api/src/users.rs"),
+                    ("Read", "/work/api/README.md"),
+                    ("Grep", "/work/api/src"),
+                ],
+            ),
+            tool_call(
+                "2026-02-17T09:05:00Z",
+                &[
+                    ("Edit", "/work/api/src/users.rs"),
+                    ("Write", "tests/new.rs"),
+                ],
+            ),
+            tool_call("2026-02-17T09:06:00Z", &[("Read", "/work/api/./README.md")]),
+        ];
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let files = files_touched(&records(&lines));
+
+        assert_eq!(
+            files,
+            vec![
+                FileTouched {
+                    path: "/work/api/src/users.rs".to_string(),
+                    read: true,
+                    modified: true,
+                    last_touched: Some("2026-02-17T09:05:00Z".to_string()),
+                },
+                FileTouched {
+                    path: "/work/api/README.md".to_string(),
+                    read: true,
+                    modified: false,
+                    last_touched: Some("2026-02-17T09:06:00Z".to_string()),
+                },
+                // Relative paths resolve against the record's cwd.
+                FileTouched {
+                    path: "/work/api/tests/new.rs".to_string(),
+                    read: false,
+                    modified: true,
+                    last_touched: Some("2026-02-17T09:05:00Z".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn notebook_edits_and_missing_paths() {
+        let lines = [
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"tool_use","id":"t1","name":"NotebookEdit","input":{"notebook_path":"/nb/a.ipynb"}},{"type":"tool_use","id":"t2","name":"Edit","input":{}},{"type":"tool_use","id":"t3","name":"Write","input":{"file_path":""}}]}}"#,
+        ];
+        let files = files_touched(&records(&lines));
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "/nb/a.ipynb");
+        assert!(files[0].modified);
+        assert_eq!(files[0].last_touched, None);
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(
+            normalize(Path::new("/a/./b/../c.rs")),
+            PathBuf::from("/a/c.rs")
+        );
+        assert_eq!(normalize(Path::new("/a/b/")), PathBuf::from("/a/b"));
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(relative_to("/a/b/c.rs", Path::new("/a")), "b/c.rs");
+        assert_eq!(relative_to("/ab/c.rs", Path::new("/a")), "/ab/c.rs");
+        assert_eq!(relative_to("/a/c.rs", Path::new("")), "/a/c.rs");
+    }
+
+    #[test]
+    fn formats_file_list() {
+        let files = vec![
+            FileTouched {
+                path: "/work/api/src/users.rs".to_string(),
+                read: true,
+                modified: true,
+                last_touched: None,
+            },
+            FileTouched {
+                path: "/etc/hosts".to_string(),
+                read: true,
+                modified: false,
+                last_touched: None,
+            },
+        ];
+        assert_eq!(
+            format_files(&files, Path::new("/work/api")),
+            "modified  src/users.rs\nread      /etc/hosts\n"
+        );
+        assert_eq!(
+            format_files(&[], Path::new("/work/api")),
+            "No files read or modified.\n"
+        );
+    }
+
+    fn session_file(dir: &TempDir, id: &str, lines: &[String]) -> SessionInfo {
+        let file = dir.child(format!("{}.jsonl", id));
+        file.write_str(&(lines.join("\n") + "\n")).unwrap();
+        SessionInfo {
+            path: file.path().to_path_buf(),
+            last_active: "2026-02-18T00:00:00Z".parse().unwrap(),
+            first_prompt: Some(format!("prompt {}", id)),
+            ..SessionInfo::fixture(id, "/work/api")
+        }
+    }
+
+    #[test]
+    fn finds_modifiers_most_recent_first() {
+        let dir = TempDir::new().unwrap();
+        let sessions = vec![
+            session_file(
+                &dir,
+                "old",
+                &[tool_call(
+                    "2026-02-10T09:00:00Z",
+                    &[("Edit", "/work/api/src/users.rs")],
+                )],
+            ),
+            session_file(
+                &dir,
+                "reader",
+                &[tool_call(
+                    "2026-02-17T09:00:00Z",
+                    &[("Read", "/work/api/src/users.rs")],
+                )],
+            ),
+            session_file(
+                &dir,
+                "new",
+                &[
+                    tool_call("2026-02-15T09:00:00Z", &[("Write", "src/users.rs")]),
+                    tool_call("2026-02-16T09:00:00Z", &[("Edit", "/work/api/src/lib.rs")]),
+                ],
+            ),
+        ];
+
+        let found = who_touched(&sessions, Path::new("/work/api/src/users.rs"));
+        let ids: Vec<&str> = found.iter().map(|m| m.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["new", "old"]);
+        assert_eq!(
+            found[0].last_modified,
+            "2026-02-15T09:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(found[0].files, vec!["/work/api/src/users.rs"]);
+
+        // A directory matches the files under it.
+        let found = who_touched(&sessions, Path::new("/work/api/src"));
+        assert_eq!(
+            found[0].files,
+            vec!["/work/api/src/users.rs", "/work/api/src/lib.rs"]
+        );
+        assert_eq!(
+            found[0].last_modified,
+            "2026-02-16T09:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        // A path that merely shares a prefix does not.
+        assert!(who_touched(&sessions, Path::new("/work/api/src/users")).is_empty());
+    }
+
+    #[test]
+    fn formats_modifiers() {
+        let dir = TempDir::new().unwrap();
+        let sessions = vec![session_file(
+            &dir,
+            "new",
+            &[
+                tool_call("2026-02-15T09:00:00Z", &[("Write", "src/users.rs")]),
+                tool_call("2026-02-16T09:00:00Z", &[("Edit", "/work/api/src/lib.rs")]),
+            ],
+        )];
+        let now = "2026-02-18T09:00:00Z".parse().unwrap();
+        let time = TimeDisplay::default();
+
+        let target = Path::new("/work/api/src/users.rs");
+        assert_eq!(
+            format_modifiers(&who_touched(&sessions, target), target, &time, now),
+            "3d ago  new  /work/api  prompt new\n"
+        );
+        let target = Path::new("/work/api/src");
+        assert_eq!(
+            format_modifiers(&who_touched(&sessions, target), target, &time, now),
+            "2d ago  new  /work/api  prompt new\n    users.rs, lib.rs\n"
+        );
+        assert_eq!(
+            format_modifiers(&[], Path::new("/x"), &time, now),
+            "No session modified /x.\n"
+        );
+    }
+}
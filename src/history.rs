@@ -0,0 +1,560 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::display;
+use crate::transcript;
+use crate::types::{ContentBlock, SessionInfo, TranscriptRecord};
+
+/// Shells whose history file format `ccsesh commands` can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HistoryShell {
+    Fish,
+    Zsh,
+    Bash,
+}
+
+/// A shell command Claude ran with the Bash tool.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandEntry {
+    pub command: String,
+    /// Exit status of the last run: 0 for a successful result, the code from
+    /// "Exit code N" for a failed one, and `None` when unknown.
+    pub exit_code: Option<i32>,
+    /// When it last ran.
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    /// Working directory of the last run.
+    pub cwd: Option<String>,
+    /// How many times it ran.
+    pub runs: usize,
+}
+
+/// Extracts Bash commands and their exit status from transcripts.
+pub struct Extractor {
+    exit_code: Regex,
+}
+
+impl Default for Extractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor {
+    pub fn new() -> Self {
+        Extractor {
+            exit_code: Regex::new(r"^Exit code (-?\d+)").unwrap(),
+        }
+    }
+
+    /// Every Bash tool call in `records`, in file order, each run counted
+    /// once. Records without a timestamp count as written at the file's
+    /// mtime.
+    pub fn commands(
+        &self,
+        session: &SessionInfo,
+        records: &[TranscriptRecord],
+    ) -> Vec<CommandEntry> {
+        let mut results: HashMap<&str, Option<i32>> = HashMap::new();
+        for record in records {
+            let TranscriptRecord::User(entry) = record else {
+                continue;
+            };
+            for block in entry.message.content.blocks() {
+                if let ContentBlock::ToolResult(result) = block {
+                    let exit_code = if result.is_error == Some(true) {
+                        result
+                            .content
+                            .as_ref()
+                            .and_then(|c| c.text())
+                            .and_then(|text| {
+                                self.exit_code
+                                    .captures(&text)
+                                    .and_then(|caps| caps[1].parse().ok())
+                            })
+                    } else {
+                        Some(0)
+                    };
+                    results.insert(&result.tool_use_id, exit_code);
+                }
+            }
+        }
+
+        let mut commands = Vec::new();
+        for record in records {
+            let TranscriptRecord::Assistant(entry) = record else {
+                continue;
+            };
+            let timestamp = entry.meta.time_or(session.last_active);
+            for block in entry.message.content.blocks() {
+                let ContentBlock::ToolUse(tool) = block else {
+                    continue;
+                };
+                if tool.name != "Bash" {
+                    continue;
+                }
+                let Some(command) = tool.input.get("command").and_then(Value::as_str) else {
+                    continue;
+                };
+                let command = command.trim();
+                if command.is_empty() {
+                    continue;
+                }
+                commands.push(CommandEntry {
+                    command: command.to_string(),
+                    exit_code: results.get(tool.id.as_str()).copied().flatten(),
+                    timestamp,
+                    session_id: session.session_id.clone(),
+                    cwd: entry.meta.cwd.clone(),
+                    runs: 1,
+                });
+            }
+        }
+        commands
+    }
+}
+
+/// The commands of all `sessions`, oldest first and deduplicated.
+/// Unreadable sessions are skipped.
+pub fn collect(sessions: &[SessionInfo]) -> Vec<CommandEntry> {
+    let extractor = Extractor::new();
+    let mut commands: Vec<CommandEntry> = sessions
+        .iter()
+        .filter_map(|s| {
+            let records = transcript::read_records(&s.path).ok()?;
+            Some(extractor.commands(s, &records))
+        })
+        .flatten()
+        .collect();
+    commands.sort_by_key(|c| c.timestamp);
+    dedupe(commands)
+}
+
+/// One entry per distinct command, at the position of its last run and
+/// carrying that run's details, like a shell's `erasedups` history option.
+pub fn dedupe(commands: Vec<CommandEntry>) -> Vec<CommandEntry> {
+    let mut runs: HashMap<String, usize> = HashMap::new();
+    for command in &commands {
+        *runs.entry(command.command.clone()).or_default() += 1;
+    }
+    let mut seen = HashSet::new();
+    let mut deduped: Vec<CommandEntry> = commands
+        .into_iter()
+        .rev()
+        .filter(|c| seen.insert(c.command.clone()))
+        .map(|mut c| {
+            c.runs = runs[&c.command];
+            c
+        })
+        .collect();
+    deduped.reverse();
+    deduped
+}
+
+impl HistoryShell {
+    /// `command` as an entry of this shell's history file, ending in a
+    /// newline.
+    pub fn format_entry(self, command: &CommandEntry) -> String {
+        let when = command.timestamp.timestamp();
+        match self {
+            HistoryShell::Fish => format!(
+                "- cmd: {}\n  when: {}\n",
+                command.command.replace('\\', "\\\\").replace('\n', "\\n"),
+                when
+            ),
+            HistoryShell::Zsh => {
+                format!(": {}:0;{}\n", when, command.command.replace('\n', "\\\n"))
+            }
+            HistoryShell::Bash => format!("#{}\n{}\n", when, command.command),
+        }
+    }
+
+    /// Whether this shell's history file can hold `command` as one entry.
+    /// Bash reads every line of its history file as a command of its own,
+    /// so a multi-line command (a heredoc, say) would come back in pieces.
+    pub fn can_store(self, command: &CommandEntry) -> bool {
+        self != HistoryShell::Bash || !command.command.contains('\n')
+    }
+
+    /// The part of an entry that does not depend on when it ran, used to
+    /// tell whether a history file already has the command.
+    fn command_part(self, command: &CommandEntry) -> String {
+        let entry = self.format_entry(command);
+        let start = match self {
+            HistoryShell::Fish => 0,
+            HistoryShell::Zsh => entry.find(';').map_or(0, |i| i + 1),
+            HistoryShell::Bash => entry.find('\n').map_or(0, |i| i + 1),
+        };
+        let end = match self {
+            HistoryShell::Fish => entry.find("\n  when:").unwrap_or(entry.len()),
+            _ => entry.len(),
+        };
+        entry[start..end].to_string()
+    }
+
+    /// Where the shell keeps its history by default.
+    pub fn default_file(self, home_dir: &str) -> PathBuf {
+        match self {
+            HistoryShell::Fish => {
+                let data = std::env::var("XDG_DATA_HOME")
+                    .ok()
+                    .filter(|d| !d.is_empty())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| Path::new(home_dir).join(".local/share"));
+                data.join("fish/fish_history")
+            }
+            HistoryShell::Zsh => Path::new(home_dir).join(".zsh_history"),
+            HistoryShell::Bash => Path::new(home_dir).join(".bash_history"),
+        }
+    }
+}
+
+/// The commands one per line, oldest first, for piping or `history -r`.
+pub fn format_plain(commands: &[CommandEntry]) -> String {
+    commands
+        .iter()
+        .map(|c| format!("{}\n", c.command))
+        .collect()
+}
+
+/// The commands in `shell`'s history file format, leaving out those it
+/// cannot hold (see `HistoryShell::can_store`).
+pub fn format_history(commands: &[CommandEntry], shell: HistoryShell) -> String {
+    commands
+        .iter()
+        .filter(|c| shell.can_store(c))
+        .map(|c| shell.format_entry(c))
+        .collect()
+}
+
+/// Stderr note about `count` commands left out because `shell` cannot hold
+/// them.
+pub fn unsupported_note(count: usize, shell: HistoryShell) -> String {
+    let name = shell.to_possible_value().map(|v| v.get_name().to_string());
+    format!(
+        "Left out {}: {} history keeps one line per command",
+        display::plural(count, "multi-line command"),
+        name.unwrap_or_default()
+    )
+}
+
+/// Result of appending to a history file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppendSummary {
+    pub appended: usize,
+    /// Commands the file already had, which were left out.
+    pub skipped: usize,
+    /// Commands the file cannot hold, which were left out too.
+    pub unsupported: usize,
+}
+
+/// Appends the commands `path` does not already contain, creating the file
+/// if needed. Nothing is rewritten, so a running shell's history is safe.
+pub fn append_history(
+    path: &Path,
+    commands: &[CommandEntry],
+    shell: HistoryShell,
+) -> Result<AppendSummary> {
+    let existing = match std::fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+        }
+    };
+    let with_newlines = format!("\n{}", existing);
+
+    let mut out = String::new();
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        out.push('\n');
+    }
+    let mut summary = AppendSummary {
+        appended: 0,
+        skipped: 0,
+        unsupported: 0,
+    };
+    for command in commands {
+        if !shell.can_store(command) {
+            summary.unsupported += 1;
+            continue;
+        }
+        let part = shell.command_part(command);
+        let present = match shell {
+            HistoryShell::Fish => with_newlines.contains(&format!("\n{}\n", part)),
+            HistoryShell::Zsh => with_newlines.contains(&format!(";{}", part)),
+            HistoryShell::Bash => with_newlines.contains(&format!("\n{}", part)),
+        };
+        if present {
+            summary.skipped += 1;
+        } else {
+            out.push_str(&shell.format_entry(command));
+            summary.appended += 1;
+        }
+    }
+    if summary.appended == 0 {
+        return Ok(summary);
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(out.as_bytes()))
+        .with_context(|| format!("Failed to append to {}", path.display()))?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn session(id: &str) -> SessionInfo {
+        SessionInfo {
+            path: PathBuf::from(format!("/nonexistent/{}.jsonl", id)),
+            last_active: at("2026-02-18T00:00:00Z"),
+            ..SessionInfo::fixture(id, "/work/api")
+        }
+    }
+
+    fn bash(id: &str, time: &str, command: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","cwd":"/work/api","timestamp":"{}","message":{{"role":"assistant","content":[{{"type":"tool_use","id":"{}","name":"Bash","input":{{"command":{}}}}}]}}}}"#,
+            time,
+            id,
+            serde_json::to_string(command).unwrap()
+        )
+    }
+
+    fn result(id: &str, is_error: bool, content: &str) -> String {
+        format!(
+            r#"{{"type":"user","message":{{"role":"user","content":[{{"type":"tool_result","tool_use_id":"{}","is_error":{},"content":{}}}]}}}}"#,
+            id,
+            is_error,
+            serde_json::to_string(content).unwrap()
+        )
+    }
+
+    fn commands(lines: &[String]) -> Vec<CommandEntry> {
+        let records: Vec<TranscriptRecord> = lines
+            .iter()
+            .map(|l| TranscriptRecord::parse(l).unwrap())
+            .collect();
+        Extractor::new().commands(&session("s1"), &records)
+    }
+
+    fn entry(command: &str, time: &str) -> CommandEntry {
+        CommandEntry {
+            command: command.to_string(),
+            exit_code: Some(0),
+            timestamp: at(time),
+            session_id: "s1".to_string(),
+            cwd: None,
+            runs: 1,
+        }
+    }
+
+    #[test]
+    fn extracts_commands_with_exit_status() {
+        let found = commands(&[
+            bash("t1", "2026-02-17T09:00:00Z", "cargo test"),
+            result("t1", false, "test result: ok"),
+            bash("t2", "2026-02-17T09:01:00Z", "cagro test"),
+            result("t2", true, "Exit code 127\nbash: cagro: command not found"),
+            bash("t3", "2026-02-17T09:02:00Z", "rm -rf /"),
+            result("t3", true, "Permission to use Bash has been denied."),
+            bash("t4", "2026-02-17T09:03:00Z", "  npm run dev  "),
+            bash("t5", "2026-02-17T09:04:00Z", "   "),
+        ]);
+        let summary: Vec<(&str, Option<i32>)> = found
+            .iter()
+            .map(|c| (c.command.as_str(), c.exit_code))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("cargo test", Some(0)),
+                ("cagro test", Some(127)),
+                ("rm -rf /", None),
+                // No result yet (interrupted, or still running).
+                ("npm run dev", None),
+            ]
+        );
+        assert_eq!(found[0].cwd.as_deref(), Some("/work/api"));
+        assert_eq!(found[0].timestamp, at("2026-02-17T09:00:00Z"));
+    }
+
+    #[test]
+    fn ignores_other_tools() {
+        let lines = [
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/a"}},{"type":"tool_use","id":"t2","name":"Bash","input":{}}]}}"#.to_string(),
+        ];
+        assert!(commands(&lines).is_empty());
+    }
+
+    #[test]
+    fn dedupes_keeping_last_run() {
+        let mut failed = entry("cargo test", "2026-02-17T09:05:00Z");
+        failed.exit_code = Some(101);
+        let deduped = dedupe(vec![
+            entry("cargo test", "2026-02-17T09:00:00Z"),
+            entry("git status", "2026-02-17T09:01:00Z"),
+            failed,
+        ]);
+        let order: Vec<&str> = deduped.iter().map(|c| c.command.as_str()).collect();
+        assert_eq!(order, vec!["git status", "cargo test"]);
+        assert_eq!(deduped[1].runs, 2);
+        assert_eq!(deduped[1].exit_code, Some(101));
+        assert_eq!(deduped[0].runs, 1);
+    }
+
+    #[test]
+    fn history_formats() {
+        let multi = entry(
+            "for f in *.rs; do\n  echo \"$f\" \\\ndone",
+            "2026-02-17T09:00:00Z",
+        );
+        assert_eq!(
+            HistoryShell::Fish.format_entry(&multi),
+            "- cmd: for f in *.rs; do\\n  echo \"$f\" \\\\\\ndone\n  when: 1771318800\n"
+        );
+        assert_eq!(
+            HistoryShell::Zsh.format_entry(&multi),
+            ": 1771318800:0;for f in *.rs; do\\\n  echo \"$f\" \\\\\ndone\n"
+        );
+        assert_eq!(
+            HistoryShell::Bash.format_entry(&entry("ls", "2026-02-17T09:00:00Z")),
+            "#1771318800\nls\n"
+        );
+        assert_eq!(
+            format_plain(&[
+                entry("ls", "2026-02-17T09:00:00Z"),
+                entry("pwd", "2026-02-17T09:00:00Z")
+            ]),
+            "ls\npwd\n"
+        );
+    }
+
+    #[test]
+    fn appends_only_new_commands() {
+        for shell in [HistoryShell::Fish, HistoryShell::Zsh, HistoryShell::Bash] {
+            let dir = TempDir::new().unwrap();
+            let file = dir.child("history");
+            let first = [entry("git status", "2026-02-17T09:00:00Z")];
+            assert_eq!(
+                append_history(file.path(), &first, shell).unwrap(),
+                AppendSummary {
+                    appended: 1,
+                    skipped: 0,
+                    unsupported: 0
+                }
+            );
+
+            // Again, later, with one new command: only that is added.
+            let second = [
+                entry("git status", "2026-02-18T09:00:00Z"),
+                entry("git log", "2026-02-18T09:01:00Z"),
+            ];
+            assert_eq!(
+                append_history(file.path(), &second, shell).unwrap(),
+                AppendSummary {
+                    appended: 1,
+                    skipped: 1,
+                    unsupported: 0
+                }
+            );
+            let written = std::fs::read_to_string(file.path()).unwrap();
+            assert_eq!(written.matches("git status").count(), 1, "{:?}", shell);
+            assert!(written.ends_with(&shell.format_entry(&second[1])));
+        }
+    }
+
+    #[test]
+    fn appends_after_unterminated_last_line() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.child(".bash_history");
+        file.write_str("ls").unwrap();
+        append_history(
+            file.path(),
+            &[entry("pwd", "2026-02-17T09:00:00Z")],
+            HistoryShell::Bash,
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            "ls\n#1771318800\npwd\n"
+        );
+    }
+
+    #[test]
+    fn bash_leaves_out_multiline_commands() {
+        let heredoc = entry(
+            "git commit -m \"$(cat <<'EOF'\nFix the login test\nEOF\n)\"",
+            "2026-02-17T09:00:00Z",
+        );
+        let commands = [heredoc, entry("git push", "2026-02-17T09:01:00Z")];
+        assert_eq!(
+            format_history(&commands, HistoryShell::Bash),
+            "#1771318860\ngit push\n"
+        );
+        assert!(HistoryShell::Zsh.can_store(&commands[0]));
+
+        let dir = TempDir::new().unwrap();
+        let file = dir.child(".bash_history");
+        let summary = append_history(file.path(), &commands, HistoryShell::Bash).unwrap();
+        assert_eq!(
+            summary,
+            AppendSummary {
+                appended: 1,
+                skipped: 0,
+                unsupported: 1
+            }
+        );
+        file.assert("#1771318860\ngit push\n");
+    }
+
+    #[test]
+    fn prefix_of_existing_command_is_new() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.child(".zsh_history");
+        file.write_str(": 1:0;git status --short\n").unwrap();
+        let summary = append_history(
+            file.path(),
+            &[entry("git status", "2026-02-17T09:00:00Z")],
+            HistoryShell::Zsh,
+        )
+        .unwrap();
+        assert_eq!(summary.appended, 1);
+    }
+
+    #[test]
+    fn default_files() {
+        assert_eq!(
+            HistoryShell::Zsh.default_file("/home/me"),
+            PathBuf::from("/home/me/.zsh_history")
+        );
+        assert_eq!(
+            HistoryShell::Bash.default_file("/home/me"),
+            PathBuf::from("/home/me/.bash_history")
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use std::io::IsTerminal;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::errors::CcseshError;
+use crate::types::SessionInfo;
+
+/// When to emit OSC 8 hyperlinks in the text listings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HyperlinkMode {
+    /// Never (the default)
+    #[default]
+    Never,
+    /// Only when stdout is a terminal known to support them
+    Auto,
+    /// Always, even when piped
+    Always,
+}
+
+/// A URL template for a session's link, e.g.
+/// `http://127.0.0.1:7777/sessions/{id}/transcript`.
+///
+/// Placeholders:
+/// - `{id}` -- session ID
+/// - `{dir}` -- project directory, percent-encoded
+/// - `{path}` -- transcript (JSONL) file path, percent-encoded
+#[derive(Debug, Clone)]
+pub struct SessionUrlTemplate {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Id,
+    Dir,
+    Path,
+}
+
+impl SessionUrlTemplate {
+    pub fn parse(template: &str) -> Result<Self, CcseshError> {
+        let invalid = |reason: String| CcseshError::InvalidSessionUrl { reason };
+
+        // An ESC or BEL in the URL would end the escape sequence early.
+        if template.chars().any(|c| c.is_control()) {
+            return Err(invalid("must not contain control characters".into()));
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(open) = rest.find(['{', '}']) {
+            if rest[open..].starts_with('}') {
+                return Err(invalid("unmatched '}'".into()));
+            }
+            if open > 0 {
+                segments.push(Segment::Literal(rest[..open].to_string()));
+            }
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| invalid(format!("unclosed placeholder '{}'", &rest[open..])))?
+                + open;
+            segments.push(match &rest[open + 1..close] {
+                "id" => Segment::Id,
+                "dir" => Segment::Dir,
+                "path" => Segment::Path,
+                other => {
+                    return Err(invalid(format!(
+                        "unknown placeholder '{{{}}}' (expected {{id}}, {{dir}} or {{path}})",
+                        other
+                    )));
+                }
+            });
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, session: &SessionInfo) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(s) => s.clone(),
+                Segment::Id => percent_encode(&session.session_id),
+                Segment::Dir => percent_encode(&session.project_dir.to_string_lossy()),
+                Segment::Path => percent_encode(&session.path.to_string_lossy()),
+            })
+            .collect()
+    }
+}
+
+/// Link targets for the text listings: the project path links to its
+/// directory, the summary to the session's transcript (the JSONL file, or
+/// `session_url` when configured).
+#[derive(Debug, Clone, Default)]
+pub struct Hyperlinks {
+    session_url: Option<SessionUrlTemplate>,
+}
+
+impl Hyperlinks {
+    pub fn new(session_url: Option<SessionUrlTemplate>) -> Self {
+        Self { session_url }
+    }
+
+    /// `Some` when links should be emitted under `mode`. `Auto` requires stdout
+    /// to be a terminal and the terminal to be known to support OSC 8.
+    pub fn resolve(mode: HyperlinkMode, session_url: Option<SessionUrlTemplate>) -> Option<Self> {
+        let enabled = match mode {
+            HyperlinkMode::Never => false,
+            HyperlinkMode::Always => true,
+            HyperlinkMode::Auto => {
+                std::io::stdout().is_terminal()
+                    && terminal_supports_hyperlinks(|name| std::env::var(name).ok())
+            }
+        };
+        enabled.then(|| Self::new(session_url))
+    }
+
+    pub fn project_url(&self, session: &SessionInfo) -> String {
+        file_url(&session.project_dir)
+    }
+
+    pub fn session_url(&self, session: &SessionInfo) -> String {
+        match &self.session_url {
+            Some(template) => template.render(session),
+            None => file_url(&session.path),
+        }
+    }
+}
+
+/// Wrap `text` in an OSC 8 hyperlink to `url`. The escape sequences take no
+/// columns, so padding computed for `text` still lines up.
+pub fn link(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// `file://` URL for an absolute path (empty host, i.e. this machine).
+pub fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let encoded: String = path
+        .split('/')
+        .map(percent_encode)
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("file://{}", encoded)
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Whether the terminal described by the environment is known to render
+/// OSC 8 links. Terminals that don't support them usually print the URL or
+/// garbage, so unknown terminals count as unsupported. `FORCE_HYPERLINK`
+/// overrides the guess either way.
+pub fn terminal_supports_hyperlinks(env: impl Fn(&str) -> Option<String>) -> bool {
+    if let Some(force) = env("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+    if env("DOMTERM").is_some() || env("WT_SESSION").is_some() || env("KITTY_WINDOW_ID").is_some() {
+        return true;
+    }
+    // GNOME Terminal and other VTE terminals since 0.50.
+    if let Some(version) = env("VTE_VERSION") {
+        return version.parse::<u32>().is_ok_and(|v| v >= 5000);
+    }
+    if let Some(program) = env("TERM_PROGRAM")
+        && matches!(
+            program.as_str(),
+            "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+        )
+    {
+        return true;
+    }
+    env("TERM").is_some_and(|term| {
+        matches!(
+            term.as_str(),
+            "xterm-kitty" | "alacritty" | "foot" | "xterm-ghostty"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn session() -> SessionInfo {
+        SessionInfo {
+            path: PathBuf::from("/home/u/.claude/projects/-home-u-my app/abc-123.jsonl"),
+            project_dir_display: "~/my app".to_string(),
+            ..SessionInfo::fixture("abc-123", "/home/u/my app")
+        }
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn file_urls_are_percent_encoded() {
+        assert_eq!(
+            file_url(Path::new("/home/u/my app/日本")),
+            "file:///home/u/my%20app/%E6%97%A5%E6%9C%AC"
+        );
+    }
+
+    #[test]
+    fn link_wraps_text_in_osc8() {
+        assert_eq!(
+            link("file:///tmp", "tmp"),
+            "\x1b]8;;file:///tmp\x1b\\tmp\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn session_url_defaults_to_transcript_file() {
+        let links = Hyperlinks::default();
+        assert_eq!(
+            links.session_url(&session()),
+            "file:///home/u/.claude/projects/-home-u-my%20app/abc-123.jsonl"
+        );
+        assert_eq!(links.project_url(&session()), "file:///home/u/my%20app");
+    }
+
+    #[test]
+    fn session_url_template_renders_placeholders() {
+        let template =
+            SessionUrlTemplate::parse("http://127.0.0.1:7777/sessions/{id}/transcript?d={dir}")
+                .unwrap();
+        assert_eq!(
+            Hyperlinks::new(Some(template)).session_url(&session()),
+            "http://127.0.0.1:7777/sessions/abc-123/transcript?d=%2Fhome%2Fu%2Fmy%20app"
+        );
+    }
+
+    #[test]
+    fn session_url_template_rejects_bad_input() {
+        for bad in [
+            "http://x/{nope}",
+            "http://x/{id",
+            "http://x/}",
+            "http://x/\x1b",
+        ] {
+            let err = SessionUrlTemplate::parse(bad).unwrap_err();
+            assert!(
+                err.to_string().starts_with("Invalid session URL"),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn never_and_always_ignore_the_terminal() {
+        assert!(Hyperlinks::resolve(HyperlinkMode::Never, None).is_none());
+        assert!(Hyperlinks::resolve(HyperlinkMode::Always, None).is_some());
+    }
+
+    #[test]
+    fn detects_supporting_terminals() {
+        assert!(terminal_supports_hyperlinks(env(&[(
+            "TERM_PROGRAM",
+            "WezTerm"
+        )])));
+        assert!(terminal_supports_hyperlinks(env(&[(
+            "TERM",
+            "xterm-kitty"
+        )])));
+        assert!(terminal_supports_hyperlinks(env(&[(
+            "VTE_VERSION",
+            "7600"
+        )])));
+        assert!(!terminal_supports_hyperlinks(env(&[(
+            "VTE_VERSION",
+            "4200"
+        )])));
+        assert!(!terminal_supports_hyperlinks(env(&[(
+            "TERM",
+            "xterm-256color"
+        )])));
+        assert!(!terminal_supports_hyperlinks(env(&[])));
+        assert!(terminal_supports_hyperlinks(env(&[(
+            "FORCE_HYPERLINK",
+            "1"
+        )])));
+        assert!(!terminal_supports_hyperlinks(env(&[
+            ("FORCE_HYPERLINK", "0"),
+            ("TERM", "xterm-kitty")
+        ])));
+    }
+}
@@ -1,9 +1,34 @@
+pub mod active;
+pub mod bundle;
+pub mod chain;
+pub mod config;
+pub mod dedupe;
+pub mod diff;
 pub mod discover;
 pub mod display;
 pub mod errors;
+pub mod files;
+pub mod history;
+pub mod hyperlink;
+pub mod mcp;
+pub mod multiplexer;
 pub mod parse;
+pub mod redact;
+pub mod relocate;
+pub mod report;
+pub mod search;
+pub mod secrets;
+pub mod serve;
+pub mod sessions;
 pub mod shell;
+pub mod template;
+pub mod theme;
+pub mod thread;
+pub mod timefmt;
+pub mod timeline;
+pub mod transcript;
 pub mod types;
+pub mod watch;
 
 #[cfg(test)]
 mod uuid_cross_reference_tests {
@@ -1,15 +1,37 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use anyhow::Result;
 use chrono::Utc;
 use clap::Parser;
 
-use ccsesh::discover;
+use ccsesh::active;
+use ccsesh::bundle::{self, PathMapping};
+use ccsesh::config;
+use ccsesh::dedupe;
+use ccsesh::diff;
 use ccsesh::display;
 use ccsesh::errors::CcseshError;
-use ccsesh::parse;
+use ccsesh::files;
+use ccsesh::history::{self, HistoryShell};
+use ccsesh::hyperlink::{HyperlinkMode, Hyperlinks, SessionUrlTemplate};
+use ccsesh::mcp;
+use ccsesh::multiplexer::{self, Multiplexer, MuxLauncher, MuxOutcome};
+use ccsesh::redact::{Identity, Redactor};
+use ccsesh::relocate;
+use ccsesh::report;
+use ccsesh::secrets;
+use ccsesh::serve;
+use ccsesh::sessions::{self, load_sessions};
 use ccsesh::shell;
-use ccsesh::types::{OutputFormat, SessionInfo};
+use ccsesh::template::OutputTemplate;
+use ccsesh::theme::{self, ColorMode, Theme};
+use ccsesh::timefmt::{self, TimeDisplay, TimeMode, Zone};
+use ccsesh::timeline;
+use ccsesh::transcript;
+use ccsesh::types::{ActiveMatch, OutputFormat, SessionField, SessionInfo};
+use ccsesh::watch;
 
 #[derive(Parser)]
 #[command(
@@ -18,11 +40,18 @@ use ccsesh::types::{OutputFormat, SessionInfo};
     about = "List and resume recent Claude Code sessions"
 )]
 struct Cli {
-    /// Session index to resume, or "init" subcommand
+    /// Session index to resume, or "init" / "show" / "export" / "watch" / "serve" / "mcp" /
+    /// "scan-secrets" / "bundle" / "import" / "relocate" / "timeline" / "report" / "who-touched" / "commands" /
+    /// "diff" / "dedupe" subcommand
     command: Option<String>,
 
-    /// Shell type for init (fish, bash, zsh)
-    shell: Option<String>,
+    /// Shell type for init (fish, bash, zsh), session index/ID for
+    /// show/export/scan-secrets/bundle/commands, bundle file for import,
+    /// path for who-touched, or the first session for diff (<N|id>[@FROM[-TO]])
+    target: Option<String>,
+
+    /// Diff: the session to compare the first with (<N|id>[@FROM[-TO]])
+    other: Option<String>,
 
     #[arg(short, long, default_value_t = 5)]
     limit: usize,
@@ -30,56 +59,167 @@ struct Cli {
     #[arg(long, default_value = "default")]
     format: OutputFormat,
 
+    /// Columns for the ndjson, csv, and tsv formats (comma-separated)
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',')]
+    fields: Vec<SessionField>,
+
+    /// Omit the header row from csv and tsv output
+    #[arg(long)]
+    no_header: bool,
+
+    /// Print each session with an output template, or a named template from
+    /// the config file. Example: '{index} {time_short} {project} {prompt:40}'
+    #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["json", "format"])]
+    template: Option<String>,
+
     #[arg(long)]
     json: bool,
 
-    #[arg(long, hide = true)]
-    shell_mode: Option<String>,
-}
+    /// Link project paths and summaries in the listing (OSC 8 hyperlinks):
+    /// auto (supported terminals only), always, or never
+    #[arg(
+        long,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto"
+    )]
+    hyperlinks: Option<HyperlinkMode>,
 
-/// Discover, parse, and filter sessions. Returns up to `limit` valid sessions
-/// (excludes team subagent sessions and empty sessions with no prompt or slug).
-fn load_sessions(home_dir: &str, limit: usize) -> Result<Vec<SessionInfo>> {
-    if limit == 0 {
-        return Ok(vec![]);
-    }
+    /// When to colour output: auto (honours NO_COLOR and CLICOLOR_FORCE),
+    /// always, or never
+    #[arg(long, value_name = "WHEN")]
+    color: Option<ColorMode>,
 
-    // Over-discover to compensate for filtered subagent/empty sessions
-    let discover_limit = (limit * 5).max(50);
-    let candidates = discover::discover_sessions(home_dir, discover_limit)?;
+    /// How to show last-active times: relative, absolute, or both
+    #[arg(long, value_name = "MODE")]
+    time: Option<TimeMode>,
 
-    if candidates.is_empty() {
-        return Err(CcseshError::NoSessionsFound.into());
-    }
+    /// Time zone for absolute times: local, utc, an offset like +05:30, or a
+    /// name like Europe/Berlin
+    #[arg(long, value_name = "ZONE")]
+    tz: Option<String>,
 
-    let mut sessions = Vec::new();
-    for candidate in &candidates {
-        if sessions.len() >= limit {
-            break;
-        }
-        match parse::parse_session(candidate, home_dir) {
-            Ok(info) => {
-                // Skip empty sessions (no prompt and no slug)
-                if info.first_prompt.is_none() && info.slug.is_none() {
-                    continue;
-                }
-                sessions.push(info);
-            }
-            Err(_) => continue, // Includes subagent sessions and parse errors
-        }
-    }
+    /// Resume command template, overriding `resume_template` in the config file.
+    /// Placeholders: {dir}, {id}, {args}
+    #[arg(long, value_name = "TEMPLATE")]
+    resume_template: Option<String>,
 
-    if sessions.is_empty() {
-        return Err(CcseshError::NoSessionsFound.into());
-    }
+    /// Resume in a new tmux window named after the session
+    #[arg(long, conflicts_with_all = ["zellij", "screen"])]
+    tmux: bool,
+
+    /// Resume in a new zellij tab named after the session
+    #[arg(long, conflicts_with = "screen")]
+    zellij: bool,
+
+    /// Resume in a new GNU screen window named after the session
+    #[arg(long)]
+    screen: bool,
+
+    /// List every session of a continuation chain, and every duplicate, as its
+    /// own entry
+    #[arg(long)]
+    expand: bool,
+
+    /// Show/export: include abandoned branches and sidechains
+    #[arg(long)]
+    branches: bool,
+
+    /// Show: list the files the session read or modified instead of the
+    /// conversation
+    #[arg(long)]
+    files: bool,
+
+    /// Show/export: replace secrets, the home directory, user and host names,
+    /// and `redact_patterns` matches with placeholders
+    #[arg(long)]
+    redact: bool,
+
+    /// MCP: return secrets and `redact_patterns` matches in tool output
+    /// instead of placeholders
+    #[arg(long)]
+    no_redact: bool,
+
+    /// Resume even if the session appears to be open in another terminal;
+    /// import: replace an existing copy of the session; relocate and dedupe:
+    /// include open sessions
+    #[arg(long)]
+    force: bool,
+
+    /// Watch mode: poll for changes instead of using inotify
+    #[arg(long)]
+    poll: bool,
 
-    Ok(sessions)
+    /// Serve: address to listen on (loopback only unless --token is set)
+    #[arg(long, value_name = "ADDR", default_value = serve::DEFAULT_LISTEN)]
+    listen: String,
+
+    /// Serve: require `Authorization: Bearer <TOKEN>` on every request
+    #[arg(long, value_name = "TOKEN")]
+    token: Option<String>,
+
+    /// Bundle: file to write [default: <session-id>.ccsesh]; commands: history
+    /// file to append to [default: the shell's own]
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Import: rewrite working directories under OLD to NEW (repeatable)
+    #[arg(long, value_name = "OLD=NEW")]
+    map_path: Vec<String>,
+
+    /// Relocate: the directory the project used to be in
+    #[arg(long, value_name = "DIR")]
+    from: Option<String>,
+
+    /// Relocate: the directory it is in now
+    #[arg(long, value_name = "DIR")]
+    to: Option<String>,
+
+    /// Relocate and dedupe: list the sessions that would change without
+    /// changing them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Timeline and report: how far back to look, e.g. 90d or 26w
+    /// [default: 52w for timeline, 7d for report]
+    #[arg(long, value_name = "AGE")]
+    since: Option<String>,
+
+    /// Commands: print in this shell's history file format (fish, zsh, bash)
+    #[arg(long, value_name = "SHELL")]
+    history: Option<HistoryShell>,
+
+    /// Commands: append to the --history shell's history file, skipping
+    /// commands it already has
+    #[arg(long, requires = "history")]
+    append: bool,
+
+    #[arg(long, hide = true)]
+    shell_mode: Option<String>,
+
+    /// Extra arguments passed through to `claude` when resuming (after `--`)
+    #[arg(last = true, value_name = "CLAUDE_ARGS")]
+    claude_args: Vec<String>,
 }
 
 fn run() -> Result<()> {
     let cli = Cli::parse();
 
     let home_dir = std::env::var("HOME").map_err(|_| CcseshError::HomeDirectoryNotFound)?;
+    let config = config::load_config(&home_dir)?;
+    theme::init_color(cli.color.or(config.color).unwrap_or_default());
+    let theme = Theme::from_config(config.theme.as_deref(), &config.colors)?;
+    let time = time_display(&cli, &config)?;
+
+    let resume_template = match cli
+        .resume_template
+        .as_deref()
+        .or(config.resume_template.as_deref())
+    {
+        Some(t) => shell::ResumeTemplate::parse(t)?,
+        None => shell::ResumeTemplate::default(),
+    };
 
     match cli.command.as_deref() {
         None => {
@@ -89,15 +229,55 @@ fn run() -> Result<()> {
                 );
             }
 
-            let sessions = load_sessions(&home_dir, cli.limit)?;
+            // Resolved before loading so a bad template fails fast.
+            let output_template = match &cli.template {
+                Some(t) if t.contains('{') => Some(OutputTemplate::parse(t)?),
+                Some(name) => {
+                    let source = config
+                        .templates
+                        .get(name)
+                        .ok_or_else(|| CcseshError::UnknownTemplate { name: name.clone() })?;
+                    Some(OutputTemplate::parse(source)?)
+                }
+                None => None,
+            };
+            let hyperlinks = hyperlinks(&cli, &config)?;
+
+            let sessions = listing(&home_dir, cli.limit, cli.expand)?;
 
             let now = Utc::now();
-            let output = if cli.json {
-                display::format_json(&sessions, now)
+            let fields = if cli.fields.is_empty() {
+                SessionField::ALL.to_vec()
+            } else {
+                cli.fields
+            };
+            let (template, args) = (&resume_template, &cli.claude_args);
+            let output = if let Some(output_template) = &output_template {
+                output_template.render_all(&sessions, now, template, args)
+            } else if cli.json {
+                display::format_json(&sessions, now, template, args)
             } else {
+                let options = display::ListingOptions {
+                    term_width: display::terminal_width(),
+                    hyperlinks: hyperlinks.as_ref(),
+                    theme,
+                    time,
+                };
                 match cli.format {
-                    OutputFormat::Short => display::format_short(&sessions, now),
-                    OutputFormat::Default => display::format_default(&sessions, now),
+                    OutputFormat::Short => display::format_short(&sessions, now, &options),
+                    OutputFormat::Default => display::format_default(&sessions, now, &options),
+                    OutputFormat::Ndjson => {
+                        display::format_ndjson(&sessions, now, template, args, &fields)
+                    }
+                    OutputFormat::Csv => {
+                        display::format_csv(&sessions, now, template, args, &fields, !cli.no_header)
+                    }
+                    OutputFormat::Tsv => {
+                        display::format_tsv(&sessions, now, template, args, &fields, !cli.no_header)
+                    }
+                    OutputFormat::Md => {
+                        anyhow::bail!("--format md is only supported by `ccsesh report`")
+                    }
                 }
             };
 
@@ -105,20 +285,321 @@ fn run() -> Result<()> {
         }
         Some("init") => {
             let shell = cli
-                .shell
+                .target
                 .as_deref()
                 .ok_or_else(|| anyhow::anyhow!("Usage: ccsesh init <fish|bash|zsh>"))?;
             shell::print_shell_init(shell)?;
         }
+        Some(command @ ("show" | "export")) => {
+            let target = cli
+                .target
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("Usage: ccsesh {} <index|session-id>", command))?;
+            let (index, session) = sessions::resolve_session(&home_dir, target, cli.expand)?;
+            let (mut messages, mut branches) = transcript::read_thread(&session.path)?;
+            let mut redactor = if cli.redact {
+                Some(Redactor::new(
+                    &home_dir,
+                    &Identity::detect(&home_dir),
+                    &config.redact_patterns,
+                )?)
+            } else {
+                None
+            };
+
+            if command == "show" && cli.files {
+                let mut files = files::read_files_touched(&session.path)?;
+                if let Some(redactor) = &mut redactor {
+                    for file in &mut files {
+                        file.path = redactor.redact_text(&file.path);
+                    }
+                }
+                print!("{}", files::format_files(&files, &session.project_dir));
+            } else if command == "show" {
+                if let Some(redactor) = &mut redactor {
+                    let branch_messages = branches.iter_mut().flat_map(|b| &mut b.messages);
+                    for message in messages.iter_mut().chain(branch_messages) {
+                        message.text = redactor.redact_text(&message.text);
+                    }
+                }
+                print!("{}", transcript::render_markdown(&messages));
+                if cli.branches {
+                    print!("{}", transcript::render_branches_markdown(&branches));
+                }
+            } else {
+                let export = display::JsonExport {
+                    session: display::json_session(
+                        index,
+                        &session,
+                        Utc::now(),
+                        &resume_template,
+                        &cli.claude_args,
+                    ),
+                    messages: &messages,
+                    files_touched: &files::read_files_touched(&session.path)?,
+                    branches: cli.branches.then_some(branches.as_slice()),
+                };
+                match &mut redactor {
+                    Some(redactor) => {
+                        let mut value = serde_json::to_value(&export)?;
+                        redactor.redact_value(&mut value);
+                        println!("{}", serde_json::to_string_pretty(&value)?);
+                    }
+                    None => println!("{}", display::format_export(&export)),
+                }
+            }
+            if let Some(redactor) = &redactor {
+                eprint!("{}", redactor.report().format());
+            }
+        }
+        Some("watch") => {
+            let hyperlinks = hyperlinks(&cli, &config)?;
+            watch::run_watch(
+                &home_dir,
+                &watch::WatchOptions {
+                    limit: cli.limit,
+                    json: cli.json,
+                    force_poll: cli.poll,
+                    template: &resume_template,
+                    args: &cli.claude_args,
+                    hyperlinks: hyperlinks.as_ref(),
+                    theme,
+                    time: &time,
+                },
+            )?;
+        }
+        Some("serve") => {
+            serve::serve(
+                &cli.listen,
+                serve::ServeContext {
+                    home_dir,
+                    default_limit: cli.limit,
+                    token: cli.token,
+                    template: resume_template,
+                    args: cli.claude_args,
+                },
+            )?;
+        }
+        Some("mcp") => {
+            let server = mcp::McpServer {
+                home_dir,
+                template: resume_template,
+                args: cli.claude_args,
+                redact: !cli.no_redact,
+                redact_patterns: config.redact_patterns,
+            };
+            server.run(std::io::stdin().lock(), std::io::stdout().lock())?;
+        }
+        Some("scan-secrets") => {
+            let sessions = match cli.target.as_deref() {
+                Some(target) => vec![sessions::resolve_session(&home_dir, target, cli.expand)?.1],
+                None => sessions::load_all_sessions(&home_dir)?,
+            };
+            let report = secrets::scan_sessions(&sessions);
+            if cli.json {
+                println!("{}", secrets::format_report_json(&report));
+            } else {
+                print!("{}", secrets::format_report(&report));
+            }
+            if report.finding_count() > 0 {
+                std::io::stdout().flush()?;
+                process::exit(1);
+            }
+        }
+        Some("bundle") => {
+            let target = cli.target.as_deref().ok_or_else(|| {
+                anyhow::anyhow!("Usage: ccsesh bundle <index|session-id> [-o FILE]")
+            })?;
+            let (_, session) = sessions::resolve_session(&home_dir, target, cli.expand)?;
+            let out = cli.output.unwrap_or_else(|| {
+                PathBuf::from(format!(
+                    "{}.{}",
+                    session.session_id,
+                    bundle::BUNDLE_EXTENSION
+                ))
+            });
+            let manifest = bundle::create_bundle(&home_dir, &session, &out)?;
+            println!(
+                "Bundled session {} ({}) into {}",
+                manifest.session_id,
+                display::plural(manifest.files.len(), "file"),
+                out.display()
+            );
+        }
+        Some("import") => {
+            let file = cli.target.as_deref().ok_or_else(|| {
+                anyhow::anyhow!("Usage: ccsesh import <file.ccsesh> [--map-path OLD=NEW]")
+            })?;
+            let mappings = cli
+                .map_path
+                .iter()
+                .map(|spec| PathMapping::parse(spec))
+                .collect::<Result<Vec<_>, _>>()?;
+            let summary = bundle::import_bundle(&home_dir, Path::new(file), &mappings, cli.force)?;
+            println!(
+                "Imported session {} into {} ({}, {} working director{} rewritten)",
+                summary.session_id,
+                summary.session_path.display(),
+                display::plural(summary.files, "file"),
+                summary.rewritten,
+                if summary.rewritten == 1 { "y" } else { "ies" }
+            );
+            if !summary.project_dir.is_empty() && !Path::new(&summary.project_dir).is_dir() {
+                eprintln!(
+                    "Note: {} does not exist on this machine; use --map-path OLD=NEW to point the session elsewhere.",
+                    summary.project_dir
+                );
+            }
+        }
+        Some("relocate") => {
+            let (Some(from), Some(to)) = (&cli.from, &cli.to) else {
+                anyhow::bail!("Usage: ccsesh relocate --from <old dir> --to <new dir> [--dry-run]");
+            };
+            let mapping = PathMapping::parse(&format!(
+                "{}={}",
+                expand_tilde(from, &home_dir),
+                expand_tilde(to, &home_dir)
+            ))?;
+            let moves = relocate::plan(&home_dir, &mapping)?;
+            let applied = if cli.dry_run {
+                Vec::new()
+            } else {
+                relocate::apply(&moves, &mapping, cli.force)?
+            };
+            print!(
+                "{}",
+                relocate::format_report(&moves, &applied, &mapping, cli.dry_run)
+            );
+        }
+        Some("timeline") => {
+            let spec = cli.since.as_deref().unwrap_or(timeline::DEFAULT_SINCE);
+            let now = time.zone.local(Utc::now());
+            let start = now
+                .checked_sub_signed(timefmt::parse_age(spec)?)
+                .ok_or_else(|| CcseshError::InvalidTimeSetting {
+                    reason: format!("age '{}' reaches before the earliest supported date", spec),
+                })?;
+            let sessions = sessions::load_all_sessions(&home_dir)?;
+            let timeline =
+                timeline::timeline_for_sessions(&sessions, time.zone, start.date(), now.date());
+            if cli.json {
+                println!("{}", timeline::format_timeline_json(&timeline));
+            } else {
+                print!("{}", timeline::format_timeline(&timeline));
+            }
+        }
+        Some("who-touched") => {
+            let target = cli
+                .target
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("Usage: ccsesh who-touched <path>"))?;
+            let target =
+                files::normalize(&std::env::current_dir()?.join(expand_tilde(target, &home_dir)));
+            let sessions = sessions::load_all_sessions(&home_dir)?;
+            let modifiers = files::who_touched(&sessions, &target);
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&modifiers)?);
+            } else {
+                print!(
+                    "{}",
+                    files::format_modifiers(&modifiers, &target, &time, Utc::now())
+                );
+            }
+        }
+        Some("commands") => {
+            let sessions = match cli.target.as_deref() {
+                Some(target) => vec![sessions::resolve_session(&home_dir, target, cli.expand)?.1],
+                None => sessions::load_all_sessions(&home_dir)?,
+            };
+            let commands = history::collect(&sessions);
+            match cli.history {
+                Some(shell) if cli.append => {
+                    let path = cli.output.unwrap_or_else(|| shell.default_file(&home_dir));
+                    let summary = history::append_history(&path, &commands, shell)?;
+                    println!(
+                        "Appended {} to {} ({} already there)",
+                        display::plural(summary.appended, "command"),
+                        path.display(),
+                        summary.skipped
+                    );
+                    if summary.unsupported > 0 {
+                        eprintln!("{}", history::unsupported_note(summary.unsupported, shell));
+                    }
+                }
+                _ if cli.json => println!("{}", serde_json::to_string_pretty(&commands)?),
+                Some(shell) => {
+                    print!("{}", history::format_history(&commands, shell));
+                    let unsupported = commands.iter().filter(|c| !shell.can_store(c)).count();
+                    if unsupported > 0 {
+                        eprintln!("{}", history::unsupported_note(unsupported, shell));
+                    }
+                }
+                None => print!("{}", history::format_plain(&commands)),
+            }
+        }
+        Some("diff") => {
+            let (Some(a), Some(b)) = (cli.target.as_deref(), cli.other.as_deref()) else {
+                anyhow::bail!("Usage: ccsesh diff <N|id>[@FROM[-TO]] <N|id>[@FROM[-TO]]");
+            };
+            let (a, b) = (diff::Point::parse(a)?, diff::Point::parse(b)?);
+            let (side_a, turns_a) = diff::load_side(&home_dir, &a, cli.expand)?;
+            let (side_b, turns_b) = diff::load_side(&home_dir, &b, cli.expand)?;
+            let diff = diff::SessionDiff {
+                turns: diff::diff_turns(&turns_a, &turns_b),
+                a: side_a,
+                b: side_b,
+            };
+            if cli.json {
+                println!("{}", diff::format_diff_json(&diff));
+            } else {
+                let width = display::terminal_width().unwrap_or(diff::DEFAULT_WIDTH);
+                print!("{}", diff::format_diff(&diff, width));
+            }
+        }
+        Some("dedupe") => {
+            let rows = load_sessions(&home_dir, usize::MAX)?;
+            let plan = dedupe::plan(&home_dir, &rows, cli.force);
+            if !cli.dry_run {
+                dedupe::archive(&plan)?;
+            }
+            print!(
+                "{}",
+                dedupe::format_report(&rows, &plan, cli.dry_run, &time, Utc::now())
+            );
+        }
+        Some("report") => {
+            let spec = cli.since.as_deref().unwrap_or(report::DEFAULT_SINCE);
+            let now = Utc::now();
+            let start = now
+                .checked_sub_signed(timefmt::parse_age(spec)?)
+                .ok_or_else(|| CcseshError::InvalidTimeSetting {
+                    reason: format!("age '{}' reaches before the earliest supported date", spec),
+                })?;
+            let sessions = sessions::load_all_sessions(&home_dir)?;
+            let report = report::build_report(&sessions, time.zone, start, now);
+            if cli.json {
+                println!("{}", report::format_report_json(&report));
+            } else {
+                match cli.format {
+                    OutputFormat::Default => {
+                        print!("{}", report::format_report(&report, time.zone))
+                    }
+                    OutputFormat::Md => {
+                        print!("{}", report::format_report_markdown(&report, time.zone))
+                    }
+                    _ => anyhow::bail!("ccsesh report supports --format default or md, or --json"),
+                }
+            }
+        }
         Some(s) => {
             let index: usize = s.parse().map_err(|_| {
                 anyhow::anyhow!(
-                    "Unknown command '{}'. Usage: ccsesh [<index>|init <shell>]",
+                    "Unknown command '{}'. Usage: ccsesh [<index>|init <shell>|show <N|id>|export <N|id>|watch|serve|mcp|scan-secrets [N|id]|bundle <N|id>|import <file>|relocate --from OLD --to NEW|timeline|report|who-touched <path>|commands [N|id]|diff <A> <B>|dedupe]",
                     s
                 )
             })?;
 
-            let sessions = load_sessions(&home_dir, cli.limit)?;
+            let sessions = listing(&home_dir, cli.limit, cli.expand)?;
 
             if index >= sessions.len() {
                 let max = sessions.len() - 1;
@@ -127,10 +608,75 @@ fn run() -> Result<()> {
 
             let session = &sessions[index];
 
-            if cli.shell_mode.is_some() {
-                shell::print_exec_protocol(session)?;
+            if session.active && !cli.force {
+                match (session.active_match, session.active_pid) {
+                    (Some(ActiveMatch::SessionId), Some(pid)) => {
+                        if Multiplexer::Tmux.is_running() {
+                            let ancestors = active::ancestor_pids(Path::new("/proc"), pid);
+                            if MuxLauncher::new(Multiplexer::Tmux).focus_process(pid, &ancestors)? {
+                                println!(
+                                    "Session {} is already open; switched to its tmux pane",
+                                    index
+                                );
+                                return Ok(());
+                            }
+                        }
+                        return Err(CcseshError::SessionAlreadyActive {
+                            index,
+                            detail: format!("claude pid {}", pid),
+                        }
+                        .into());
+                    }
+                    // Matched by working directory or mtime only: too uncertain
+                    // to refuse, so just warn.
+                    (_, Some(pid)) => eprintln!(
+                        "warning: a claude process (pid {}) is running in this session's project and may have session {} open",
+                        pid, index
+                    ),
+                    (_, None) => eprintln!(
+                        "warning: session {} was modified in the last {} seconds and may still be open",
+                        index,
+                        active::ACTIVE_WINDOW_SECS
+                    ),
+                }
+            }
+
+            let mux = if cli.tmux {
+                Some(Multiplexer::Tmux)
+            } else if cli.zellij {
+                Some(Multiplexer::Zellij)
+            } else if cli.screen {
+                Some(Multiplexer::Screen)
+            } else {
+                None
+            };
+
+            if let Some(kind) = mux {
+                if !kind.is_running() {
+                    return Err(CcseshError::MultiplexerNotRunning {
+                        name: kind.name().to_string(),
+                    }
+                    .into());
+                }
+                let launcher = MuxLauncher::new(kind);
+                let name = multiplexer::window_name(session);
+                match multiplexer::open_session(
+                    &launcher,
+                    session,
+                    &resume_template,
+                    &cli.claude_args,
+                )? {
+                    MuxOutcome::Opened => {
+                        println!("Opened session in {} window '{}'", kind.name(), name)
+                    }
+                    MuxOutcome::Reused => {
+                        println!("Switched to existing {} window '{}'", kind.name(), name)
+                    }
+                }
+            } else if cli.shell_mode.is_some() {
+                shell::print_exec_protocol(session, &resume_template, &cli.claude_args)?;
             } else {
-                shell::print_resume_instructions(session);
+                shell::print_resume_instructions(session, &resume_template, &cli.claude_args);
             }
         }
     }
@@ -138,6 +684,61 @@ fn run() -> Result<()> {
     Ok(())
 }
 
+/// Hyperlink settings for the text listings: `--hyperlinks` over the config
+/// file, off by default.
+fn hyperlinks(cli: &Cli, config: &config::Config) -> Result<Option<Hyperlinks>> {
+    let session_url = config
+        .session_url
+        .as_deref()
+        .map(SessionUrlTemplate::parse)
+        .transpose()?;
+    let mode = cli.hyperlinks.or(config.hyperlinks).unwrap_or_default();
+    Ok(Hyperlinks::resolve(mode, session_url))
+}
+
+/// Time display for the text listings: `--time` / `--tz` over the config
+/// file. Runs before any thread is spawned, since a named `--tz` sets `$TZ`.
+fn time_display(cli: &Cli, config: &config::Config) -> Result<TimeDisplay> {
+    let format = config
+        .time_format
+        .clone()
+        .unwrap_or_else(|| timefmt::DEFAULT_TIME_FORMAT.to_string());
+    timefmt::validate_format(&format)?;
+    let zone = match cli.tz.as_deref().or(config.timezone.as_deref()) {
+        Some(spec) => Zone::parse(spec)?,
+        None => Zone::Local,
+    };
+    let absolute_after = config
+        .absolute_after
+        .as_deref()
+        .map(timefmt::parse_age)
+        .transpose()?;
+    Ok(TimeDisplay {
+        mode: cli.time.or(config.time).unwrap_or_default(),
+        format,
+        zone,
+        absolute_after,
+    })
+}
+
+/// The session listing that indices refer to, with or without chains expanded.
+fn listing(home_dir: &str, limit: usize, expand: bool) -> Result<Vec<SessionInfo>> {
+    if expand {
+        sessions::load_expanded_sessions(home_dir, limit)
+    } else {
+        load_sessions(home_dir, limit)
+    }
+}
+
+/// `~` or `~/...` in a path given with `--from=~/x`, where the shell does
+/// not expand it.
+fn expand_tilde(path: &str, home_dir: &str) -> String {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", home_dir, rest),
+        _ => path.to_string(),
+    }
+}
+
 fn main() {
     if let Err(err) = run() {
         eprintln!("{}", err);
@@ -0,0 +1,602 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::display::{self, JsonSearchHit, JsonSession};
+use crate::errors::CcseshError;
+use crate::redact::{Identity, Redactor};
+use crate::search;
+use crate::sessions;
+use crate::shell::ResumeTemplate;
+use crate::transcript::{self, TranscriptMessage};
+use crate::types::SessionInfo;
+
+/// Protocol revisions this server can speak, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Hard cap on the text returned by any single tool call.
+const MAX_OUTPUT_CHARS: usize = 40_000;
+/// Default `max_chars` for `get_session_transcript`.
+const DEFAULT_TRANSCRIPT_CHARS: usize = 20_000;
+/// Longest a single transcript message may be before it is cut.
+const MAX_MESSAGE_CHARS: usize = 4_000;
+/// Whitespace-free runs longer than this (base64 blobs, minified code) are elided.
+const MAX_TOKEN_CHARS: usize = 200;
+
+const DEFAULT_LIST_LIMIT: usize = 10;
+const MAX_LIST_LIMIT: usize = 100;
+const SEARCH_SNIPPETS: usize = 3;
+
+// JSON-RPC 2.0 error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Model Context Protocol server exposing sessions as tools over stdio.
+pub struct McpServer {
+    pub home_dir: String,
+    pub template: ResumeTemplate,
+    pub args: Vec<String>,
+    /// Scrub secrets and `redact_patterns` matches from tool output.
+    pub redact: bool,
+    pub redact_patterns: Vec<String>,
+}
+
+/// Outcome of a JSON-RPC method: a result, or an error code and message.
+type RpcResult = std::result::Result<Value, (i64, String)>;
+
+impl McpServer {
+    /// Serve newline-delimited JSON-RPC messages from `input` until EOF.
+    pub fn run(&self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line) {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one JSON-RPC message. Returns the serialized response, or `None`
+    /// for notifications.
+    pub fn handle_message(&self, line: &str) -> Option<String> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // Responses from the client (we never send requests) are ignored.
+            let is_response = message.get("result").is_some() || message.get("error").is_some();
+            return match id {
+                Some(id) if !is_response => {
+                    Some(error_response(id, INVALID_REQUEST, "Missing method"))
+                }
+                _ => None,
+            };
+        };
+        let params = message.get("params").cloned().unwrap_or(json!({}));
+
+        let result = match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.call_tool(&params),
+            _ if method.starts_with("notifications/") => return None,
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        };
+
+        // Notifications get no response, even on error.
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn call_tool(&self, params: &Value) -> RpcResult {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+        let outcome = match name {
+            "list_sessions" => self.list_sessions(&arguments),
+            "search_sessions" => self.search_sessions(&arguments),
+            "get_session_transcript" => self.get_session_transcript(&arguments),
+            _ => return Err((INVALID_PARAMS, format!("Unknown tool '{}'", name))),
+        };
+
+        // Tool failures are reported in-band so the model can see and react to them.
+        Ok(match outcome {
+            Ok(text) => json!({
+                "content": [{ "type": "text", "text": cap_output(text) }],
+                "isError": false,
+            }),
+            Err(e) => json!({
+                "content": [{ "type": "text", "text": e.to_string() }],
+                "isError": true,
+            }),
+        })
+    }
+
+    /// A fresh `Redactor` for one tool call, unless redaction is off. The
+    /// caller runs as the local user, so names and host names are kept.
+    fn redactor(&self) -> Result<Option<Redactor>> {
+        if !self.redact {
+            return Ok(None);
+        }
+        let redactor = Redactor::new(&self.home_dir, &Identity::default(), &self.redact_patterns)?;
+        Ok(Some(redactor))
+    }
+
+    /// Pretty JSON of `value`, redacted.
+    fn to_json(&self, value: impl Serialize) -> Result<String> {
+        let mut value = serde_json::to_value(value)?;
+        if let Some(mut redactor) = self.redactor()? {
+            redactor.redact_value(&mut value);
+        }
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+
+    fn json_session(&self, index: usize, session: &SessionInfo) -> JsonSession {
+        display::json_session(index, session, Utc::now(), &self.template, &self.args)
+    }
+
+    fn load_all(&self) -> Result<Vec<SessionInfo>> {
+        match sessions::load_sessions(&self.home_dir, usize::MAX) {
+            Err(e) if matches!(e.downcast_ref(), Some(CcseshError::NoSessionsFound)) => Ok(vec![]),
+            other => other,
+        }
+    }
+
+    fn list_sessions(&self, arguments: &Value) -> Result<String> {
+        let limit = usize_arg(arguments, "limit")?
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .min(MAX_LIST_LIMIT);
+        let sessions = match sessions::load_sessions(&self.home_dir, limit) {
+            Err(e) if matches!(e.downcast_ref(), Some(CcseshError::NoSessionsFound)) => vec![],
+            other => other?,
+        };
+        let json: Vec<JsonSession> = sessions
+            .iter()
+            .enumerate()
+            .map(|(i, s)| self.json_session(i, s))
+            .collect();
+        self.to_json(json)
+    }
+
+    fn search_sessions(&self, arguments: &Value) -> Result<String> {
+        let query = str_arg(arguments, "query")?;
+        let limit = usize_arg(arguments, "limit")?
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .min(MAX_LIST_LIMIT);
+        let now = Utc::now();
+        let hits: Vec<JsonSearchHit> =
+            search::search_sessions(&self.load_all()?, query, SEARCH_SNIPPETS)
+                .iter()
+                .take(limit)
+                .map(|hit| display::json_search_hit(hit, now, &self.template, &self.args))
+                .collect();
+        self.to_json(hits)
+    }
+
+    fn get_session_transcript(&self, arguments: &Value) -> Result<String> {
+        let session_id = str_arg(arguments, "session_id")?;
+        let offset = usize_arg(arguments, "offset")?.unwrap_or(0);
+        let max_chars = usize_arg(arguments, "max_chars")?
+            .unwrap_or(DEFAULT_TRANSCRIPT_CHARS)
+            .min(MAX_OUTPUT_CHARS);
+
+        let (_, session) = sessions::find_session(&self.home_dir, session_id)?;
+        let mut messages = transcript::read_transcript(&session.path)?;
+        if let Some(mut redactor) = self.redactor()? {
+            for message in &mut messages {
+                message.text = redactor.redact_text(&message.text);
+            }
+        }
+        Ok(render_transcript_page(
+            &session.session_id,
+            &session.project_dir_display,
+            &messages,
+            offset,
+            max_chars,
+        ))
+    }
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "ccsesh", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Search and read past Claude Code sessions on this machine. \
+            Use search_sessions to find relevant sessions, then get_session_transcript to read one.",
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "list_sessions",
+            "description": "List the most recent Claude Code sessions, newest first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "integer", "minimum": 0, "maximum": MAX_LIST_LIMIT, "description": "Number of sessions (default 10)" }
+                }
+            }
+        },
+        {
+            "name": "search_sessions",
+            "description": "Find past sessions whose conversation mentions every word of the query (case-insensitive). Returns matching sessions with short snippets.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Words to search for" },
+                    "limit": { "type": "integer", "minimum": 0, "maximum": MAX_LIST_LIMIT, "description": "Maximum sessions to return (default 10)" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "get_session_transcript",
+            "description": "Read the user/assistant conversation of one session as Markdown. Tool calls, tool output, and images are omitted and long messages are shortened. Large transcripts are paged: continue with the offset given at the end of the output.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session_id": { "type": "string", "description": "Session ID from list_sessions or search_sessions" },
+                    "offset": { "type": "integer", "minimum": 0, "description": "Index of the first message to return (default 0)" },
+                    "max_chars": { "type": "integer", "minimum": 1, "maximum": MAX_OUTPUT_CHARS, "description": "Approximate size limit for this page (default 20000)" }
+                },
+                "required": ["session_id"]
+            }
+        }
+    ])
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+    .to_string()
+}
+
+fn str_arg<'a>(arguments: &'a Value, name: &str) -> Result<&'a str> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("Missing string argument '{}'", name))
+}
+
+fn usize_arg(arguments: &Value, name: &str) -> Result<Option<usize>> {
+    match arguments.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => v
+            .as_u64()
+            .map(|n| Some(n as usize))
+            .ok_or_else(|| anyhow::anyhow!("Argument '{}' must be a non-negative integer", name)),
+    }
+}
+
+/// Render messages starting at `offset` until roughly `max_chars`, with a
+/// header and a footer telling the caller how to fetch the next page.
+fn render_transcript_page(
+    session_id: &str,
+    project: &str,
+    messages: &[TranscriptMessage],
+    offset: usize,
+    max_chars: usize,
+) -> String {
+    let total = messages.len();
+    let mut body = String::new();
+    let mut next = offset.min(total);
+
+    for message in messages.iter().skip(offset) {
+        let message = TranscriptMessage {
+            text: shrink_text(&message.text, MAX_MESSAGE_CHARS),
+            ..message.clone()
+        };
+        let section = transcript::render_markdown(std::slice::from_ref(&message));
+        // Always include at least one message so paging makes progress.
+        if next > offset && body.chars().count() + section.chars().count() > max_chars {
+            break;
+        }
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        body.push_str(&section);
+        next += 1;
+    }
+
+    let mut out = format!(
+        "Session {} ({})\nMessages {}-{} of {}\n\n",
+        session_id,
+        project,
+        offset.min(total),
+        next,
+        total
+    );
+    out.push_str(&body);
+    if next < total {
+        out.push_str(&format!(
+            "\n[Transcript continues: call get_session_transcript with offset={} for more.]\n",
+            next
+        ));
+    }
+    out
+}
+
+/// Elide whitespace-free runs longer than `MAX_TOKEN_CHARS`, then cut the
+/// text to `max_chars` characters.
+fn shrink_text(text: &str, max_chars: usize) -> String {
+    let mut out = String::with_capacity(text.len().min(max_chars * 4));
+    let mut token = String::new();
+    let flush = |token: &mut String, out: &mut String| {
+        let len = token.chars().count();
+        if len > MAX_TOKEN_CHARS {
+            out.push_str(&format!("[\u{2026} {} chars elided]", len));
+        } else {
+            out.push_str(token);
+        }
+        token.clear();
+    };
+    for c in text.chars() {
+        if c.is_whitespace() {
+            flush(&mut token, &mut out);
+            out.push(c);
+        } else {
+            token.push(c);
+        }
+    }
+    flush(&mut token, &mut out);
+
+    let len = out.chars().count();
+    if len <= max_chars {
+        return out;
+    }
+    let mut cut: String = out.chars().take(max_chars).collect();
+    cut.push_str(&format!(
+        "\n[\u{2026} {} more chars truncated]",
+        len - max_chars
+    ));
+    cut
+}
+
+/// Enforce `MAX_OUTPUT_CHARS` on any tool result.
+fn cap_output(text: String) -> String {
+    if text.chars().count() <= MAX_OUTPUT_CHARS {
+        return text;
+    }
+    let mut cut: String = text.chars().take(MAX_OUTPUT_CHARS).collect();
+    cut.push_str("\n[Output truncated]");
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::Role;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const UUID_NORMAL: &str = "eb53d999-8692-42ce-a376-4f82206a086d";
+
+    fn setup_server() -> (assert_fs::TempDir, McpServer) {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let dir = tmp.path().join(".claude/projects/-a");
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/normal.jsonl"),
+            dir.join(format!("{}.jsonl", UUID_NORMAL)),
+        )
+        .unwrap();
+        let server = McpServer {
+            home_dir: tmp.path().to_str().unwrap().to_string(),
+            template: ResumeTemplate::default(),
+            args: vec![],
+            redact: true,
+            redact_patterns: vec![],
+        };
+        (tmp, server)
+    }
+
+    fn request(server: &McpServer, method: &str, params: Value) -> Value {
+        let line = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        serde_json::from_str(&server.handle_message(&line.to_string()).unwrap()).unwrap()
+    }
+
+    fn tool_text(response: &Value) -> &str {
+        response["result"]["content"][0]["text"].as_str().unwrap()
+    }
+
+    fn message(text: &str) -> TranscriptMessage {
+        TranscriptMessage {
+            role: Role::User,
+            timestamp: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn initialize_negotiates_version() {
+        let (_tmp, server) = setup_server();
+        let known = request(
+            &server,
+            "initialize",
+            json!({ "protocolVersion": "2024-11-05" }),
+        );
+        assert_eq!(known["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(known["result"]["serverInfo"]["name"], "ccsesh");
+
+        let unknown = request(
+            &server,
+            "initialize",
+            json!({ "protocolVersion": "1999-01-01" }),
+        );
+        assert_eq!(unknown["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        let (_tmp, server) = setup_server();
+        let line = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server.handle_message(&line.to_string()).is_none());
+    }
+
+    #[test]
+    fn tools_list_names() {
+        let (_tmp, server) = setup_server();
+        let response = request(&server, "tools/list", json!({}));
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["list_sessions", "search_sessions", "get_session_transcript"]
+        );
+    }
+
+    #[test]
+    fn malformed_and_unknown_requests() {
+        let (_tmp, server) = setup_server();
+        let parse: Value = serde_json::from_str(&server.handle_message("{nope").unwrap()).unwrap();
+        assert_eq!(parse["error"]["code"], PARSE_ERROR);
+
+        let unknown = request(&server, "resources/list", json!({}));
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+
+        let bad_tool = request(&server, "tools/call", json!({ "name": "rm_rf" }));
+        assert_eq!(bad_tool["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn list_sessions_tool() {
+        let (_tmp, server) = setup_server();
+        let response = request(
+            &server,
+            "tools/call",
+            json!({ "name": "list_sessions", "arguments": { "limit": 5 } }),
+        );
+        let sessions: Value = serde_json::from_str(tool_text(&response)).unwrap();
+        assert_eq!(sessions[0]["session_id"], UUID_NORMAL);
+        assert_eq!(response["result"]["isError"], false);
+    }
+
+    #[test]
+    fn search_sessions_tool() {
+        let (_tmp, server) = setup_server();
+        let response = request(
+            &server,
+            "tools/call",
+            json!({ "name": "search_sessions", "arguments": { "query": "discovery module" } }),
+        );
+        let hits: Value = serde_json::from_str(tool_text(&response)).unwrap();
+        assert_eq!(hits[0]["session"]["session_id"], UUID_NORMAL);
+        assert!(!hits[0]["snippets"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn transcript_tool_and_errors_in_band() {
+        let (_tmp, server) = setup_server();
+        let response = request(
+            &server,
+            "tools/call",
+            json!({ "name": "get_session_transcript", "arguments": { "session_id": UUID_NORMAL } }),
+        );
+        let text = tool_text(&response);
+        assert!(text.contains("Messages 0-4 of 4"));
+        assert!(text.contains("Design technical approach for ccsesh"));
+
+        let missing = request(
+            &server,
+            "tools/call",
+            json!({ "name": "get_session_transcript", "arguments": { "session_id": "nope" } }),
+        );
+        assert_eq!(missing["result"]["isError"], true);
+        assert!(tool_text(&missing).contains("nope"));
+
+        let no_args = request(
+            &server,
+            "tools/call",
+            json!({ "name": "get_session_transcript", "arguments": {} }),
+        );
+        assert_eq!(no_args["result"]["isError"], true);
+    }
+
+    #[test]
+    fn transcript_pages_by_size() {
+        let messages: Vec<TranscriptMessage> = (0..10)
+            .map(|i| message(&format!("message {} {}", i, "x ".repeat(40))))
+            .collect();
+        let page = render_transcript_page("id", "~/p", &messages, 0, 250);
+        assert!(page.contains("Messages 0-2 of 10"));
+        assert!(page.contains("offset=2"));
+
+        let last = render_transcript_page("id", "~/p", &messages, 9, 250);
+        assert!(last.contains("Messages 9-10 of 10"));
+        assert!(!last.contains("offset="));
+    }
+
+    #[test]
+    fn transcript_page_always_makes_progress() {
+        let messages = vec![message(&"word ".repeat(1000))];
+        let page = render_transcript_page("id", "~/p", &messages, 0, 10);
+        assert!(page.contains("Messages 0-1 of 1"));
+    }
+
+    #[test]
+    fn shrink_text_elides_blobs_and_truncates() {
+        let blob = "A".repeat(500);
+        assert_eq!(
+            shrink_text(&format!("see {} here", blob), 1000),
+            "see [\u{2026} 500 chars elided] here"
+        );
+
+        let long = "word ".repeat(100);
+        let shrunk = shrink_text(&long, 20);
+        assert!(shrunk.starts_with("word word word word "));
+        assert!(shrunk.ends_with("480 more chars truncated]"));
+    }
+
+    #[test]
+    fn run_processes_lines() {
+        let (_tmp, server) = setup_server();
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#,
+            "\n"
+        );
+        let mut output = Vec::new();
+        server.run(input.as_bytes(), &mut output).unwrap();
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["id"], 2);
+    }
+}
@@ -0,0 +1,741 @@
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::CcseshError;
+use crate::shell::{ResumeTemplate, is_valid_uuid};
+use crate::types::SessionInfo;
+
+/// Maximum length of a generated window/tab name.
+const MAX_WINDOW_NAME: usize = 32;
+
+/// Terminal multiplexers that can host a resumed session in a new window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Zellij,
+    Screen,
+}
+
+/// What `MuxLauncher::open` did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MuxOutcome {
+    /// A new window was created and the resume command started in it.
+    Opened,
+    /// A window with the same name already existed and was selected instead.
+    Reused,
+}
+
+impl Multiplexer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Zellij => "zellij",
+            Multiplexer::Screen => "screen",
+        }
+    }
+
+    /// Environment variable set by the multiplexer inside its panes.
+    fn env_var(self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "TMUX",
+            Multiplexer::Zellij => "ZELLIJ",
+            Multiplexer::Screen => "STY",
+        }
+    }
+
+    /// Whether the current process is running inside this multiplexer.
+    pub fn is_running(self) -> bool {
+        std::env::var_os(self.env_var()).is_some_and(|v| !v.is_empty())
+    }
+
+    /// Arguments that list existing window (or tab) names.
+    fn list_args(self) -> Vec<String> {
+        match self {
+            Multiplexer::Tmux => args(&["list-windows", "-F", "#{window_name}"]),
+            Multiplexer::Zellij => args(&["action", "query-tab-names"]),
+            Multiplexer::Screen => args(&["-Q", "windows"]),
+        }
+    }
+
+    /// Extract window names from the output of `list_args`.
+    fn parse_window_names(self, output: &str) -> Vec<String> {
+        match self {
+            Multiplexer::Tmux | Multiplexer::Zellij => output
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(str::to_string)
+                .collect(),
+            // `screen -Q windows` prints "0$ bash  1*$ my-window" on one line:
+            // each title is preceded by a window number with optional flag chars.
+            Multiplexer::Screen => {
+                let mut names = Vec::new();
+                let mut current: Vec<&str> = Vec::new();
+                for token in output.split_whitespace() {
+                    let is_marker = token.starts_with(|c: char| c.is_ascii_digit())
+                        && token
+                            .chars()
+                            .all(|c| c.is_ascii_digit() || "-*$!@&Z".contains(c));
+                    if is_marker {
+                        if !current.is_empty() {
+                            names.push(current.join(" "));
+                            current.clear();
+                        }
+                    } else {
+                        current.push(token);
+                    }
+                }
+                if !current.is_empty() {
+                    names.push(current.join(" "));
+                }
+                names
+            }
+        }
+    }
+
+    /// Invocations that focus an existing window named `name`.
+    fn select_invocations(self, name: &str) -> Vec<Vec<String>> {
+        match self {
+            Multiplexer::Tmux => vec![args(&["select-window", "-t", &format!("={}", name)])],
+            Multiplexer::Zellij => vec![args(&["action", "go-to-tab-name", name])],
+            Multiplexer::Screen => vec![args(&["-X", "select", name])],
+        }
+    }
+
+    /// Invocations that create a window named `name` in `dir` running `command`.
+    /// Zellij takes the command from `layout`, a file holding `zellij_layout()`,
+    /// so the new tab has no idle shell pane beside it.
+    fn open_invocations(
+        self,
+        name: &str,
+        dir: &str,
+        command: &[String],
+        layout: &Path,
+    ) -> Vec<Vec<String>> {
+        match self {
+            Multiplexer::Tmux => {
+                let mut open = args(&["new-window", "-n", name, "-c", dir, "--"]);
+                open.extend(command.iter().cloned());
+                vec![open]
+            }
+            Multiplexer::Zellij => vec![args(&[
+                "action",
+                "new-tab",
+                "--name",
+                name,
+                "--layout",
+                &layout.to_string_lossy(),
+            ])],
+            // `chdir` would change the directory of every later window too, so
+            // the window changes directory itself. "$1" is `dir`, passed as an
+            // argument rather than spliced into the script.
+            Multiplexer::Screen => {
+                let mut open = args(&[
+                    "-X",
+                    "screen",
+                    "-t",
+                    name,
+                    "sh",
+                    "-c",
+                    "cd \"$1\" && shift && exec \"$@\"",
+                    "sh",
+                    dir,
+                ]);
+                open.extend(command.iter().cloned());
+                vec![open]
+            }
+        }
+    }
+}
+
+/// A zellij layout for one tab running `command` in `dir`, with the tab and
+/// status bars of the default layout.
+fn zellij_layout(dir: &str, command: &[String]) -> String {
+    let (program, rest) = command
+        .split_first()
+        .map_or(("", &[][..]), |(p, r)| (p.as_str(), r));
+    let mut pane = format!(
+        "    pane command={} cwd={} close_on_exit=true focus=true",
+        kdl_string(program),
+        kdl_string(dir)
+    );
+    if !rest.is_empty() {
+        let args: Vec<String> = rest.iter().map(|a| kdl_string(a)).collect();
+        pane.push_str(&format!(" {{\n        args {}\n    }}", args.join(" ")));
+    }
+    format!(
+        concat!(
+            "layout {{\n",
+            "    pane size=1 borderless=true {{\n",
+            "        plugin location=\"zellij:tab-bar\"\n",
+            "    }}\n",
+            "{}\n",
+            "    pane size=2 borderless=true {{\n",
+            "        plugin location=\"zellij:status-bar\"\n",
+            "    }}\n",
+            "}}\n",
+        ),
+        pane
+    )
+}
+
+/// Write `contents` to `layout.kdl` in a new directory under the temp dir,
+/// readable only by this user on Unix. Both are created fresh, so nothing
+/// another user planted in a shared `/tmp` is written through. The caller
+/// removes the directory once zellij has read the layout.
+fn write_layout(contents: &str) -> std::io::Result<PathBuf> {
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let mut attempt = 0;
+    let dir = loop {
+        let dir = std::env::temp_dir().join(format!(
+            "ccsesh-{}-{}-{}",
+            std::process::id(),
+            nanos,
+            attempt
+        ));
+        match builder.create(&dir) {
+            Ok(()) => break dir,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    };
+    let path = dir.join("layout.kdl");
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()));
+    if let Err(e) = written {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// `s` as a quoted KDL string.
+fn kdl_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn args(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+/// Window name for a session: its slug, or `claude-<id prefix>` when it has none.
+/// Characters that multiplexers treat specially in targets are replaced with `-`.
+pub fn window_name(session: &SessionInfo) -> String {
+    let raw = match &session.slug {
+        Some(slug) if !slug.is_empty() => slug.clone(),
+        _ => format!(
+            "claude-{}",
+            session.session_id.chars().take(8).collect::<String>()
+        ),
+    };
+    raw.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(MAX_WINDOW_NAME)
+        .collect()
+}
+
+/// Runs multiplexer commands as argv (never through a shell).
+pub struct MuxLauncher {
+    kind: Multiplexer,
+    program: PathBuf,
+}
+
+impl MuxLauncher {
+    /// Launcher using the multiplexer binary found on `$PATH`.
+    pub fn new(kind: Multiplexer) -> Self {
+        Self::with_program(kind, kind.name())
+    }
+
+    /// Launcher using an explicit binary (used by tests with a fake multiplexer).
+    pub fn with_program(kind: Multiplexer, program: impl AsRef<Path>) -> Self {
+        Self {
+            kind,
+            program: program.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Focus the window named `name` if it exists, otherwise create it in `dir`
+    /// and start `command` there.
+    pub fn open(
+        &self,
+        name: &str,
+        dir: &Path,
+        command: &[String],
+    ) -> Result<MuxOutcome, CcseshError> {
+        let listing = self.run(&self.kind.list_args(), true)?;
+        let existing = self.kind.parse_window_names(&listing);
+
+        if existing.iter().any(|n| n == name) {
+            for invocation in self.kind.select_invocations(name) {
+                self.run(&invocation, false)?;
+            }
+            return Ok(MuxOutcome::Reused);
+        }
+
+        let dir = dir.to_string_lossy();
+        let layout = match self.kind {
+            Multiplexer::Zellij => write_layout(&zellij_layout(&dir, command)).map_err(|e| {
+                CcseshError::MultiplexerFailed {
+                    name: self.kind.name().to_string(),
+                    detail: format!("could not write the tab layout: {}", e),
+                }
+            })?,
+            _ => PathBuf::new(),
+        };
+        let result = self
+            .kind
+            .open_invocations(name, &dir, command, &layout)
+            .iter()
+            .try_for_each(|invocation| self.run(invocation, false).map(drop));
+        if let Some(layout_dir) = layout.parent().filter(|p| !p.as_os_str().is_empty()) {
+            let _ = fs::remove_dir_all(layout_dir);
+        }
+        result.map(|()| MuxOutcome::Opened)
+    }
+
+    /// Focus the tmux pane running `pid` (or one of its `ancestors`, since the
+    /// pane's own process is usually the shell that launched it). Returns
+    /// `Ok(false)` when no pane matches or the multiplexer is not tmux.
+    pub fn focus_process(&self, pid: u32, ancestors: &[u32]) -> Result<bool, CcseshError> {
+        if self.kind != Multiplexer::Tmux {
+            return Ok(false);
+        }
+
+        let listing = self.run(
+            &args(&["list-panes", "-a", "-F", "#{pane_pid} #{pane_id}"]),
+            true,
+        )?;
+        let pane = listing.lines().find_map(|line| {
+            let (pane_pid, pane_id) = line.trim().split_once(' ')?;
+            let pane_pid: u32 = pane_pid.parse().ok()?;
+            (pane_pid == pid || ancestors.contains(&pane_pid)).then(|| pane_id.to_string())
+        });
+
+        let Some(pane_id) = pane else {
+            return Ok(false);
+        };
+        for command in ["switch-client", "select-window", "select-pane"] {
+            self.run(&args(&[command, "-t", &pane_id]), false)?;
+        }
+        Ok(true)
+    }
+
+    fn run(&self, args: &[String], capture: bool) -> Result<String, CcseshError> {
+        let failed = |detail: String| CcseshError::MultiplexerFailed {
+            name: self.kind.name().to_string(),
+            detail,
+        };
+
+        let output = Command::new(&self.program)
+            .args(args)
+            .output()
+            .map_err(|e| failed(format!("could not run {}: {}", self.program.display(), e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(failed(format!(
+                "`{} {}` exited with {}: {}",
+                self.kind.name(),
+                args.join(" "),
+                output.status,
+                stderr.trim()
+            )));
+        }
+
+        Ok(if capture {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        } else {
+            String::new()
+        })
+    }
+}
+
+/// Open `session` in a multiplexer window named after it, starting the rendered
+/// resume command in the session's project directory.
+pub fn open_session(
+    launcher: &MuxLauncher,
+    session: &SessionInfo,
+    template: &ResumeTemplate,
+    args: &[String],
+) -> anyhow::Result<MuxOutcome> {
+    if !is_valid_uuid(&session.session_id) {
+        anyhow::bail!("Invalid session ID: {}", session.session_id);
+    }
+    let command = template.argv(session, args);
+    Ok(launcher.open(&window_name(session), &session.project_dir, &command)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_session(slug: Option<&str>) -> SessionInfo {
+        SessionInfo {
+            path: PathBuf::from("/tmp/test.jsonl"),
+            project_dir_display: "~/project".to_string(),
+            first_prompt: Some("test prompt".to_string()),
+            slug: slug.map(|s| s.to_string()),
+            ..SessionInfo::fixture("eb53d999-8692-42ce-a376-4f82206a086d", "/home/user/project")
+        }
+    }
+
+    #[test]
+    fn window_name_uses_slug() {
+        let session = make_session(Some("woolly-conjuring-journal"));
+        assert_eq!(window_name(&session), "woolly-conjuring-journal");
+    }
+
+    #[test]
+    fn window_name_falls_back_to_id_prefix() {
+        let session = make_session(None);
+        assert_eq!(window_name(&session), "claude-eb53d999");
+    }
+
+    #[test]
+    fn window_name_sanitizes_target_characters() {
+        let session = make_session(Some("a:b.c d;e"));
+        assert_eq!(window_name(&session), "a-b-c-d-e");
+    }
+
+    #[test]
+    fn parse_tmux_window_names() {
+        let names = Multiplexer::Tmux.parse_window_names("zsh\nwoolly-journal\n\n");
+        assert_eq!(names, vec!["zsh", "woolly-journal"]);
+    }
+
+    #[test]
+    fn parse_screen_window_names() {
+        let names = Multiplexer::Screen.parse_window_names("0$ bash  1*$ woolly-journal  2-$ vim");
+        assert_eq!(names, vec!["bash", "woolly-journal", "vim"]);
+    }
+
+    #[test]
+    fn tmux_open_invocation_is_argv() {
+        let command = args(&["claude", "--resume", "abc", "--model", "it's opus"]);
+        let invocations = Multiplexer::Tmux.open_invocations(
+            "my-slug",
+            "/tmp/my project",
+            &command,
+            Path::new("/unused"),
+        );
+        assert_eq!(
+            invocations,
+            vec![args(&[
+                "new-window",
+                "-n",
+                "my-slug",
+                "-c",
+                "/tmp/my project",
+                "--",
+                "claude",
+                "--resume",
+                "abc",
+                "--model",
+                "it's opus",
+            ])]
+        );
+    }
+
+    #[test]
+    fn zellij_open_creates_tab_from_layout() {
+        let command = args(&["claude", "--resume", "abc"]);
+        let invocations = Multiplexer::Zellij.open_invocations(
+            "my-slug",
+            "/tmp/p",
+            &command,
+            Path::new("/tmp/l.kdl"),
+        );
+        assert_eq!(
+            invocations,
+            vec![args(&[
+                "action",
+                "new-tab",
+                "--name",
+                "my-slug",
+                "--layout",
+                "/tmp/l.kdl"
+            ])]
+        );
+    }
+
+    #[test]
+    fn zellij_layout_runs_command_in_its_only_pane() {
+        let command = args(&["claude", "--resume", "abc", "--model", "say \"hi\"\\"]);
+        assert_eq!(
+            zellij_layout("/tmp/my project", &command),
+            "layout {
+    pane size=1 borderless=true {
+        plugin location=\"zellij:tab-bar\"
+    }
+    pane command=\"claude\" cwd=\"/tmp/my project\" close_on_exit=true focus=true {
+        args \"--resume\" \"abc\" \"--model\" \"say \\\"hi\\\"\\\\\"
+    }
+    pane size=2 borderless=true {
+        plugin location=\"zellij:status-bar\"
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn screen_open_changes_dir_inside_the_window() {
+        let command = args(&["claude", "--resume", "abc"]);
+        let invocations = Multiplexer::Screen.open_invocations(
+            "my-slug",
+            "/tmp/it's here",
+            &command,
+            Path::new("/unused"),
+        );
+        assert_eq!(
+            invocations,
+            vec![args(&[
+                "-X",
+                "screen",
+                "-t",
+                "my-slug",
+                "sh",
+                "-c",
+                "cd \"$1\" && shift && exec \"$@\"",
+                "sh",
+                "/tmp/it's here",
+                "claude",
+                "--resume",
+                "abc"
+            ])]
+        );
+    }
+
+    /// Writes a fake multiplexer script that logs its argv (one invocation per
+    /// line) and prints `windows` when asked to list windows.
+    #[cfg(unix)]
+    fn fake_multiplexer(dir: &Path, windows: &str) -> (PathBuf, PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let log = dir.join("calls.log");
+        let script = dir.join("fake-mux");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$*\" >> '{}'\ncase \"$1\" in list-windows|-Q|action) printf '{}' ;; esac\n",
+                log.display(),
+                windows
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        (script, log)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fake_tmux_opens_new_window() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let (script, log) = fake_multiplexer(tmp.path(), "zsh\\n");
+        let launcher = MuxLauncher::with_program(Multiplexer::Tmux, &script);
+
+        let outcome = launcher
+            .open(
+                "woolly-journal",
+                Path::new("/tmp/project"),
+                &args(&["claude", "--resume", "abc"]),
+            )
+            .unwrap();
+
+        assert_eq!(outcome, MuxOutcome::Opened);
+        let calls = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = calls.lines().collect();
+        assert_eq!(lines[0], "list-windows -F #{window_name}");
+        assert_eq!(
+            lines[1],
+            "new-window -n woolly-journal -c /tmp/project -- claude --resume abc"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fake_tmux_reuses_existing_window() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let (script, log) = fake_multiplexer(tmp.path(), "zsh\\nwoolly-journal\\n");
+        let launcher = MuxLauncher::with_program(Multiplexer::Tmux, &script);
+
+        let outcome = launcher
+            .open(
+                "woolly-journal",
+                Path::new("/tmp/project"),
+                &args(&["claude", "--resume", "abc"]),
+            )
+            .unwrap();
+
+        assert_eq!(outcome, MuxOutcome::Reused);
+        let calls = std::fs::read_to_string(&log).unwrap();
+        assert!(calls.contains("select-window -t =woolly-journal"));
+        assert!(!calls.contains("new-window"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fake_zellij_opens_tab_from_layout() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let (script, log) = fake_multiplexer(tmp.path(), "Tab #1\\n");
+        // Keep a copy of the layout, which is removed once the tab is open.
+        let copy = tmp.path().join("layout.kdl");
+        let body = std::fs::read_to_string(&script).unwrap();
+        std::fs::write(
+            &script,
+            format!(
+                "{}if [ \"$2\" = new-tab ]; then cp \"$6\" '{}'; fi\n",
+                body,
+                copy.display()
+            ),
+        )
+        .unwrap();
+        let launcher = MuxLauncher::with_program(Multiplexer::Zellij, &script);
+
+        let outcome = launcher
+            .open(
+                "woolly-journal",
+                Path::new("/tmp/project"),
+                &args(&["claude", "--resume", "abc"]),
+            )
+            .unwrap();
+
+        assert_eq!(outcome, MuxOutcome::Opened);
+        let calls = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = calls.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "action query-tab-names");
+        let layout = lines[1]
+            .strip_prefix("action new-tab --name woolly-journal --layout ")
+            .unwrap();
+        assert!(!Path::new(layout).parent().unwrap().exists());
+        assert_eq!(
+            std::fs::read_to_string(&copy).unwrap(),
+            zellij_layout("/tmp/project", &args(&["claude", "--resume", "abc"]))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn layout_is_written_into_a_private_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = write_layout("layout {}\n").unwrap();
+        let dir = path.parent().unwrap();
+        let mode = fs::metadata(dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "layout {}\n");
+
+        let other = write_layout("layout {}\n").unwrap();
+        assert_ne!(other.parent(), path.parent());
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(other.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fake_screen_opens_window_without_chdir() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let (script, log) = fake_multiplexer(tmp.path(), "0$ bash");
+        let launcher = MuxLauncher::with_program(Multiplexer::Screen, &script);
+
+        launcher
+            .open(
+                "woolly-journal",
+                Path::new("/tmp/project"),
+                &args(&["claude", "--resume", "abc"]),
+            )
+            .unwrap();
+
+        let calls = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = calls.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "-Q windows",
+                "-X screen -t woolly-journal sh -c cd \"$1\" && shift && exec \"$@\" sh /tmp/project claude --resume abc",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fake_tmux_focuses_pane_of_ancestor() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let (script, log) = fake_multiplexer(tmp.path(), "");
+        // The fake prints nothing for list-panes, so rewrite it to report panes.
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$*\" >> '{}'\ncase \"$1\" in list-panes) printf '10 %%1\\n20 %%2\\n' ;; esac\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+        let launcher = MuxLauncher::with_program(Multiplexer::Tmux, &script);
+
+        assert!(launcher.focus_process(999, &[500, 20, 1]).unwrap());
+        let calls = std::fs::read_to_string(&log).unwrap();
+        assert!(calls.contains("switch-client -t %2"));
+        assert!(calls.contains("select-pane -t %2"));
+
+        assert!(!launcher.focus_process(999, &[500]).unwrap());
+    }
+
+    #[test]
+    fn focus_process_unsupported_for_screen() {
+        let launcher = MuxLauncher::with_program(Multiplexer::Screen, "/nonexistent/screen");
+        assert!(!launcher.focus_process(1, &[]).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_multiplexer_is_error() {
+        let launcher = MuxLauncher::with_program(Multiplexer::Tmux, "/bin/false");
+        let err = launcher
+            .open("x", Path::new("/tmp"), &args(&["claude"]))
+            .unwrap_err();
+        assert!(err.to_string().contains("tmux"));
+    }
+
+    #[test]
+    fn missing_binary_is_error() {
+        let launcher =
+            MuxLauncher::with_program(Multiplexer::Zellij, "/nonexistent/ccsesh-fake-zellij");
+        assert!(
+            launcher
+                .open("x", Path::new("/tmp"), &args(&["claude"]))
+                .is_err()
+        );
+    }
+}
@@ -1,10 +1,11 @@
+use std::collections::HashSet;
 use std::io::BufRead;
 use std::path::PathBuf;
 
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 
-use crate::types::{JsonlLine, SessionCandidate, SessionInfo};
+use crate::types::{ContinuationRefs, JsonlLine, SessionCandidate, SessionInfo};
 
 const MAX_LINES: usize = 50;
 
@@ -18,6 +19,8 @@ pub fn parse_session(candidate: &SessionCandidate, home_dir: &str) -> Result<Ses
     let mut cwd: Option<String> = None;
     let mut slug: Option<String> = None;
     let mut first_prompt: Option<String> = None;
+    let mut continuation = ContinuationRefs::default();
+    let mut seen_uuids: HashSet<String> = HashSet::new();
 
     for line_result in reader.lines().take(MAX_LINES) {
         let line_str = match line_result {
@@ -54,11 +57,19 @@ pub fn parse_session(candidate: &SessionCandidate, home_dir: &str) -> Result<Ses
             first_prompt = Some(prompt);
         }
 
+        collect_continuation(&parsed, &session_id, &seen_uuids, &mut continuation);
+        if let Some(uuid) = parsed.uuid {
+            seen_uuids.insert(uuid);
+        }
+
         if cwd.is_some() && slug.is_some() && first_prompt.is_some() {
             break;
         }
     }
 
+    // A summary may title a leaf further down this same file.
+    continuation.uuids.retain(|uuid| !seen_uuids.contains(uuid));
+
     // Fall back to an empty PathBuf when cwd is absent or was rejected (e.g.
     // contained C0/C1 control characters or DEL). The session can still be
     // listed — it just cannot be meaningfully resumed via `cd`, and we prefer
@@ -84,9 +95,44 @@ pub fn parse_session(candidate: &SessionCandidate, home_dir: &str) -> Result<Ses
         last_active,
         first_prompt,
         slug,
+        active: false,
+        active_pid: None,
+        active_match: None,
+        continuation,
+        earlier: vec![],
+        duplicates: vec![],
     })
 }
 
+/// Record any reference `line` makes to an earlier session: a foreign
+/// `sessionId`, a summary's `leafUuid`, or a parent uuid not defined earlier in
+/// this file. Carried-over records and summaries come first in a continued
+/// session, so the 50-line head is enough to find them.
+fn collect_continuation(
+    line: &JsonlLine,
+    session_id: &str,
+    seen_uuids: &HashSet<String>,
+    refs: &mut ContinuationRefs,
+) {
+    if let Some(id) = &line.session_id
+        && id != session_id
+        && !refs.session_ids.contains(id)
+    {
+        refs.session_ids.push(id.clone());
+    }
+
+    let uuids = [
+        line.leaf_uuid.as_ref(),
+        line.parent_uuid.as_ref(),
+        line.logical_parent_uuid.as_ref(),
+    ];
+    for uuid in uuids.into_iter().flatten() {
+        if !seen_uuids.contains(uuid) && !refs.uuids.contains(uuid) {
+            refs.uuids.push(uuid.clone());
+        }
+    }
+}
+
 /// Extract text from a `serde_json::Value` that is either a string or an array
 /// containing `{"type":"text","text":"..."}` items.
 pub fn extract_text_from_content(value: &serde_json::Value) -> Option<String> {
@@ -697,4 +743,45 @@ mod tests {
         assert_eq!(info.first_prompt.as_deref(), Some("Valid prompt"));
         assert_eq!(info.project_dir, PathBuf::from("/tmp/proj"));
     }
+
+    #[test]
+    fn parse_collects_continuation_refs() {
+        let candidate = fixture_candidate("continued.jsonl");
+        let info = parse_session(&candidate, "/Users/testuser").unwrap();
+        assert_eq!(
+            info.continuation.uuids,
+            vec!["a0000011-0000-4000-8000-000000000011"]
+        );
+        assert_eq!(info.first_prompt.as_deref(), Some("Now write one about Go"));
+
+        // Parents defined earlier in the file are not references.
+        let candidate = fixture_candidate("branching.jsonl");
+        let info = parse_session(&candidate, "/Users/testuser").unwrap();
+        assert!(info.continuation.uuids.is_empty());
+    }
+
+    #[test]
+    fn parse_collects_foreign_session_ids() {
+        let tmp = std::env::temp_dir().join("ccsesh_test_foreign_session");
+        let _ = std::fs::create_dir_all(&tmp);
+        let path = tmp.join(format!("{}.jsonl", TEST_UUID));
+        std::fs::write(
+            &path,
+            format!(
+                "{}\n{}\n",
+                r#"{"type":"user","sessionId":"1a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d","message":{"content":"Earlier prompt"}}"#,
+                r#"{"type":"user","sessionId":"eb53d999-8692-42ce-a376-4f82206a086d","message":{"content":"New prompt"}}"#,
+            ),
+        )
+        .unwrap();
+        let candidate = SessionCandidate {
+            path,
+            mtime: SystemTime::now(),
+        };
+        let info = parse_session(&candidate, "/tmp").unwrap();
+        assert_eq!(
+            info.continuation.session_ids,
+            vec!["1a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d"]
+        );
+    }
 }
@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::bundle::{PathMapping, rewrite_jsonl};
+use crate::discover::{self, project_folder_name};
+use crate::display;
+use crate::errors::CcseshError;
+use crate::parse::is_valid_uuid;
+use crate::sessions;
+
+/// Suffix of the copy kept of every file `relocate` rewrites.
+pub const BACKUP_SUFFIX: &str = ".bak";
+
+/// One session `relocate` would change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionMove {
+    pub session_id: String,
+    /// The transcript now.
+    pub from: PathBuf,
+    /// Where it goes: a different project folder when its starting directory
+    /// moved, otherwise `from`.
+    pub to: PathBuf,
+    /// Records whose `cwd` changes, across the transcript and its subagent
+    /// transcripts.
+    pub records: usize,
+    /// Subagent transcripts under `<folder>/<id>/` that also get rewritten.
+    pub extra_files: Vec<PathBuf>,
+    /// Whether a running `claude` appears to hold the session. Such sessions
+    /// are skipped unless forced.
+    pub active: bool,
+    pub active_pid: Option<u32>,
+}
+
+impl SessionMove {
+    pub fn moves_folder(&self) -> bool {
+        self.from != self.to
+    }
+
+    fn session_dir(path: &Path, id: &str) -> Option<PathBuf> {
+        path.parent().map(|folder| folder.join(id))
+    }
+}
+
+/// Every session with a record whose `cwd` is `mapping.from` or lies under
+/// it, newest first.
+///
+/// A session moves to the project folder of its first working directory
+/// after mapping, which is where Claude Code would have filed it had it been
+/// started in the new location. Sessions that only `cd` into the old tree
+/// later on are rewritten in place.
+pub fn plan(home_dir: &str, mapping: &PathMapping) -> Result<Vec<SessionMove>> {
+    let mappings = std::slice::from_ref(mapping);
+    let active: HashMap<String, Option<u32>> = sessions::load_all_sessions(home_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| s.active)
+        .map(|s| (s.session_id, s.active_pid))
+        .collect();
+
+    let mut moves = Vec::new();
+    for candidate in discover::discover_sessions(home_dir, usize::MAX)? {
+        let path = candidate.path;
+        let Some(id) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|s| is_valid_uuid(s))
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let Ok(contents) = fs::read(&path) else {
+            continue;
+        };
+        let first_cwd = first_cwd(&contents);
+        let (_, mut records) = rewrite_jsonl(contents, mappings);
+
+        let extra_files = SessionMove::session_dir(&path, &id)
+            .map(|dir| jsonl_files(&dir))
+            .unwrap_or_default();
+        for file in &extra_files {
+            if let Ok(contents) = fs::read(file) {
+                records += rewrite_jsonl(contents, mappings).1;
+            }
+        }
+        if records == 0 {
+            continue;
+        }
+
+        let to = match first_cwd.and_then(|cwd| mapping.apply(&cwd)) {
+            Some(new_dir) => path
+                .parent()
+                .and_then(Path::parent)
+                .map(|projects| {
+                    projects
+                        .join(project_folder_name(&new_dir))
+                        .join(format!("{}.jsonl", id))
+                })
+                .unwrap_or_else(|| path.clone()),
+            None => path.clone(),
+        };
+        moves.push(SessionMove {
+            active: active.contains_key(&id),
+            active_pid: active.get(&id).copied().flatten(),
+            session_id: id,
+            from: path,
+            to,
+            records,
+            extra_files,
+        });
+    }
+    Ok(moves)
+}
+
+/// The `cwd` of the first record that has one.
+fn first_cwd(contents: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(contents)
+        .lines()
+        .filter(|line| line.contains("\"cwd\""))
+        .find_map(|line| {
+            let record: serde_json::Value = serde_json::from_str(line).ok()?;
+            record.get("cwd")?.as_str().map(str::to_string)
+        })
+}
+
+/// `.jsonl` files under `dir`, recursively, skipping symlinks.
+fn jsonl_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            files.extend(jsonl_files(&path));
+        } else if file_type.is_file() && path.extension().is_some_and(|e| e == "jsonl") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Carry out `moves`, skipping active sessions unless `force`. Returns the
+/// moves that were applied.
+///
+/// Every destination is checked before anything is touched. Each rewritten
+/// file is first copied to `<file>.<timestamp>.bak`, then replaced by
+/// writing a temporary file beside its destination and renaming it into
+/// place, so a crash never leaves a half-written transcript. Modification
+/// times are kept, so the listing order does not change.
+pub fn apply(
+    moves: &[SessionMove],
+    mapping: &PathMapping,
+    force: bool,
+) -> Result<Vec<SessionMove>> {
+    let selected: Vec<&SessionMove> = moves.iter().filter(|m| force || !m.active).collect();
+
+    for m in selected.iter().filter(|m| m.moves_folder()) {
+        let new_dir = SessionMove::session_dir(&m.to, &m.session_id);
+        let old_dir = SessionMove::session_dir(&m.from, &m.session_id);
+        if m.to.exists() {
+            return Err(CcseshError::SessionExists { path: m.to.clone() }.into());
+        }
+        if let (Some(new_dir), Some(old_dir)) = (new_dir, old_dir)
+            && old_dir.is_dir()
+            && new_dir.exists()
+        {
+            return Err(CcseshError::SessionExists { path: new_dir }.into());
+        }
+    }
+
+    let mappings = std::slice::from_ref(mapping);
+    for m in &selected {
+        for file in &m.extra_files {
+            rewrite_file(file, file, mappings)?;
+        }
+        rewrite_file(&m.from, &m.to, mappings)?;
+        if m.moves_folder() {
+            fs::remove_file(&m.from)
+                .with_context(|| format!("Failed to remove {}", m.from.display()))?;
+            if let (Some(old_dir), Some(new_dir)) = (
+                SessionMove::session_dir(&m.from, &m.session_id),
+                SessionMove::session_dir(&m.to, &m.session_id),
+            ) && old_dir.is_dir()
+            {
+                fs::rename(&old_dir, &new_dir).with_context(|| {
+                    format!(
+                        "Failed to move {} to {}",
+                        old_dir.display(),
+                        new_dir.display()
+                    )
+                })?;
+            }
+        }
+    }
+    Ok(selected.into_iter().cloned().collect())
+}
+
+/// Back up `from`, then atomically write its rewritten contents to `to`.
+fn rewrite_file(from: &Path, to: &Path, mappings: &[PathMapping]) -> Result<()> {
+    let read_error = |source| CcseshError::SessionReadError {
+        path: from.to_path_buf(),
+        source,
+    };
+    let original = fs::read(from).map_err(read_error)?;
+    let metadata = fs::metadata(from).map_err(read_error)?;
+    let (contents, changed) = rewrite_jsonl(original.clone(), mappings);
+    if changed == 0 && from == to {
+        return Ok(());
+    }
+
+    back_up(from, &original, metadata.permissions())
+        .with_context(|| format!("Failed to back up {}", from.display()))?;
+
+    let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+    write_atomic(to, &contents, Some(metadata.permissions()), modified)
+        .with_context(|| format!("Failed to write {}", to.display()))
+}
+
+/// Save `contents` as `<path>.<timestamp>.bak`, adding `-2`, `-3`, ... when
+/// that name is taken, so an earlier backup (from a previous relocate, or a
+/// rerun after one failed) is never overwritten.
+fn back_up(path: &Path, contents: &[u8], permissions: fs::Permissions) -> std::io::Result<PathBuf> {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S").to_string();
+    let mut attempt = 1;
+    loop {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(format!(".{}", stamp));
+        if attempt > 1 {
+            backup.push(format!("-{}", attempt));
+        }
+        backup.push(BACKUP_SUFFIX);
+        let backup = PathBuf::from(backup);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&backup)
+        {
+            Ok(mut file) => {
+                file.set_permissions(permissions)?;
+                file.write_all(contents)?;
+                return Ok(backup);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Write `contents` to a temporary file next to `path` and rename it into
+/// place, so `path` is never left half-written.
+pub(crate) fn write_atomic(
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<fs::Permissions>,
+    modified: SystemTime,
+) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{}.ccsesh-tmp", name));
+
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        if let Some(permissions) = &permissions {
+            file.set_permissions(permissions.clone())?;
+        }
+        file.set_modified(modified)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Text report of a relocation, or of what one would do with `dry_run`.
+pub fn format_report(
+    moves: &[SessionMove],
+    applied: &[SessionMove],
+    mapping: &PathMapping,
+    dry_run: bool,
+) -> String {
+    if moves.is_empty() {
+        return format!(
+            "No sessions have a working directory under {}.\n",
+            mapping.from
+        );
+    }
+
+    let mut out = String::new();
+    let count = if dry_run { moves.len() } else { applied.len() };
+    out.push_str(&format!(
+        "{} {} from {} to {}:\n",
+        if dry_run {
+            "Would relocate"
+        } else {
+            "Relocated"
+        },
+        display::plural(count, "session"),
+        mapping.from,
+        mapping.to
+    ));
+
+    let folder = |path: &Path| {
+        path.parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    for m in moves {
+        let skipped = !dry_run && !applied.iter().any(|a| a.session_id == m.session_id);
+        let records = display::plural(m.records, "record");
+        let place = if m.moves_folder() {
+            format!("{} -> {}", folder(&m.from), folder(&m.to))
+        } else {
+            format!("stays in {}", folder(&m.from))
+        };
+        let note = if m.active && (skipped || dry_run) {
+            format!(
+                "  [open{}; {}]",
+                m.active_pid
+                    .map(|p| format!(" in pid {}", p))
+                    .unwrap_or_default(),
+                if dry_run { "needs --force" } else { "skipped" }
+            )
+        } else {
+            String::new()
+        };
+        out.push_str(&format!(
+            "  {}  {:<11}  {}{}\n",
+            m.session_id, records, place, note
+        ));
+    }
+
+    if !dry_run && !applied.is_empty() {
+        out.push_str(&format!(
+            "Originals were kept as {} files next to their old locations.\n",
+            BACKUP_SUFFIX
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const A: &str = "eb53d999-8692-42ce-a376-4f82206a086d";
+    const B: &str = "ab53d999-8692-42ce-a376-4f82206a086d";
+    const C: &str = "cb53d999-8692-42ce-a376-4f82206a086d";
+
+    fn record(cwd: &str) -> String {
+        format!(
+            "{{\"type\":\"user\",\"cwd\":\"{}\",\"message\":{{\"content\":\"hi from {}\"}}}}\n",
+            cwd, cwd
+        )
+    }
+
+    fn write(path: &Path, contents: &str, mtime_secs: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(mtime_secs))
+            .unwrap();
+    }
+
+    /// A started in ~/dev/api (with a subagent), B started elsewhere but
+    /// `cd`-ed into it, C never touched it.
+    fn home() -> (assert_fs::TempDir, PathBuf) {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let projects = tmp.path().join(".claude").join("projects");
+        let api = projects.join("-home-u-dev-api");
+        write(
+            &api.join(format!("{}.jsonl", A)),
+            &(record("/home/u/dev/api") + &record("/home/u/dev/api/src")),
+            1_000,
+        );
+        write(
+            &api.join(A).join("subagents").join("agent-1.jsonl"),
+            &record("/home/u/dev/api"),
+            1_000,
+        );
+        write(
+            &projects.join("-home-u").join(format!("{}.jsonl", B)),
+            &(record("/home/u") + &record("/home/u/dev/api")),
+            2_000,
+        );
+        write(
+            &projects
+                .join("-home-u-dev-apiary")
+                .join(format!("{}.jsonl", C)),
+            &record("/home/u/dev/apiary"),
+            3_000,
+        );
+        (tmp, projects)
+    }
+
+    fn mapping() -> PathMapping {
+        PathMapping::parse("/home/u/dev/api=/home/u/work/api").unwrap()
+    }
+
+    /// Contents of the backups of `path`, sorted.
+    fn backups(path: &Path) -> Vec<String> {
+        let prefix = format!("{}.", path.file_name().unwrap().to_string_lossy());
+        let mut contents: Vec<String> = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| {
+                let name = p.file_name().unwrap().to_string_lossy();
+                name.starts_with(&prefix) && name.ends_with(BACKUP_SUFFIX)
+            })
+            .map(|p| fs::read_to_string(p).unwrap())
+            .collect();
+        contents.sort();
+        contents
+    }
+
+    #[test]
+    fn plan_finds_affected_sessions() {
+        let (tmp, projects) = home();
+        let moves = plan(tmp.path().to_str().unwrap(), &mapping()).unwrap();
+        assert_eq!(
+            moves
+                .iter()
+                .map(|m| (m.session_id.as_str(), m.records, m.moves_folder()))
+                .collect::<Vec<_>>(),
+            vec![(B, 1, false), (A, 3, true)]
+        );
+        assert_eq!(
+            moves[1].to,
+            projects
+                .join("-home-u-work-api")
+                .join(format!("{}.jsonl", A))
+        );
+        // Planning changes nothing.
+        assert!(
+            projects
+                .join("-home-u-dev-api")
+                .join(format!("{}.jsonl", A))
+                .is_file()
+        );
+    }
+
+    #[test]
+    fn apply_rewrites_moves_and_backs_up() {
+        let (tmp, projects) = home();
+        let moves = plan(tmp.path().to_str().unwrap(), &mapping()).unwrap();
+        let applied = apply(&moves, &mapping(), false).unwrap();
+        assert_eq!(applied.len(), 2);
+
+        let old = projects.join("-home-u-dev-api");
+        let new = projects.join("-home-u-work-api");
+        let moved = new.join(format!("{}.jsonl", A));
+        // Only `cwd` changes, not the message text.
+        assert_eq!(
+            fs::read_to_string(&moved).unwrap(),
+            "{\"type\":\"user\",\"cwd\":\"/home/u/work/api\",\"message\":{\"content\":\"hi from /home/u/dev/api\"}}\n\
+             {\"type\":\"user\",\"cwd\":\"/home/u/work/api/src\",\"message\":{\"content\":\"hi from /home/u/dev/api/src\"}}\n"
+        );
+        assert_eq!(
+            fs::metadata(&moved).unwrap().modified().unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1_000)
+        );
+        assert!(!old.join(format!("{}.jsonl", A)).exists());
+        assert_eq!(
+            backups(&old.join(format!("{}.jsonl", A))),
+            vec![record("/home/u/dev/api") + &record("/home/u/dev/api/src")]
+        );
+        let subagent = new.join(A).join("subagents").join("agent-1.jsonl");
+        assert!(
+            fs::read_to_string(subagent)
+                .unwrap()
+                .contains("\"cwd\":\"/home/u/work/api\"")
+        );
+
+        // B is rewritten where it is; C is untouched.
+        let b = projects.join("-home-u").join(format!("{}.jsonl", B));
+        assert!(
+            fs::read_to_string(&b)
+                .unwrap()
+                .contains("\"cwd\":\"/home/u/work/api\"")
+        );
+        assert!(
+            backups(
+                &projects
+                    .join("-home-u-dev-apiary")
+                    .join(format!("{}.jsonl", C))
+            )
+            .is_empty()
+        );
+
+        // No temporary files are left behind.
+        for dir in [&old, &new, &projects.join("-home-u")] {
+            for entry in fs::read_dir(dir).unwrap() {
+                let name = entry.unwrap().file_name();
+                assert!(!name.to_string_lossy().ends_with(".ccsesh-tmp"));
+            }
+        }
+        assert!(
+            plan(tmp.path().to_str().unwrap(), &mapping())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn second_relocate_keeps_earlier_backups() {
+        let (tmp, projects) = home();
+        let home = tmp.path().to_str().unwrap();
+        let b = projects.join("-home-u").join(format!("{}.jsonl", B));
+        let original = fs::read_to_string(&b).unwrap();
+        apply(&plan(home, &mapping()).unwrap(), &mapping(), false).unwrap();
+        let first = fs::read_to_string(&b).unwrap();
+
+        let onward = PathMapping::parse("/home/u/work/api=/srv/api").unwrap();
+        apply(&plan(home, &onward).unwrap(), &onward, false).unwrap();
+        assert!(
+            fs::read_to_string(&b)
+                .unwrap()
+                .contains("\"cwd\":\"/srv/api\"")
+        );
+        let mut expected = vec![original, first];
+        expected.sort();
+        assert_eq!(backups(&b), expected);
+    }
+
+    #[test]
+    fn apply_refuses_to_overwrite_and_skips_active() {
+        let (tmp, projects) = home();
+        let taken = projects
+            .join("-home-u-work-api")
+            .join(format!("{}.jsonl", A));
+        write(&taken, "{}\n", 1);
+        let moves = plan(tmp.path().to_str().unwrap(), &mapping()).unwrap();
+        let err = apply(&moves, &mapping(), false).unwrap_err();
+        assert!(err.to_string().starts_with("Session already exists at"));
+        // Nothing was changed.
+        let b = projects.join("-home-u").join(format!("{}.jsonl", B));
+        assert!(
+            fs::read_to_string(&b)
+                .unwrap()
+                .contains("\"cwd\":\"/home/u/dev/api\"")
+        );
+
+        fs::remove_file(&taken).unwrap();
+        let mut moves = plan(tmp.path().to_str().unwrap(), &mapping()).unwrap();
+        moves[0].active = true;
+        moves[0].active_pid = Some(4242);
+        let applied = apply(&moves, &mapping(), false).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert!(
+            fs::read_to_string(&b)
+                .unwrap()
+                .contains("\"cwd\":\"/home/u/dev/api\"")
+        );
+        let report = format_report(&moves, &applied, &mapping(), false);
+        assert!(report.contains(&format!(
+            "{}  1 record     stays in -home-u  [open in pid 4242; skipped]",
+            B
+        )));
+    }
+
+    #[test]
+    fn dry_run_report() {
+        let (tmp, _) = home();
+        let moves = plan(tmp.path().to_str().unwrap(), &mapping()).unwrap();
+        assert_eq!(
+            format_report(&moves, &[], &mapping(), true),
+            format!(
+                "Would relocate 2 sessions from /home/u/dev/api to /home/u/work/api:\n  \
+                 {}  1 record     stays in -home-u\n  \
+                 {}  3 records    -home-u-dev-api -> -home-u-work-api\n",
+                B, A
+            )
+        );
+        let none = PathMapping::parse("/nowhere=/x").unwrap();
+        assert_eq!(
+            format_report(&[], &[], &none, true),
+            "No sessions have a working directory under /nowhere.\n"
+        );
+    }
+}
@@ -0,0 +1,678 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::display;
+use crate::files;
+use crate::timefmt::Zone;
+use crate::transcript;
+use crate::types::{ContentBlock, SessionInfo, TranscriptRecord};
+
+/// How far back `ccsesh report` looks unless `--since` says otherwise.
+pub const DEFAULT_SINCE: &str = "7d";
+
+/// Gaps between messages longer than this are time away from the session
+/// and do not count towards its duration.
+const IDLE_GAP_SECS: i64 = 30 * 60;
+
+/// Longest session title before it is truncated.
+const TITLE_WIDTH: usize = 80;
+
+/// A commit created during a session, from `git commit` output in a tool
+/// result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Commit {
+    pub hash: String,
+    pub subject: String,
+}
+
+/// What happened in one session within the report's range.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionDigest {
+    pub session_id: String,
+    /// The session's summary, else its first prompt or slug.
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Time between messages, leaving out gaps of more than 30 minutes.
+    pub active_seconds: i64,
+    pub messages: usize,
+    /// Relative to the project directory when under it, in first-edit order.
+    pub files_edited: Vec<String>,
+    pub commits: Vec<Commit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayDigest {
+    pub date: NaiveDate,
+    /// In order of their first message.
+    pub sessions: Vec<SessionDigest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectDigest {
+    pub project_dir: String,
+    pub project_dir_display: String,
+    pub active_seconds: i64,
+    /// Oldest first.
+    pub days: Vec<DayDigest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub sessions: usize,
+    /// Most active time first.
+    pub projects: Vec<ProjectDigest>,
+}
+
+/// Extracts session digests from transcript records.
+pub struct Digester {
+    commit: Regex,
+}
+
+impl Default for Digester {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Digester {
+    pub fn new() -> Self {
+        Digester {
+            // `git commit` prints "[main 1a2b3c4] Subject", with
+            // "(root-commit)" or "detached HEAD" before the hash at times.
+            commit: Regex::new(r"(?m)^\[[^\]\n]+ ([0-9a-f]{7,40})\] (.+)$").unwrap(),
+        }
+    }
+
+    /// The digest of the part of a session from `since` on, or `None` if it
+    /// has no messages in that range. Records without a timestamp count as
+    /// written at the file's mtime.
+    pub fn digest(
+        &self,
+        session: &SessionInfo,
+        records: &[TranscriptRecord],
+        since: DateTime<Utc>,
+    ) -> Option<SessionDigest> {
+        let own_uuids: HashSet<&str> = records
+            .iter()
+            .filter_map(|r| r.meta()?.uuid.as_deref())
+            .collect();
+        let mut summary = None;
+        let mut times = Vec::new();
+        let mut files_edited: Vec<String> = Vec::new();
+        let mut commits: Vec<Commit> = Vec::new();
+
+        for record in records {
+            let entry = match record {
+                // Summaries carried over from an earlier session title that one.
+                TranscriptRecord::Summary(s)
+                    if s.leaf_uuid
+                        .as_deref()
+                        .is_some_and(|uuid| own_uuids.contains(uuid)) =>
+                {
+                    summary = Some(s.summary.clone());
+                    continue;
+                }
+                TranscriptRecord::User(entry) | TranscriptRecord::Assistant(entry) => entry,
+                _ => continue,
+            };
+            let time = entry.meta.time_or(session.last_active);
+            if time < since || entry.is_meta == Some(true) {
+                continue;
+            }
+            times.push(time);
+
+            for touch in files::touches(entry) {
+                if touch.modified {
+                    let path =
+                        files::relative_to(&touch.path.to_string_lossy(), &session.project_dir);
+                    if !files_edited.contains(&path) {
+                        files_edited.push(path);
+                    }
+                }
+            }
+            for block in entry.message.content.blocks() {
+                if let ContentBlock::ToolResult(result) = block
+                    && let Some(text) = result.content.as_ref().and_then(|c| c.text())
+                {
+                    for caps in self.commit.captures_iter(&text) {
+                        if !commits.iter().any(|c| c.hash == caps[1]) {
+                            commits.push(Commit {
+                                hash: caps[1].to_string(),
+                                subject: caps[2].trim().to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        times.sort();
+        let (&start, &end) = (times.first()?, times.last()?);
+        let active_seconds = times
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).num_seconds())
+            .filter(|&gap| gap <= IDLE_GAP_SECS)
+            .sum();
+        let title = summary
+            .or_else(|| session.first_prompt.clone())
+            .or_else(|| session.slug.clone())
+            .unwrap_or_else(|| "(untitled)".to_string());
+
+        Some(SessionDigest {
+            session_id: session.session_id.clone(),
+            title: display::truncate_prompt(&title, TITLE_WIDTH),
+            start,
+            end,
+            active_seconds,
+            messages: times.len(),
+            files_edited,
+            commits,
+        })
+    }
+}
+
+/// Digests every session with messages between `from` and `to`. Sessions
+/// last active before `from` are not read; unreadable ones are skipped.
+pub fn build_report(
+    sessions: &[SessionInfo],
+    zone: Zone,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Report {
+    let digester = Digester::new();
+    let digests = sessions
+        .iter()
+        .filter(|s| s.last_active >= from)
+        .filter_map(|s| {
+            let records = transcript::read_records(&s.path).ok()?;
+            Some((s, digester.digest(s, &records, from)?))
+        });
+    group(digests, zone, from, to)
+}
+
+/// Groups digests by project and by the day (in `zone`) of each session's
+/// last message.
+pub fn group<'a>(
+    digests: impl IntoIterator<Item = (&'a SessionInfo, SessionDigest)>,
+    zone: Zone,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Report {
+    let mut projects: HashMap<String, (&str, BTreeMap<NaiveDate, Vec<SessionDigest>>)> =
+        HashMap::new();
+    let mut sessions = 0;
+    for (session, digest) in digests {
+        sessions += 1;
+        let (_, days) = projects
+            .entry(session.project_dir.to_string_lossy().into_owned())
+            .or_insert((&session.project_dir_display, BTreeMap::new()));
+        days.entry(zone.local(digest.end).date())
+            .or_default()
+            .push(digest);
+    }
+
+    let mut projects: Vec<ProjectDigest> = projects
+        .into_iter()
+        .map(|(dir, (display, days))| {
+            let days: Vec<DayDigest> = days
+                .into_iter()
+                .map(|(date, mut sessions)| {
+                    sessions.sort_by_key(|s| s.start);
+                    DayDigest { date, sessions }
+                })
+                .collect();
+            ProjectDigest {
+                project_dir_display: if display.is_empty() {
+                    "(unknown)".to_string()
+                } else {
+                    display.to_string()
+                },
+                project_dir: dir,
+                active_seconds: days
+                    .iter()
+                    .flat_map(|d| &d.sessions)
+                    .map(|s| s.active_seconds)
+                    .sum(),
+                days,
+            }
+        })
+        .collect();
+    projects.sort_by(|a, b| {
+        b.active_seconds
+            .cmp(&a.active_seconds)
+            .then_with(|| a.project_dir_display.cmp(&b.project_dir_display))
+    });
+
+    Report {
+        from,
+        to,
+        sessions,
+        projects,
+    }
+}
+
+/// "<1m", "45m", or "2h 5m".
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    match minutes {
+        0 => "<1m".to_string(),
+        1..60 => format!("{}m", minutes),
+        _ => format!("{}h {}m", minutes / 60, minutes % 60),
+    }
+}
+
+fn session_stats(session: &SessionDigest, zone: Zone) -> String {
+    format!(
+        "{}, {}, {}",
+        zone.local(session.start).format("%H:%M"),
+        format_duration(session.active_seconds),
+        display::plural(session.messages, "message")
+    )
+}
+
+fn range(report: &Report, zone: Zone) -> (NaiveDate, NaiveDate) {
+    (zone.local(report.from).date(), zone.local(report.to).date())
+}
+
+/// The report as indented plain text.
+pub fn format_report(report: &Report, zone: Zone) -> String {
+    let (from, to) = range(report, zone);
+    if report.projects.is_empty() {
+        return format!("No sessions between {} and {}.\n", from, to);
+    }
+    let mut out = format!(
+        "Report from {} to {}: {} in {}\n",
+        from,
+        to,
+        display::plural(report.sessions, "session"),
+        display::plural(report.projects.len(), "project")
+    );
+    for project in &report.projects {
+        out.push_str(&format!(
+            "\n{}  ({})\n",
+            project.project_dir_display,
+            format_duration(project.active_seconds)
+        ));
+        for day in &project.days {
+            out.push_str(&format!("  {}\n", day.date.format("%a %Y-%m-%d")));
+            for session in &day.sessions {
+                out.push_str(&format!(
+                    "    {}  ({})\n",
+                    session.title,
+                    session_stats(session, zone)
+                ));
+                if !session.files_edited.is_empty() {
+                    out.push_str(&format!(
+                        "      edited: {}\n",
+                        session.files_edited.join(", ")
+                    ));
+                }
+                for commit in &session.commits {
+                    out.push_str(&format!(
+                        "      commit {} {}\n",
+                        short_hash(&commit.hash),
+                        commit.subject
+                    ));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// The report as a Markdown digest: a section per project, a subsection per
+/// day, and a list item per session.
+pub fn format_report_markdown(report: &Report, zone: Zone) -> String {
+    let (from, to) = range(report, zone);
+    let mut out = format!("# Report: {} to {}\n\n", from, to);
+    if report.projects.is_empty() {
+        out.push_str("No sessions.\n");
+        return out;
+    }
+    out.push_str(&format!(
+        "{} in {}.\n",
+        display::plural(report.sessions, "session"),
+        display::plural(report.projects.len(), "project")
+    ));
+    for project in &report.projects {
+        out.push_str(&format!(
+            "\n## {} ({})\n",
+            escape_markdown(&project.project_dir_display),
+            format_duration(project.active_seconds)
+        ));
+        for day in &project.days {
+            out.push_str(&format!("\n### {}\n\n", day.date.format("%a %Y-%m-%d")));
+            for session in &day.sessions {
+                out.push_str(&format!(
+                    "- **{}** ({})\n",
+                    escape_markdown(&session.title),
+                    session_stats(session, zone)
+                ));
+                if !session.files_edited.is_empty() {
+                    let files: Vec<String> = session
+                        .files_edited
+                        .iter()
+                        .map(|f| format!("`{}`", f))
+                        .collect();
+                    out.push_str(&format!("  - Edited: {}\n", files.join(", ")));
+                }
+                for commit in &session.commits {
+                    out.push_str(&format!(
+                        "  - Commit `{}` {}\n",
+                        short_hash(&commit.hash),
+                        escape_markdown(&commit.subject)
+                    ));
+                }
+            }
+        }
+    }
+    out
+}
+
+pub fn format_report_json(report: &Report) -> String {
+    serde_json::to_string_pretty(report).expect("report serializes")
+}
+
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+/// Backslash-escapes characters that would otherwise start Markdown
+/// formatting in a title or commit subject.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn session(id: &str, project: &str) -> SessionInfo {
+        SessionInfo {
+            path: PathBuf::from(format!("/nonexistent/{}.jsonl", id)),
+            project_dir_display: project.replace("/Users/testuser", "~"),
+            last_active: at("2026-02-17T12:00:00Z"),
+            first_prompt: Some("Add cursor pagination".to_string()),
+            slug: Some("quiet-autumn-leaf".to_string()),
+            ..SessionInfo::fixture(id, project)
+        }
+    }
+
+    fn records(lines: &[&str]) -> Vec<TranscriptRecord> {
+        lines
+            .iter()
+            .map(|l| TranscriptRecord::parse(l).unwrap())
+            .collect()
+    }
+
+    fn user(uuid: &str, time: &str, content: &str) -> String {
+        format!(
+            r#"{{"type":"user","uuid":"{}","timestamp":"{}","message":{{"role":"user","content":{}}}}}"#,
+            uuid, time, content
+        )
+    }
+
+    fn assistant(uuid: &str, time: &str, content: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","uuid":"{}","timestamp":"{}","message":{{"role":"assistant","content":{}}}}}"#,
+            uuid, time, content
+        )
+    }
+
+    fn digest(session: &SessionInfo, lines: &[String]) -> Option<SessionDigest> {
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        Digester::new().digest(session, &records(&lines), at("2026-02-10T00:00:00Z"))
+    }
+
+    #[test]
+    fn collects_edits_commits_and_duration() {
+        let s = session("s1", "/Users/testuser/dev/api");
+        let d = digest(
+            &s,
+            &[
+                user("u1", "2026-02-17T09:00:00Z", r#""Paginate /users""#),
+                assistant(
+                    "a1",
+                    "2026-02-17T09:05:00Z",
+                    r#"[{"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"/Users/testuser/dev/api/src/users.rs","old_string":"a","new_string":"b"}},
+                        {"type":"tool_use","id":"t2","name":"Write","input":{"file_path":"/tmp/notes.md","content":"x"}},
+                        {"type":"tool_use","id":"t3","name":"Read","input":{"file_path":"/Users/testuser/dev/api/README.md"}},
+                        {"type":"tool_use","id":"t4","name":"Edit","input":{"file_path":"/Users/testuser/dev/api/src/users.rs"}}]"#,
+                ),
+                user(
+                    "u2",
+                    "2026-02-17T09:10:00Z",
+                    r#"[{"type":"tool_result","tool_use_id":"t5","content":"[main 1a2b3c4] Add cursor pagination\n 2 files changed, 40 insertions(+)"}]"#,
+                ),
+                // Two hours away, then back for a minute.
+                user("u3", "2026-02-17T11:10:00Z", r#""Thanks""#),
+                assistant("a2", "2026-02-17T11:11:00Z", r#""Done.""#),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(d.title, "Add cursor pagination");
+        assert_eq!(d.start, at("2026-02-17T09:00:00Z"));
+        assert_eq!(d.end, at("2026-02-17T11:11:00Z"));
+        assert_eq!(d.active_seconds, 11 * 60);
+        assert_eq!(d.messages, 5);
+        assert_eq!(d.files_edited, vec!["src/users.rs", "/tmp/notes.md"]);
+        assert_eq!(
+            d.commits,
+            vec![Commit {
+                hash: "1a2b3c4".to_string(),
+                subject: "Add cursor pagination".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn commit_output_variants() {
+        let s = session("s1", "/p");
+        let result = r#"[{"type":"tool_result","tool_use_id":"t1","content":[{"type":"text","text":"[main (root-commit) 0f0f0f0f] Initial commit\n[detached HEAD 9e8d7c6] Try fix\n[main 0f0f0f0f] Initial commit\nnot [a commit] line"}]}]"#;
+        let d = digest(&s, &[user("u1", "2026-02-17T09:00:00Z", result)]).unwrap();
+        let hashes: Vec<&str> = d.commits.iter().map(|c| c.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["0f0f0f0f", "9e8d7c6"]);
+    }
+
+    #[test]
+    fn title_prefers_own_summary() {
+        let s = session("s1", "/p");
+        let lines = [
+            // Carried over from an earlier session: not this one's title.
+            r#"{"type":"summary","summary":"Earlier work","leafUuid":"elsewhere"}"#.to_string(),
+            user("u1", "2026-02-17T09:00:00Z", r#""Hi""#),
+            r#"{"type":"summary","summary":"Cursor pagination for /users","leafUuid":"u1"}"#
+                .to_string(),
+        ];
+        assert_eq!(
+            digest(&s, &lines).unwrap().title,
+            "Cursor pagination for /users"
+        );
+
+        let mut untitled = session("s2", "/p");
+        untitled.first_prompt = None;
+        untitled.slug = None;
+        let d = digest(&untitled, &[user("u1", "2026-02-17T09:00:00Z", r#""Hi""#)]);
+        assert_eq!(d.unwrap().title, "(untitled)");
+    }
+
+    #[test]
+    fn skips_messages_before_since() {
+        let s = session("s1", "/p");
+        assert_eq!(
+            digest(&s, &[user("u1", "2026-02-01T09:00:00Z", r#""Old""#)]),
+            None
+        );
+        let d = digest(
+            &s,
+            &[
+                assistant(
+                    "a1",
+                    "2026-02-01T09:00:00Z",
+                    r#"[{"type":"tool_use","id":"t1","name":"Write","input":{"file_path":"/p/old.rs"}}]"#,
+                ),
+                user("u1", "2026-02-17T09:00:00Z", r#""New""#),
+            ],
+        )
+        .unwrap();
+        assert_eq!(d.messages, 1);
+        assert!(d.files_edited.is_empty());
+    }
+
+    fn sample_report() -> Report {
+        let api = session("s1", "/Users/testuser/dev/api");
+        let mut api2 = session("s2", "/Users/testuser/dev/api");
+        api2.first_prompt = Some("Fix *flaky* test".to_string());
+        let web = session("s3", "/Users/testuser/dev/web");
+        let make = |s: &SessionInfo, start: &str, end: &str, active: i64| SessionDigest {
+            session_id: s.session_id.clone(),
+            title: s.first_prompt.clone().unwrap(),
+            start: at(start),
+            end: at(end),
+            active_seconds: active,
+            messages: 4,
+            files_edited: Vec::new(),
+            commits: Vec::new(),
+        };
+        let mut first = make(
+            &api,
+            "2026-02-17T09:00:00Z",
+            "2026-02-17T10:05:00Z",
+            65 * 60,
+        );
+        first.files_edited = vec!["src/users.rs".to_string(), "tests/users.rs".to_string()];
+        first.commits = vec![Commit {
+            hash: "1a2b3c4d5e6f".to_string(),
+            subject: "Add cursor pagination".to_string(),
+        }];
+        let digests = vec![
+            (
+                &api2,
+                make(
+                    &api2,
+                    "2026-02-17T14:00:00Z",
+                    "2026-02-17T14:20:00Z",
+                    20 * 60,
+                ),
+            ),
+            (&api, first),
+            (
+                &web,
+                make(&web, "2026-02-16T23:30:00Z", "2026-02-16T23:40:00Z", 30),
+            ),
+        ];
+        group(
+            digests,
+            Zone::Utc,
+            at("2026-02-11T00:00:00Z"),
+            at("2026-02-18T00:00:00Z"),
+        )
+    }
+
+    #[test]
+    fn groups_by_project_and_day() {
+        let report = sample_report();
+        assert_eq!(report.sessions, 3);
+        assert_eq!(report.projects[0].project_dir_display, "~/dev/api");
+        assert_eq!(report.projects[0].active_seconds, 85 * 60);
+        let day = &report.projects[0].days[0];
+        assert_eq!(day.date, "2026-02-17".parse::<NaiveDate>().unwrap());
+        let ids: Vec<&str> = day.sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["s1", "s2"]);
+        assert_eq!(report.projects[1].project_dir_display, "~/dev/web");
+
+        // Days are local: 23:40Z is the next morning in Tokyo.
+        let web = session("s3", "/w");
+        let digest = sample_report().projects[1].days[0].sessions[0].clone();
+        let tokyo = group(
+            [(&web, digest)],
+            Zone::parse("+09:00").unwrap(),
+            at("2026-02-11T00:00:00Z"),
+            at("2026-02-18T00:00:00Z"),
+        );
+        assert_eq!(
+            tokyo.projects[0].days[0].date,
+            "2026-02-17".parse::<NaiveDate>().unwrap()
+        );
+    }
+
+    #[test]
+    fn formats_text() {
+        let out = format_report(&sample_report(), Zone::Utc);
+        assert_eq!(
+            out,
+            "Report from 2026-02-11 to 2026-02-18: 3 sessions in 2 projects\n\
+             \n\
+             ~/dev/api  (1h 25m)\n\
+             \x20 Tue 2026-02-17\n\
+             \x20   Add cursor pagination  (09:00, 1h 5m, 4 messages)\n\
+             \x20     edited: src/users.rs, tests/users.rs\n\
+             \x20     commit 1a2b3c4 Add cursor pagination\n\
+             \x20   Fix *flaky* test  (14:00, 20m, 4 messages)\n\
+             \n\
+             ~/dev/web  (<1m)\n\
+             \x20 Mon 2026-02-16\n\
+             \x20   Add cursor pagination  (23:30, <1m, 4 messages)\n"
+        );
+    }
+
+    #[test]
+    fn formats_markdown() {
+        let out = format_report_markdown(&sample_report(), Zone::Utc);
+        assert!(out.starts_with("# Report: 2026-02-11 to 2026-02-18\n\n3 sessions in 2 projects.\n\n## ~/dev/api (1h 25m)\n\n### Tue 2026-02-17\n\n"));
+        assert!(out.contains(
+            "- **Add cursor pagination** (09:00, 1h 5m, 4 messages)\n  - Edited: `src/users.rs`, `tests/users.rs`\n  - Commit `1a2b3c4` Add cursor pagination\n"
+        ));
+        assert!(out.contains("- **Fix \\*flaky\\* test** (14:00, 20m, 4 messages)\n"));
+        assert!(out.contains("\n## ~/dev/web (<1m)\n\n### Mon 2026-02-16\n"));
+    }
+
+    #[test]
+    fn empty_report() {
+        let report = group(
+            [],
+            Zone::Utc,
+            at("2026-02-11T00:00:00Z"),
+            at("2026-02-18T00:00:00Z"),
+        );
+        assert_eq!(
+            format_report(&report, Zone::Utc),
+            "No sessions between 2026-02-11 and 2026-02-18.\n"
+        );
+        assert_eq!(
+            format_report_markdown(&report, Zone::Utc),
+            "# Report: 2026-02-11 to 2026-02-18\n\nNo sessions.\n"
+        );
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(0), "<1m");
+        assert_eq!(format_duration(59), "<1m");
+        assert_eq!(format_duration(45 * 60), "45m");
+        assert_eq!(format_duration(125 * 60), "2h 5m");
+    }
+}
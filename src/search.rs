@@ -0,0 +1,218 @@
+use crate::transcript;
+use crate::types::SessionInfo;
+
+/// Characters of context kept on each side of a match in a snippet.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// A session matching a search query.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    /// Position of the session in the listing that was searched.
+    pub index: usize,
+    pub session: SessionInfo,
+    /// Excerpts around matches, in transcript order.
+    pub snippets: Vec<String>,
+}
+
+/// Case-insensitive search over each session's transcript and metadata
+/// (first prompt, slug, project dir). Every whitespace-separated term in
+/// `query` must occur somewhere in the session. Returns hits in listing order
+/// with at most `max_snippets` excerpts each; unreadable sessions are skipped.
+pub fn search_sessions(
+    sessions: &[SessionInfo],
+    query: &str,
+    max_snippets: usize,
+) -> Vec<SearchHit> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if terms.is_empty() {
+        return vec![];
+    }
+
+    let mut hits = Vec::new();
+    for (index, session) in sessions.iter().enumerate() {
+        let Ok(messages) = transcript::read_transcript(&session.path) else {
+            continue;
+        };
+
+        let texts = [
+            session.first_prompt.as_deref(),
+            session.slug.as_deref(),
+            Some(session.project_dir_display.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(messages.iter().map(|m| m.text.as_str()));
+
+        let mut found = vec![false; terms.len()];
+        let mut snippets = Vec::new();
+        for text in texts {
+            let folded = FoldedText::new(text);
+            let mut snippet_taken = false;
+            for (term, found) in terms.iter().zip(found.iter_mut()) {
+                let Some(offset) = folded.find(term) else {
+                    continue;
+                };
+                *found = true;
+                if !snippet_taken && snippets.len() < max_snippets {
+                    let snippet = snippet_around(text, offset, term.chars().count());
+                    if !snippets.contains(&snippet) {
+                        snippets.push(snippet);
+                    }
+                    snippet_taken = true;
+                }
+            }
+        }
+
+        if found.iter().all(|f| *f) {
+            hits.push(SearchHit {
+                index,
+                session: session.clone(),
+                snippets,
+            });
+        }
+    }
+    hits
+}
+
+/// A lowercased copy of a string that can map match offsets back to the
+/// original (lowercasing can change byte lengths, e.g. `İ`).
+struct FoldedText {
+    lower: String,
+    /// Original byte offset for each byte of `lower`.
+    origin: Vec<usize>,
+}
+
+impl FoldedText {
+    fn new(text: &str) -> Self {
+        let mut lower = String::with_capacity(text.len());
+        let mut origin = Vec::with_capacity(text.len());
+        for (i, c) in text.char_indices() {
+            for lc in c.to_lowercase() {
+                lower.push(lc);
+                origin.extend(std::iter::repeat_n(i, lc.len_utf8()));
+            }
+        }
+        FoldedText { lower, origin }
+    }
+
+    /// Byte offset in the original text of the first match of a lowercase `needle`.
+    fn find(&self, needle: &str) -> Option<usize> {
+        self.lower.find(needle).map(|pos| self.origin[pos])
+    }
+}
+
+/// Up to `SNIPPET_CONTEXT` characters either side of the `match_chars`-long
+/// match at byte `offset`, whitespace collapsed, with `…` marking truncation.
+fn snippet_around(text: &str, offset: usize, match_chars: usize) -> String {
+    let before: Vec<(usize, char)> = text[..offset].char_indices().collect();
+    let start = before
+        .len()
+        .checked_sub(SNIPPET_CONTEXT)
+        .map_or(0, |i| before[i].0);
+
+    let match_end = text[offset..]
+        .char_indices()
+        .nth(match_chars)
+        .map_or(text.len(), |(i, _)| offset + i);
+    let end = text[match_end..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(text.len(), |(i, _)| match_end + i);
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('\u{2026}');
+    }
+    snippet.push_str(
+        &text[start..end]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    if end < text.len() {
+        snippet.push('\u{2026}');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture_session(name: &str, id: &str) -> SessionInfo {
+        SessionInfo {
+            path: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(name),
+            project_dir_display: "~/dev/myproject".to_string(),
+            ..SessionInfo::fixture(id, "/Users/testuser/dev/myproject")
+        }
+    }
+
+    #[test]
+    fn finds_term_in_transcript_case_insensitively() {
+        let sessions = vec![
+            fixture_session("no_cwd.jsonl", "a"),
+            fixture_session("normal.jsonl", "b"),
+        ];
+        let hits = search_sessions(&sessions, "DISCOVERY", 3);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].index, 1);
+        assert_eq!(hits[0].session.session_id, "b");
+        assert!(hits[0].snippets[0].contains("discovery"));
+    }
+
+    #[test]
+    fn all_terms_must_match() {
+        let sessions = vec![fixture_session("normal.jsonl", "b")];
+        assert_eq!(search_sessions(&sessions, "discovery read_dir", 3).len(), 1);
+        assert!(search_sessions(&sessions, "discovery pagination", 3).is_empty());
+    }
+
+    #[test]
+    fn matches_metadata() {
+        let mut session = fixture_session("normal.jsonl", "b");
+        session.slug = Some("woolly-conjuring-journal".to_string());
+        let hits = search_sessions(&[session], "conjuring", 3);
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn empty_query_matches_nothing() {
+        let sessions = vec![fixture_session("normal.jsonl", "b")];
+        assert!(search_sessions(&sessions, "   ", 3).is_empty());
+    }
+
+    #[test]
+    fn snippet_count_is_capped() {
+        let sessions = vec![fixture_session("normal.jsonl", "b")];
+        let hits = search_sessions(&sessions, "ccsesh", 1);
+        assert_eq!(hits[0].snippets.len(), 1);
+    }
+
+    #[test]
+    fn unreadable_sessions_are_skipped() {
+        let sessions = vec![fixture_session("does_not_exist.jsonl", "x")];
+        assert!(search_sessions(&sessions, "anything", 3).is_empty());
+    }
+
+    #[test]
+    fn snippet_truncates_long_text() {
+        let text = format!("{}needle{}", "a ".repeat(100), "b ".repeat(100));
+        let offset = text.find("needle").unwrap();
+        let snippet = snippet_around(&text, offset, "needle".len());
+        assert!(snippet.starts_with('\u{2026}'));
+        assert!(snippet.ends_with('\u{2026}'));
+        assert!(snippet.contains("needle"));
+        assert!(snippet.chars().count() < 140);
+    }
+
+    #[test]
+    fn folded_text_maps_offsets_for_multibyte_lowercase() {
+        // 'İ' lowercases to two chars ("i̇"), shifting byte offsets.
+        let folded = FoldedText::new("İstanbul needle");
+        let offset = folded.find("needle").unwrap();
+        assert_eq!(&"İstanbul needle"[offset..], "needle");
+    }
+}
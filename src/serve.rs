@@ -0,0 +1,744 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::display::{self, JsonExport, JsonSearchHit, JsonSession};
+use crate::errors::CcseshError;
+use crate::files;
+use crate::search;
+use crate::sessions;
+use crate::shell::ResumeTemplate;
+use crate::transcript;
+
+/// Address `ccsesh serve` listens on when `--listen` is not given.
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:7420";
+
+/// Upper bound on the request line plus headers; bodies are never read.
+const MAX_HEAD_BYTES: u64 = 16 * 1024;
+
+/// How long a connection may stall while sending its request or reading the
+/// response before it is dropped.
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections handled at once. Further ones wait in the listen backlog until
+/// a worker is free, so idle clients cannot pile up threads or descriptors.
+const WORKERS: usize = 16;
+
+/// Excerpts returned per search hit.
+const SEARCH_SNIPPETS: usize = 3;
+
+/// Everything a request handler needs; shared across worker threads.
+pub struct ServeContext {
+    pub home_dir: String,
+    /// `limit` used by `/sessions` and `/search` when the query omits it.
+    pub default_limit: usize,
+    /// Required `Authorization: Bearer` token, if any.
+    pub token: Option<String>,
+    pub template: ResumeTemplate,
+    pub args: Vec<String>,
+}
+
+/// A parsed HTTP request head.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Percent-decoded query parameters in order of appearance.
+    pub query: Vec<(String, String)>,
+    /// Headers with lowercased names.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(value: &T) -> Response {
+        match serde_json::to_string_pretty(value) {
+            Ok(body) => Response {
+                status: 200,
+                content_type: "application/json",
+                body,
+            },
+            Err(e) => Response::error(500, &e.to_string()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+
+    fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            self.content_type,
+            self.body.len(),
+            self.body
+        )?;
+        out.flush()
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Bind `listen` and serve requests until the process is killed.
+///
+/// Non-loopback addresses are refused unless a token is configured.
+pub fn serve(listen: &str, context: ServeContext) -> Result<()> {
+    let addrs: Vec<_> = listen
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid listen address '{}'", listen))?
+        .collect();
+    if context.token.is_none() && !addrs.iter().all(|a| a.ip().is_loopback()) {
+        return Err(CcseshError::UnsafeListenAddress {
+            addr: listen.to_string(),
+        }
+        .into());
+    }
+
+    let listener =
+        TcpListener::bind(&addrs[..]).with_context(|| format!("Failed to listen on {}", listen))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    run(&listener, context, WORKERS);
+    Ok(())
+}
+
+/// Accept connections and hand each to one of `workers` threads. The channel
+/// has no buffer, so nothing is accepted while every worker is busy.
+fn run(listener: &TcpListener, context: ServeContext, workers: usize) {
+    let context = Arc::new(context);
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(0);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let context = Arc::clone(&context);
+        let receiver = Arc::clone(&receiver);
+        std::thread::spawn(move || {
+            loop {
+                let Ok(Ok(stream)) = receiver.lock().map(|r| r.recv()) else {
+                    return;
+                };
+                handle_connection(stream, &context);
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        if sender.send(stream).is_err() {
+            break;
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, context: &ServeContext) {
+    let _ = stream.set_read_timeout(Some(IO_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+    let response = match stream.try_clone() {
+        Ok(read_half) => {
+            let mut reader = BufReader::new(read_half.take(MAX_HEAD_BYTES));
+            match read_request(&mut reader) {
+                Some(request) => handle(context, &request),
+                None => Response::error(400, "Malformed request"),
+            }
+        }
+        Err(e) => Response::error(500, &e.to_string()),
+    };
+    let _ = response.write_to(&mut stream);
+}
+
+/// Read a request line and headers. Returns `None` if the head is malformed,
+/// truncated, or larger than `MAX_HEAD_BYTES`.
+pub fn read_request(reader: &mut impl BufRead) -> Option<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':')?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(k), percent_decode(v))
+        })
+        .collect();
+
+    Some(Request {
+        method,
+        path: percent_decode(path),
+        query,
+        headers,
+    })
+}
+
+/// Decode `%XX` escapes and `+` (as space). Invalid escapes are kept verbatim.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("00");
+                out.push(u8::from_str_radix(hex, 16).unwrap_or(0));
+                i += 3;
+                continue;
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Compare tokens without short-circuiting on the first differing byte.
+fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Without a token the server relies on being reachable only from this
+/// machine, so reject requests whose `Host` is not a loopback name. This stops
+/// web pages from reaching the API through DNS rebinding.
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(""),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    name.eq_ignore_ascii_case("localhost")
+        || name
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Route a request to its endpoint.
+pub fn handle(context: &ServeContext, request: &Request) -> Response {
+    if request.method != "GET" {
+        return Response::error(405, "Only GET is supported");
+    }
+
+    match &context.token {
+        Some(token) => {
+            let given = request
+                .header("authorization")
+                .and_then(|v| v.strip_prefix("Bearer "));
+            if !given.is_some_and(|g| tokens_match(token, g)) {
+                return Response::error(401, "Missing or invalid bearer token");
+            }
+        }
+        None => {
+            if !request.header("host").is_some_and(is_loopback_host) {
+                return Response::error(403, "Host header must name a loopback address");
+            }
+        }
+    }
+
+    let segments: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
+    let result = match segments.as_slice() {
+        ["sessions"] => list_sessions(context, request),
+        ["sessions", id] => get_session(context, id),
+        ["sessions", id, "transcript"] => get_transcript(context, id, request),
+        ["search"] => search(context, request),
+        _ => return Response::error(404, "Not found"),
+    };
+
+    result.unwrap_or_else(|e| match e.downcast_ref() {
+        Some(CcseshError::SessionNotFound { .. }) => Response::error(404, &e.to_string()),
+        _ => Response::error(500, &e.to_string()),
+    })
+}
+
+fn limit_param(context: &ServeContext, request: &Request) -> Result<usize, Response> {
+    match request.query_param("limit") {
+        None => Ok(context.default_limit),
+        Some(v) => v
+            .parse()
+            .map_err(|_| Response::error(400, "limit must be a non-negative integer")),
+    }
+}
+
+/// `name=true` or `name=false` (a bare `name` counts as true).
+fn flag_param(request: &Request, name: &str) -> Result<bool, Response> {
+    match request.query_param(name) {
+        None | Some("false") => Ok(false),
+        Some("" | "true") => Ok(true),
+        Some(_) => Err(Response::error(
+            400,
+            &format!("{} must be true or false", name),
+        )),
+    }
+}
+
+/// Sessions for listing, treating "no sessions" as an empty list. With
+/// `expand`, chain members and duplicates get entries of their own, as with
+/// `--expand`.
+fn load_or_empty(
+    context: &ServeContext,
+    limit: usize,
+    expand: bool,
+) -> Result<Vec<crate::types::SessionInfo>> {
+    let loaded = if expand {
+        sessions::load_expanded_sessions(&context.home_dir, limit)
+    } else {
+        sessions::load_sessions(&context.home_dir, limit)
+    };
+    match loaded {
+        Err(e) if matches!(e.downcast_ref(), Some(CcseshError::NoSessionsFound)) => Ok(vec![]),
+        other => other,
+    }
+}
+
+fn list_sessions(context: &ServeContext, request: &Request) -> Result<Response> {
+    let limit = match limit_param(context, request) {
+        Ok(l) => l,
+        Err(response) => return Ok(response),
+    };
+    let expand = match flag_param(request, "expand") {
+        Ok(e) => e,
+        Err(response) => return Ok(response),
+    };
+    let now = Utc::now();
+    let sessions: Vec<JsonSession> = load_or_empty(context, limit, expand)?
+        .iter()
+        .enumerate()
+        .map(|(i, s)| display::json_session(i, s, now, &context.template, &context.args))
+        .collect();
+    Ok(Response::json(&sessions))
+}
+
+fn get_session(context: &ServeContext, id: &str) -> Result<Response> {
+    let (index, session) = sessions::find_session(&context.home_dir, id)?;
+    Ok(Response::json(&display::json_session(
+        index,
+        &session,
+        Utc::now(),
+        &context.template,
+        &context.args,
+    )))
+}
+
+fn get_transcript(context: &ServeContext, id: &str, request: &Request) -> Result<Response> {
+    let branches = match flag_param(request, "branches") {
+        Ok(b) => b,
+        Err(response) => return Ok(response),
+    };
+    let (index, session) = sessions::find_session(&context.home_dir, id)?;
+    let (messages, thread_branches) = transcript::read_thread(&session.path)?;
+
+    match request.query_param("format") {
+        Some("markdown") => {
+            let mut body = transcript::render_markdown(&messages);
+            if branches {
+                body.push_str(&transcript::render_branches_markdown(&thread_branches));
+            }
+            Ok(Response {
+                status: 200,
+                content_type: "text/markdown",
+                body,
+            })
+        }
+        None | Some("json") => Ok(Response::json(&JsonExport {
+            session: display::json_session(
+                index,
+                &session,
+                Utc::now(),
+                &context.template,
+                &context.args,
+            ),
+            messages: &messages,
+            files_touched: &files::read_files_touched(&session.path)?,
+            branches: branches.then_some(thread_branches.as_slice()),
+        })),
+        Some(_) => Ok(Response::error(400, "format must be json or markdown")),
+    }
+}
+
+fn search(context: &ServeContext, request: &Request) -> Result<Response> {
+    let Some(query) = request.query_param("q") else {
+        return Ok(Response::error(400, "Missing q parameter"));
+    };
+    let limit = match limit_param(context, request) {
+        Ok(l) => l,
+        Err(response) => return Ok(response),
+    };
+
+    let now = Utc::now();
+    let all = load_or_empty(context, usize::MAX, false)?;
+    let results: Vec<JsonSearchHit> = search::search_sessions(&all, query, SEARCH_SNIPPETS)
+        .iter()
+        .take(limit)
+        .map(|hit| display::json_search_hit(hit, now, &context.template, &context.args))
+        .collect();
+    Ok(Response::json(&results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    const UUID_NORMAL: &str = "eb53d999-8692-42ce-a376-4f82206a086d";
+    const UUID_OTHER: &str = "ab53d999-8692-42ce-a376-4f82206a086d";
+
+    /// Copy a fixture into `dir` as session `id`, last modified `age` seconds ago.
+    fn copy_fixture(dir: &Path, fixture: &str, id: &str, age: u64) {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        fs::create_dir_all(dir).unwrap();
+        let dest = dir.join(format!("{}.jsonl", id));
+        fs::copy(fixtures.join(fixture), &dest).unwrap();
+        let mtime = std::time::SystemTime::now() - Duration::from_secs(age);
+        fs::File::options()
+            .write(true)
+            .open(&dest)
+            .unwrap()
+            .set_times(fs::FileTimes::new().set_modified(mtime))
+            .unwrap();
+    }
+
+    fn setup_home() -> assert_fs::TempDir {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let projects = tmp.path().join(".claude/projects");
+        copy_fixture(&projects.join("-a"), "normal.jsonl", UUID_NORMAL, 10);
+        copy_fixture(&projects.join("-b"), "no_cwd.jsonl", UUID_OTHER, 100);
+        tmp
+    }
+
+    fn context(home: &Path, token: Option<&str>) -> ServeContext {
+        ServeContext {
+            home_dir: home.to_str().unwrap().to_string(),
+            default_limit: 5,
+            token: token.map(str::to_string),
+            template: ResumeTemplate::default(),
+            args: vec![],
+        }
+    }
+
+    fn get(target: &str) -> Request {
+        let raw = format!("GET {} HTTP/1.1\r\nHost: 127.0.0.1:7420\r\n\r\n", target);
+        read_request(&mut raw.as_bytes()).unwrap()
+    }
+
+    fn body_json(response: &Response) -> serde_json::Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn read_request_parses_head_and_query() {
+        let raw = "GET /search?q=hello+w%C3%B6rld&limit=2 HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer x\r\n\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/search");
+        assert_eq!(request.query_param("q"), Some("hello wörld"));
+        assert_eq!(request.query_param("limit"), Some("2"));
+        assert_eq!(request.header("authorization"), Some("Bearer x"));
+    }
+
+    #[test]
+    fn read_request_rejects_garbage_and_truncation() {
+        assert!(read_request(&mut "nonsense\r\n\r\n".as_bytes()).is_none());
+        assert!(read_request(&mut "GET / HTTP/1.1\r\nHost: x\r\n".as_bytes()).is_none());
+    }
+
+    #[test]
+    fn percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode("a%2Fb"), "a/b");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+        assert_eq!(percent_decode("%+1"), "% 1");
+    }
+
+    #[test]
+    fn loopback_host_names() {
+        assert!(is_loopback_host("localhost:7420"));
+        assert!(is_loopback_host("127.0.0.1"));
+        assert!(is_loopback_host("[::1]:7420"));
+        assert!(!is_loopback_host("evil.example.com:7420"));
+        assert!(!is_loopback_host("192.168.1.5:7420"));
+    }
+
+    #[test]
+    fn list_sessions_returns_json_schema() {
+        let home = setup_home();
+        let response = handle(&context(home.path(), None), &get("/sessions"));
+        assert_eq!(response.status, 200);
+        let body = body_json(&response);
+        assert_eq!(body.as_array().unwrap().len(), 2);
+        assert_eq!(body[0]["session_id"], UUID_NORMAL);
+        assert_eq!(body[1]["index"], 1);
+        assert!(
+            body[0]["resume_command"]
+                .as_str()
+                .unwrap()
+                .contains(UUID_NORMAL)
+        );
+    }
+
+    #[test]
+    fn list_sessions_respects_limit() {
+        let home = setup_home();
+        let ctx = context(home.path(), None);
+        let body = body_json(&handle(&ctx, &get("/sessions?limit=1")));
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(handle(&ctx, &get("/sessions?limit=x")).status, 400);
+    }
+
+    #[test]
+    fn list_sessions_expands_chains() {
+        let home = setup_home();
+        let dir = home.path().join(".claude/projects/-haiku");
+        copy_fixture(
+            &dir,
+            "continued.jsonl",
+            "9c53d999-8692-42ce-a376-4f82206a086d",
+            5,
+        );
+        copy_fixture(
+            &dir,
+            "branching.jsonl",
+            "9b53d999-8692-42ce-a376-4f82206a086d",
+            3600,
+        );
+        let ctx = context(home.path(), None);
+
+        let collapsed = body_json(&handle(&ctx, &get("/sessions")));
+        assert_eq!(collapsed.as_array().unwrap().len(), 3);
+        let expanded = body_json(&handle(&ctx, &get("/sessions?expand=true")));
+        assert_eq!(expanded.as_array().unwrap().len(), 4);
+        assert_eq!(
+            expanded[1]["session_id"],
+            "9b53d999-8692-42ce-a376-4f82206a086d"
+        );
+        assert_eq!(body_json(&handle(&ctx, &get("/sessions?expand"))), expanded);
+        assert_eq!(handle(&ctx, &get("/sessions?expand=yes")).status, 400);
+    }
+
+    #[test]
+    fn get_session_by_id_keeps_listing_index() {
+        let home = setup_home();
+        let response = handle(
+            &context(home.path(), None),
+            &get(&format!("/sessions/{}", UUID_OTHER)),
+        );
+        assert_eq!(response.status, 200);
+        let body = body_json(&response);
+        assert_eq!(body["index"], 1);
+        assert_eq!(body["slug"], "deep-ocean-wave");
+    }
+
+    #[test]
+    fn unknown_session_is_404() {
+        let home = setup_home();
+        let response = handle(&context(home.path(), None), &get("/sessions/nope"));
+        assert_eq!(response.status, 404);
+        assert!(
+            body_json(&response)["error"]
+                .as_str()
+                .unwrap()
+                .contains("nope")
+        );
+    }
+
+    #[test]
+    fn transcript_json_and_markdown() {
+        let home = setup_home();
+        let ctx = context(home.path(), None);
+        let path = format!("/sessions/{}/transcript", UUID_NORMAL);
+
+        let body = body_json(&handle(&ctx, &get(&path)));
+        assert_eq!(body["session"]["session_id"], UUID_NORMAL);
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(
+            body["messages"][0]["text"],
+            "Design technical approach for ccsesh"
+        );
+        assert_eq!(body["files_touched"], serde_json::json!([]));
+
+        let markdown = handle(&ctx, &get(&format!("{}?format=markdown", path)));
+        assert_eq!(markdown.content_type, "text/markdown");
+        assert!(markdown.body.starts_with("## User"));
+    }
+
+    #[test]
+    fn transcript_follows_main_thread_like_show() {
+        let home = setup_home();
+        let id = "9b53d999-8692-42ce-a376-4f82206a086d";
+        copy_fixture(
+            &home.path().join(".claude/projects/-haiku"),
+            "branching.jsonl",
+            id,
+            5,
+        );
+        let ctx = context(home.path(), None);
+        let path = format!("/sessions/{}/transcript", id);
+        let abandoned = "Borrowed, never owned";
+
+        let body = body_json(&handle(&ctx, &get(&path)));
+        assert_eq!(body["messages"][0]["text"], "Write a haiku about Rust");
+        assert!(!body.to_string().contains(abandoned));
+        assert!(body.get("branches").is_none());
+        let markdown = handle(&ctx, &get(&format!("{}?format=markdown", path)));
+        assert!(!markdown.body.contains(abandoned));
+
+        let body = body_json(&handle(&ctx, &get(&format!("{}?branches=true", path))));
+        assert_eq!(body["branches"][0]["kind"], "abandoned");
+        assert!(body["branches"].to_string().contains(abandoned));
+        let markdown = handle(
+            &ctx,
+            &get(&format!("{}?format=markdown&branches=true", path)),
+        );
+        assert!(markdown.body.contains(abandoned));
+        assert_eq!(
+            handle(&ctx, &get(&format!("{}?branches=1", path))).status,
+            400
+        );
+    }
+
+    #[test]
+    fn search_returns_hits_with_snippets() {
+        let home = setup_home();
+        let ctx = context(home.path(), None);
+        let body = body_json(&handle(&ctx, &get("/search?q=Future+trait")));
+        let hits = body.as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["session"]["session_id"], UUID_OTHER);
+        assert_eq!(hits[0]["session"]["index"], 1);
+        assert!(hits[0]["snippets"][0].as_str().unwrap().contains("Future"));
+
+        assert_eq!(handle(&ctx, &get("/search")).status, 400);
+    }
+
+    #[test]
+    fn token_required_when_configured() {
+        let home = setup_home();
+        let ctx = context(home.path(), Some("s3cret"));
+        assert_eq!(handle(&ctx, &get("/sessions")).status, 401);
+
+        let raw = "GET /sessions HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(handle(&ctx, &request).status, 200);
+    }
+
+    #[test]
+    fn non_loopback_host_rejected_without_token() {
+        let home = setup_home();
+        let raw = "GET /sessions HTTP/1.1\r\nHost: attacker.example:7420\r\n\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(handle(&context(home.path(), None), &request).status, 403);
+    }
+
+    #[test]
+    fn other_methods_and_paths() {
+        let home = setup_home();
+        let ctx = context(home.path(), None);
+        let raw = "POST /sessions HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(handle(&ctx, &request).status, 405);
+        assert_eq!(handle(&ctx, &get("/nope")).status, 404);
+    }
+
+    #[test]
+    fn busy_workers_hold_back_new_connections() {
+        let home = setup_home();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let ctx = context(home.path(), None);
+        std::thread::spawn(move || run(&listener, ctx, 1));
+
+        let idle = TcpStream::connect(addr).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let mut waiting = TcpStream::connect(addr).unwrap();
+        waiting
+            .write_all(b"GET /sessions HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+            .unwrap();
+        waiting
+            .set_read_timeout(Some(Duration::from_millis(300)))
+            .unwrap();
+        let mut buf = [0; 1];
+        assert!(waiting.read(&mut buf).is_err());
+
+        drop(idle);
+        waiting.set_read_timeout(Some(IO_TIMEOUT)).unwrap();
+        let mut response = String::new();
+        waiting.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn serve_refuses_public_address_without_token() {
+        let home = setup_home();
+        let err = serve("0.0.0.0:0", context(home.path(), None)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(CcseshError::UnsafeListenAddress { .. })
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::active;
+use crate::chain::{self, ChainLinker};
+use crate::dedupe::{self, Collapser};
+use crate::discover;
+use crate::errors::CcseshError;
+use crate::parse;
+use crate::types::{SessionCandidate, SessionInfo};
+
+/// Discover, parse, and filter sessions. Returns up to `limit` valid sessions
+/// (excludes team subagent sessions and empty sessions with no prompt or slug).
+/// Sessions that continue one another are collapsed into one entry for the
+/// newest, with the older ones in its `earlier` list. Entries in the same
+/// project whose first prompts match are then collapsed the same way, into
+/// `duplicates`, and `limit` counts what remains.
+pub fn load_sessions(home_dir: &str, limit: usize) -> Result<Vec<SessionInfo>> {
+    load_sessions_with(home_dir, limit, |candidate| {
+        parse::parse_session(candidate, home_dir).ok()
+    })
+}
+
+/// Same pipeline as `load_sessions`, but each candidate is turned into a
+/// `SessionInfo` by `parse` (returning `None` for subagent sessions and parse
+/// errors). Lets callers such as watch mode reuse previously parsed results.
+pub fn load_sessions_with(
+    home_dir: &str,
+    limit: usize,
+    mut parse: impl FnMut(&SessionCandidate) -> Option<SessionInfo>,
+) -> Result<Vec<SessionInfo>> {
+    if limit == 0 {
+        return Ok(vec![]);
+    }
+
+    // Over-discover to compensate for filtered subagent/empty sessions
+    let discover_limit = limit.saturating_mul(5).max(50);
+    let candidates = discover::discover_sessions(home_dir, discover_limit)?;
+
+    if candidates.is_empty() {
+        return Err(CcseshError::NoSessionsFound.into());
+    }
+
+    // Once `limit` rows are found, keep going only to pick up older members
+    // of their chains. A chain duplicating a newer one is not a row.
+    let mut linker = ChainLinker::default();
+    let mut collapser = Collapser::default();
+    for candidate in &candidates {
+        let full = collapser.row_count() >= limit;
+        if full && !linker.has_pending() {
+            break;
+        }
+        match parse(candidate) {
+            Some(info) => {
+                // Skip empty sessions (no prompt and no slug)
+                if info.first_prompt.is_none() && info.slug.is_none() {
+                    continue;
+                }
+                let chains = linker.chain_count();
+                let (project_dir, prompt) = (info.project_dir.clone(), info.first_prompt.clone());
+                linker.push(info, !full);
+                if linker.chain_count() > chains {
+                    collapser.add(&project_dir, prompt.as_deref());
+                }
+            }
+            None => continue, // Includes subagent sessions and parse errors
+        }
+    }
+
+    let mut sessions = dedupe::collapse(linker.finish());
+    if sessions.is_empty() {
+        return Err(CcseshError::NoSessionsFound.into());
+    }
+
+    active::detect_active(&mut sessions, Utc::now());
+
+    Ok(sessions)
+}
+
+/// Resolve a `show` / `export` target: a listing index, or otherwise a
+/// session ID. Returns the session's index in the listing alongside it.
+/// With `expand`, indices count every member of a continuation chain.
+pub fn resolve_session(home_dir: &str, target: &str, expand: bool) -> Result<(usize, SessionInfo)> {
+    let Ok(index) = target.parse::<usize>() else {
+        return find_session(home_dir, target);
+    };
+    let mut sessions = if expand {
+        load_expanded_sessions(home_dir, index.saturating_add(1))?
+    } else {
+        load_sessions(home_dir, index.saturating_add(1))?
+    };
+    if index >= sessions.len() {
+        let max = sessions.len() - 1;
+        return Err(CcseshError::IndexOutOfRange { index, max }.into());
+    }
+    Ok((index, sessions.swap_remove(index)))
+}
+
+/// Like `load_sessions`, but every duplicate is listed as its own entry, and
+/// every member of a continuation chain right after the newest one (see
+/// `chain::expand`).
+pub fn load_expanded_sessions(home_dir: &str, limit: usize) -> Result<Vec<SessionInfo>> {
+    let mut sessions = chain::expand(dedupe::expand(load_sessions(home_dir, limit)?));
+    // Re-run detection so chain members are checked too.
+    for session in &mut sessions {
+        session.active = false;
+        session.active_pid = None;
+        session.active_match = None;
+    }
+    active::detect_active(&mut sessions, Utc::now());
+    Ok(sessions)
+}
+
+/// Every parseable session on disk, newest first, without chain grouping or
+/// the empty-session filter. For commands that act on all transcripts
+/// (`scan-secrets`, `relocate`), where an older part of a chain matters as
+/// much as its head.
+pub fn load_all_sessions(home_dir: &str) -> Result<Vec<SessionInfo>> {
+    let mut sessions: Vec<SessionInfo> = discover::discover_sessions(home_dir, usize::MAX)?
+        .iter()
+        .filter_map(|candidate| parse::parse_session(candidate, home_dir).ok())
+        .collect();
+    active::detect_active(&mut sessions, Utc::now());
+    Ok(sessions)
+}
+
+/// Find a session by ID among all listable sessions, including older members
+/// of continuation chains and collapsed duplicates. Returns the index of its
+/// entry in the full listing alongside the session.
+pub fn find_session(home_dir: &str, session_id: &str) -> Result<(usize, SessionInfo)> {
+    let sessions = load_sessions(home_dir, usize::MAX)?;
+    sessions
+        .into_iter()
+        .enumerate()
+        .find_map(|(i, s)| {
+            if s.session_id == session_id {
+                return Some((i, s));
+            }
+            s.earlier
+                .into_iter()
+                .chain(
+                    s.duplicates
+                        .into_iter()
+                        .flat_map(|d| std::iter::once(d.clone()).chain(d.earlier)),
+                )
+                .find(|member| member.session_id == session_id)
+                .map(|member| (i, member))
+        })
+        .ok_or_else(|| {
+            CcseshError::SessionNotFound {
+                id: session_id.to_string(),
+            }
+            .into()
+        })
+}
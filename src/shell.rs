@@ -17,17 +17,151 @@ pub fn print_shell_init(shell: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// The resume command used when no template is configured. Matches the
+/// historical hard-coded `cd <dir> && claude --resume <id>` form.
+pub const DEFAULT_RESUME_TEMPLATE: &str = "cd {dir} && claude --resume {id} {args}";
+
+/// A validated resume command template.
+///
+/// Supported placeholders:
+/// - `{dir}` -- the project directory, single-quote escaped
+/// - `{id}` -- the session UUID (required)
+/// - `{args}` -- pass-through arguments, each single-quote escaped and space-joined
+///
+/// The rendered command is eval'd by the shell wrapper, so templates containing
+/// control characters or unknown placeholders are rejected up front.
+#[derive(Debug, Clone)]
+pub struct ResumeTemplate {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Dir,
+    Id,
+    Args,
+}
+
+impl ResumeTemplate {
+    pub fn parse(template: &str) -> Result<Self, CcseshError> {
+        let invalid = |reason: String| CcseshError::InvalidResumeTemplate { reason };
+
+        if template.chars().any(|c| c.is_control()) {
+            return Err(invalid("must not contain control characters".into()));
+        }
+
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for inner in chars.by_ref() {
+                        if inner == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(inner);
+                    }
+                    if !closed {
+                        return Err(invalid(format!("unclosed placeholder '{{{}'", name)));
+                    }
+                    let segment = match name.as_str() {
+                        "dir" => Segment::Dir,
+                        "id" => Segment::Id,
+                        "args" => Segment::Args,
+                        other => {
+                            return Err(invalid(format!(
+                                "unknown placeholder '{{{}}}' (expected {{dir}}, {{id}} or {{args}})",
+                                other
+                            )));
+                        }
+                    };
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(segment);
+                }
+                '}' => return Err(invalid("unmatched '}'".into())),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        if !segments.contains(&Segment::Id) {
+            return Err(invalid("must contain the {id} placeholder".into()));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Render the command for `session`. Does not validate the session ID;
+    /// callers that eval the result must check `is_valid_uuid` first.
+    pub fn render(&self, session: &SessionInfo, args: &[String]) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(s) => out.push_str(s),
+                Segment::Dir => out.push_str(&shell_escape_single_quote(
+                    &session.project_dir.to_string_lossy(),
+                )),
+                Segment::Id => out.push_str(&session.session_id),
+                Segment::Args => {
+                    let escaped: Vec<String> =
+                        args.iter().map(|a| shell_escape_single_quote(a)).collect();
+                    out.push_str(&escaped.join(" "));
+                }
+            }
+        }
+        out.trim_end().to_string()
+    }
+
+    /// Render the command as an argv for launchers that do not go through the
+    /// shell wrapper (e.g. a multiplexer window that already starts in the
+    /// project dir). The default template maps onto `claude --resume <id> <args>`
+    /// directly; custom templates are run via `sh -c`.
+    pub fn argv(&self, session: &SessionInfo, args: &[String]) -> Vec<String> {
+        if self.segments == Self::default().segments {
+            let mut argv = vec![
+                "claude".to_string(),
+                "--resume".to_string(),
+                session.session_id.clone(),
+            ];
+            argv.extend(args.iter().cloned());
+            argv
+        } else {
+            vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                self.render(session, args),
+            ]
+        }
+    }
+}
+
+impl Default for ResumeTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_RESUME_TEMPLATE).expect("default resume template is valid")
+    }
+}
+
 /// Outputs the __CCSESH_EXEC__ protocol for shell wrapper eval.
-pub fn print_exec_protocol(session: &SessionInfo) -> anyhow::Result<()> {
+pub fn print_exec_protocol(
+    session: &SessionInfo,
+    template: &ResumeTemplate,
+    args: &[String],
+) -> anyhow::Result<()> {
     if !is_valid_uuid(&session.session_id) {
         anyhow::bail!("Invalid session ID: {}", session.session_id);
     }
-    let escaped_dir = shell_escape_single_quote(&session.project_dir.to_string_lossy());
     println!("__CCSESH_EXEC__");
-    println!(
-        "cd {} && claude --resume {}",
-        escaped_dir, session.session_id
-    );
+    println!("{}", template.render(session, args));
     Ok(())
 }
 
@@ -35,17 +169,24 @@ pub fn print_exec_protocol(session: &SessionInfo) -> anyhow::Result<()> {
 ///
 /// Uses `session.project_dir` (the full path) rather than `project_dir_display`
 /// because tilde expansion does not occur inside single-quoted strings.
-pub fn format_resume_instructions(session: &SessionInfo) -> String {
-    let escaped_dir = shell_escape_single_quote(&session.project_dir.to_string_lossy());
+pub fn format_resume_instructions(
+    session: &SessionInfo,
+    template: &ResumeTemplate,
+    args: &[String],
+) -> String {
     format!(
-        "To resume this session, run:\n  cd {} && claude --resume {}",
-        escaped_dir, session.session_id
+        "To resume this session, run:\n  {}",
+        template.render(session, args)
     )
 }
 
 /// Prints human-readable resume instructions (fallback when --shell-mode is not set).
-pub fn print_resume_instructions(session: &SessionInfo) {
-    println!("{}", format_resume_instructions(session));
+pub fn print_resume_instructions(
+    session: &SessionInfo,
+    template: &ResumeTemplate,
+    args: &[String],
+) {
+    println!("{}", format_resume_instructions(session, template, args));
 }
 
 // SECURITY: Duplicated from parse.rs. Must remain identical in behavior.
@@ -125,18 +266,14 @@ const ZSH_TEMPLATE: &str = r#"ccsesh() {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
     use std::path::PathBuf;
 
     fn make_session(session_id: &str, project_dir: &str, display: &str) -> SessionInfo {
         SessionInfo {
-            session_id: session_id.to_string(),
-            path: PathBuf::from("/tmp/test.jsonl"),
-            project_dir: PathBuf::from(project_dir),
             project_dir_display: display.to_string(),
-            last_active: Utc::now(),
             first_prompt: Some("test prompt".to_string()),
-            slug: None,
+            path: PathBuf::from("/tmp/test.jsonl"),
+            ..SessionInfo::fixture(session_id, project_dir)
         }
     }
 
@@ -218,13 +355,13 @@ mod tests {
             "/home/user/project",
             "~/project",
         );
-        assert!(print_exec_protocol(&session).is_ok());
+        assert!(print_exec_protocol(&session, &ResumeTemplate::default(), &[]).is_ok());
     }
 
     #[test]
     fn test_exec_protocol_invalid_uuid() {
         let session = make_session("not-a-uuid", "/home/user/project", "~/project");
-        assert!(print_exec_protocol(&session).is_err());
+        assert!(print_exec_protocol(&session, &ResumeTemplate::default(), &[]).is_err());
     }
 
     #[test]
@@ -234,7 +371,7 @@ mod tests {
             "/home/user/my project",
             "~/my project",
         );
-        assert!(print_exec_protocol(&session).is_ok());
+        assert!(print_exec_protocol(&session, &ResumeTemplate::default(), &[]).is_ok());
     }
 
     #[test]
@@ -244,7 +381,7 @@ mod tests {
             "/tmp/it's here",
             "~/it's here",
         );
-        assert!(print_exec_protocol(&session).is_ok());
+        assert!(print_exec_protocol(&session, &ResumeTemplate::default(), &[]).is_ok());
     }
 
     #[test]
@@ -254,7 +391,7 @@ mod tests {
             "/home/user/project",
             "~/project",
         );
-        let output = format_resume_instructions(&session);
+        let output = format_resume_instructions(&session, &ResumeTemplate::default(), &[]);
         // Must use the full path, not the tilde-abbreviated display path,
         // because tilde expansion does not occur inside single quotes.
         assert!(
@@ -275,7 +412,7 @@ mod tests {
             "/home/user/my project",
             "~/my project",
         );
-        let output = format_resume_instructions(&session);
+        let output = format_resume_instructions(&session, &ResumeTemplate::default(), &[]);
         // Path with spaces must be wrapped in single quotes
         assert!(
             output.contains("'/home/user/my project'"),
@@ -290,7 +427,7 @@ mod tests {
             "/tmp/it's here",
             "~/it's here",
         );
-        let output = format_resume_instructions(&session);
+        let output = format_resume_instructions(&session, &ResumeTemplate::default(), &[]);
         // Internal single quotes must be escaped as '\''
         assert!(
             output.contains("'\\''"),
@@ -298,4 +435,138 @@ mod tests {
         );
         assert!(output.contains("/tmp/it"));
     }
+
+    #[test]
+    fn test_default_template_matches_legacy_command() {
+        let session = make_session(
+            "eb53d999-8692-42ce-a376-4f82206a086d",
+            "/home/user/project",
+            "~/project",
+        );
+        assert_eq!(
+            ResumeTemplate::default().render(&session, &[]),
+            "cd '/home/user/project' && claude --resume eb53d999-8692-42ce-a376-4f82206a086d"
+        );
+    }
+
+    #[test]
+    fn test_template_args_are_escaped() {
+        let session = make_session(
+            "eb53d999-8692-42ce-a376-4f82206a086d",
+            "/home/user/project",
+            "~/project",
+        );
+        let args = vec![
+            "--model".to_string(),
+            "opus".to_string(),
+            "it's; rm -rf ~".to_string(),
+        ];
+        let cmd = ResumeTemplate::default().render(&session, &args);
+        assert!(cmd.ends_with(
+            "--resume eb53d999-8692-42ce-a376-4f82206a086d '--model' 'opus' 'it'\\''s; rm -rf ~'"
+        ));
+    }
+
+    #[test]
+    fn test_template_args_strip_newlines() {
+        let session = make_session(
+            "eb53d999-8692-42ce-a376-4f82206a086d",
+            "/home/user/project",
+            "~/project",
+        );
+        let args = vec!["--model\nopus".to_string()];
+        let cmd = ResumeTemplate::default().render(&session, &args);
+        assert!(!cmd.contains('\n'));
+        assert!(cmd.ends_with("'--modelopus'"));
+    }
+
+    #[test]
+    fn test_custom_template_wrapper_binary() {
+        let session = make_session(
+            "eb53d999-8692-42ce-a376-4f82206a086d",
+            "/tmp/it's here",
+            "~/it's here",
+        );
+        let template =
+            ResumeTemplate::parse("cd {dir} && npx @anthropic-ai/claude-code -r {id} {args}")
+                .unwrap();
+        let cmd = template.render(&session, &["--fork-session".to_string()]);
+        assert_eq!(
+            cmd,
+            "cd '/tmp/it'\\''s here' && npx @anthropic-ai/claude-code -r eb53d999-8692-42ce-a376-4f82206a086d '--fork-session'"
+        );
+    }
+
+    #[test]
+    fn test_template_requires_id() {
+        let err = ResumeTemplate::parse("cd {dir} && claude").unwrap_err();
+        assert!(err.to_string().contains("{id}"));
+    }
+
+    #[test]
+    fn test_template_rejects_unknown_placeholder() {
+        let err = ResumeTemplate::parse("claude --resume {id} {model}").unwrap_err();
+        assert!(err.to_string().contains("{model}"));
+    }
+
+    #[test]
+    fn test_template_rejects_unbalanced_braces() {
+        assert!(ResumeTemplate::parse("claude --resume {id").is_err());
+        assert!(ResumeTemplate::parse("claude --resume {id} }").is_err());
+    }
+
+    #[test]
+    fn test_template_rejects_control_characters() {
+        assert!(ResumeTemplate::parse("claude --resume {id}\nrm -rf ~").is_err());
+        assert!(ResumeTemplate::parse("claude --resume {id}\r").is_err());
+    }
+
+    #[test]
+    fn test_exec_protocol_with_args() {
+        let session = make_session(
+            "eb53d999-8692-42ce-a376-4f82206a086d",
+            "/home/user/project",
+            "~/project",
+        );
+        let args = vec!["--model".to_string(), "opus".to_string()];
+        assert!(print_exec_protocol(&session, &ResumeTemplate::default(), &args).is_ok());
+    }
+
+    #[test]
+    fn test_default_template_argv_skips_shell() {
+        let session = make_session(
+            "eb53d999-8692-42ce-a376-4f82206a086d",
+            "/home/user/project",
+            "~/project",
+        );
+        let argv = ResumeTemplate::default().argv(&session, &["--model".to_string()]);
+        assert_eq!(
+            argv,
+            vec![
+                "claude",
+                "--resume",
+                "eb53d999-8692-42ce-a376-4f82206a086d",
+                "--model"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_template_argv_uses_sh() {
+        let session = make_session(
+            "eb53d999-8692-42ce-a376-4f82206a086d",
+            "/home/user/project",
+            "~/project",
+        );
+        let template = ResumeTemplate::parse("npx claude -r {id}").unwrap();
+        let argv = template.argv(&session, &[]);
+        assert_eq!(
+            argv,
+            vec![
+                "sh",
+                "-c",
+                "npx claude -r eb53d999-8692-42ce-a376-4f82206a086d"
+            ]
+        );
+    }
 }
@@ -0,0 +1,474 @@
+use chrono::{DateTime, Utc};
+use owo_colors::{OwoColorize, Stream, Style};
+
+use crate::display::{format_relative_time, format_relative_time_short, pad, truncate_prompt};
+use crate::errors::CcseshError;
+use crate::shell::ResumeTemplate;
+use crate::theme::StyleName;
+use crate::types::{SessionField, SessionInfo};
+
+/// A user-defined single-line layout for session listings, e.g.
+/// `{index} {time_short} {project} {prompt:40}`.
+///
+/// Placeholders are `{field[:spec][|style]...}`:
+/// - `field` -- any `JsonSession` field, plus the aliases and extras listed in
+///   `Field::parse`.
+/// - `spec` -- `[<|>][width][.max]`. `max` truncates at a word boundary (like
+///   the built-in formats); `width` pads, left-aligned unless `>` is given, and
+///   also truncates when no `max` is given.
+/// - `style` -- a colour or attribute, optionally only when another field is
+///   set: `active:green`, or `!active:dimmed` for the opposite.
+///
+/// `{{` and `}}` are literal braces. Styles follow `NO_COLOR` and TTY
+/// detection like the built-in formats.
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Placeholder {
+    field: Field,
+    right_align: bool,
+    width: Option<usize>,
+    max: Option<usize>,
+    styles: Vec<ConditionalStyle>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ConditionalStyle {
+    /// Apply only when this field is set (or, when negated, when it is not).
+    condition: Option<(Field, bool)>,
+    style: StyleName,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Json(SessionField),
+    /// `last_active_relative` without " ago", as in `--format short`.
+    TimeShort,
+    /// First prompt, else slug, else "(empty session)".
+    Summary,
+    /// The `●` shown for open sessions, or nothing.
+    ActiveMarker,
+    /// Number of earlier sessions in the continuation chain.
+    Continued,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        let field = match name {
+            "id" => Field::Json(SessionField::SessionId),
+            "project" => Field::Json(SessionField::ProjectDirDisplay),
+            "time" => Field::Json(SessionField::LastActiveRelative),
+            "prompt" => Field::Json(SessionField::FirstPrompt),
+            "time_short" => Field::TimeShort,
+            "summary" => Field::Summary,
+            "active_marker" => Field::ActiveMarker,
+            "continued" => Field::Continued,
+            other => Field::Json(
+                SessionField::ALL
+                    .into_iter()
+                    .find(|field| field.name() == other)?,
+            ),
+        };
+        Some(field)
+    }
+
+    fn value(
+        self,
+        index: usize,
+        session: &SessionInfo,
+        now: DateTime<Utc>,
+        resume: &ResumeTemplate,
+        args: &[String],
+    ) -> String {
+        let field = match self {
+            Field::TimeShort => return format_relative_time_short(now - session.last_active),
+            Field::Summary => {
+                return session
+                    .first_prompt
+                    .clone()
+                    .or_else(|| session.slug.clone())
+                    .unwrap_or_else(|| "(empty session)".to_string());
+            }
+            Field::ActiveMarker => {
+                return if session.active { "\u{25cf}" } else { "" }.to_string();
+            }
+            Field::Continued => return session.earlier.len().to_string(),
+            Field::Json(field) => field,
+        };
+        match field {
+            SessionField::Index => index.to_string(),
+            SessionField::SessionId => session.session_id.clone(),
+            SessionField::ProjectDir => session.project_dir.to_string_lossy().into_owned(),
+            SessionField::ProjectDirDisplay => session.project_dir_display.clone(),
+            SessionField::LastActive => {
+                session.last_active.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+            }
+            SessionField::LastActiveRelative => format_relative_time(now - session.last_active),
+            SessionField::FirstPrompt => session.first_prompt.clone().unwrap_or_default(),
+            SessionField::Slug => session.slug.clone().unwrap_or_default(),
+            SessionField::Active => session.active.to_string(),
+            SessionField::ContinuedFrom => session
+                .earlier
+                .iter()
+                .map(|s| s.session_id.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            SessionField::Duplicates => session
+                .duplicates
+                .iter()
+                .map(|s| s.session_id.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            SessionField::ResumeCommand => resume.render(session, args),
+        }
+    }
+}
+
+/// Whether a rendered value counts as set for a style condition.
+fn is_set(value: &str) -> bool {
+    !matches!(value, "" | "false" | "0")
+}
+
+impl OutputTemplate {
+    pub fn parse(template: &str) -> Result<Self, CcseshError> {
+        let invalid = |reason: String| CcseshError::InvalidOutputTemplate { reason };
+
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut body = String::new();
+                    let mut closed = false;
+                    for inner in chars.by_ref() {
+                        if inner == '}' {
+                            closed = true;
+                            break;
+                        }
+                        body.push(inner);
+                    }
+                    if !closed {
+                        return Err(invalid(format!("unclosed placeholder '{{{}'", body)));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(
+                        parse_placeholder(&body).map_err(invalid)?,
+                    ));
+                }
+                '}' => {
+                    return Err(invalid(
+                        "unmatched '}' (use '}}' for a literal brace)".into(),
+                    ));
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Render one line for `session` at list position `index`, without a
+    /// trailing newline.
+    pub fn render(
+        &self,
+        index: usize,
+        session: &SessionInfo,
+        now: DateTime<Utc>,
+        resume: &ResumeTemplate,
+        args: &[String],
+    ) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(s) => out.push_str(s),
+                Segment::Placeholder(p) => {
+                    // Templates are single-line; flatten multi-line prompts.
+                    let value = p.field.value(index, session, now, resume, args);
+                    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+                    let text = p.fit(&value);
+
+                    let mut style = Style::new();
+                    let mut styled = false;
+                    for s in &p.styles {
+                        let applies = s.condition.is_none_or(|(field, want)| {
+                            is_set(&field.value(index, session, now, resume, args)) == want
+                        });
+                        if applies {
+                            style = s.style.apply(style);
+                            styled = true;
+                        }
+                    }
+                    if styled {
+                        out.push_str(
+                            &text
+                                .if_supports_color(Stream::Stdout, |t| t.style(style))
+                                .to_string(),
+                        );
+                    } else {
+                        out.push_str(&text);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Render every session, one line each.
+    pub fn render_all(
+        &self,
+        sessions: &[SessionInfo],
+        now: DateTime<Utc>,
+        resume: &ResumeTemplate,
+        args: &[String],
+    ) -> String {
+        let mut out = String::new();
+        for (i, session) in sessions.iter().enumerate() {
+            out.push_str(&self.render(i, session, now, resume, args));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Placeholder {
+    /// Apply truncation and padding.
+    fn fit(&self, value: &str) -> String {
+        let value = match self.max.or(self.width) {
+            Some(max) => truncate_prompt(value, max),
+            None => value.to_string(),
+        };
+        match self.width {
+            Some(width) => pad(&value, width, !self.right_align),
+            None => value,
+        }
+    }
+}
+
+/// Parse the inside of `{...}`: `field[:spec][|style]...`.
+fn parse_placeholder(body: &str) -> Result<Placeholder, String> {
+    let mut parts = body.split('|');
+    let head = parts.next().unwrap_or_default();
+    let (name, spec) = match head.split_once(':') {
+        Some((name, spec)) => (name, Some(spec)),
+        None => (head, None),
+    };
+
+    let field = Field::parse(name.trim())
+        .ok_or_else(|| format!("unknown placeholder '{{{}}}'", name.trim()))?;
+
+    let mut placeholder = Placeholder {
+        field,
+        right_align: false,
+        width: None,
+        max: None,
+        styles: vec![],
+    };
+
+    if let Some(spec) = spec {
+        let bad_spec = || format!("invalid format '{}' in '{{{}}}'", spec, body);
+        let mut rest = spec;
+        if let Some(r) = rest.strip_prefix('>') {
+            placeholder.right_align = true;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('<') {
+            rest = r;
+        }
+        let (width, max) = match rest.split_once('.') {
+            Some((width, max)) => (width, Some(max)),
+            None => (rest, None),
+        };
+        if !width.is_empty() {
+            placeholder.width = Some(width.parse().map_err(|_| bad_spec())?);
+        }
+        if let Some(max) = max {
+            placeholder.max = Some(max.parse().map_err(|_| bad_spec())?);
+        }
+    }
+
+    for part in parts {
+        let part = part.trim();
+        let (condition, style) = match part.split_once(':') {
+            Some((cond, style)) => {
+                let (negated, cond) = match cond.strip_prefix('!') {
+                    Some(c) => (true, c),
+                    None => (false, cond),
+                };
+                let field = Field::parse(cond)
+                    .ok_or_else(|| format!("unknown condition '{}' in '{{{}}}'", cond, body))?;
+                (Some((field, !negated)), style)
+            }
+            None => (None, part),
+        };
+        let style = StyleName::parse(style)
+            .ok_or_else(|| format!("unknown style '{}' in '{{{}}}'", style, body))?;
+        placeholder
+            .styles
+            .push(ConditionalStyle { condition, style });
+    }
+
+    Ok(placeholder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-02-18T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn session(prompt: Option<&str>) -> SessionInfo {
+        SessionInfo {
+            project_dir_display: "~/dev/myapp".to_string(),
+            last_active: now() - TimeDelta::seconds(150),
+            first_prompt: prompt.map(str::to_string),
+            slug: Some("flickering-jumping-raven".to_string()),
+            ..SessionInfo::fixture(
+                "3ab5f3ce-483e-4f9e-8772-cb488b79f3cc",
+                "/home/user/dev/myapp",
+            )
+        }
+    }
+
+    fn render(template: &str, session: &SessionInfo) -> String {
+        OutputTemplate::parse(template).unwrap().render(
+            3,
+            session,
+            now(),
+            &ResumeTemplate::default(),
+            &[],
+        )
+    }
+
+    #[test]
+    fn renders_fields_and_aliases() {
+        let s = session(Some("Add auth"));
+        assert_eq!(
+            render("{index} {time_short} {project} {prompt}", &s),
+            "3 2m ~/dev/myapp Add auth"
+        );
+        assert_eq!(render("{id}", &s), s.session_id);
+        assert_eq!(
+            render("{time} / {last_active}", &s),
+            "2m ago / 2026-02-17T23:57:30Z"
+        );
+        assert_eq!(
+            render("{resume_command}", &s),
+            "cd '/home/user/dev/myapp' && claude --resume 3ab5f3ce-483e-4f9e-8772-cb488b79f3cc"
+        );
+    }
+
+    #[test]
+    fn summary_falls_back_and_missing_values_are_empty() {
+        let s = session(None);
+        assert_eq!(
+            render("[{prompt}] {summary}", &s),
+            "[] flickering-jumping-raven"
+        );
+        assert_eq!(
+            render("[{active_marker}] {active} {continued}", &s),
+            "[] false 0"
+        );
+    }
+
+    #[test]
+    fn width_truncates_and_pads() {
+        let s = session(Some("Refactor database connection pooling to use deadpool"));
+        assert_eq!(render("{prompt:21}|", &s), "Refactor database... |");
+        assert_eq!(render("{index:>4}|{index:<3}|", &s), "   3|3  |");
+        assert_eq!(render("{prompt:.12}|", &s), "Refactor...|");
+        assert_eq!(render("{index:6.2}|", &s), "3     |");
+    }
+
+    #[test]
+    fn width_pads_by_display_width() {
+        let s = session(Some("日本語"));
+        assert_eq!(render("{prompt:8}|", &s), "日本語  |");
+    }
+
+    #[test]
+    fn multiline_values_are_flattened() {
+        let s = session(Some("line one\nline two"));
+        assert_eq!(render("{prompt}", &s), "line one line two");
+    }
+
+    #[test]
+    fn literal_braces() {
+        let s = session(None);
+        assert_eq!(render("{{{index}}}", &s), "{3}");
+    }
+
+    #[test]
+    fn styles_parse_with_conditions() {
+        let template = OutputTemplate::parse("{project|green|active:bold|!active:dim}").unwrap();
+        let Segment::Placeholder(p) = &template.segments[0] else {
+            panic!("expected placeholder");
+        };
+        assert_eq!(p.styles.len(), 3);
+        assert_eq!(
+            p.styles[1].condition,
+            Some((Field::Json(SessionField::Active), true))
+        );
+        assert_eq!(
+            p.styles[2].condition,
+            Some((Field::Json(SessionField::Active), false))
+        );
+        assert_eq!(p.styles[2].style, StyleName::Dimmed);
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        for bad in [
+            "{nope}",
+            "{index",
+            "index}",
+            "{prompt:abc}",
+            "{prompt|sparkly}",
+            "{prompt|maybe:green}",
+        ] {
+            let err = OutputTemplate::parse(bad).unwrap_err();
+            assert!(
+                matches!(err, CcseshError::InvalidOutputTemplate { .. }),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn render_all_one_line_each() {
+        let template = OutputTemplate::parse("{index}:{prompt}").unwrap();
+        let sessions = vec![session(Some("a")), session(Some("b"))];
+        assert_eq!(
+            template.render_all(&sessions, now(), &ResumeTemplate::default(), &[]),
+            "0:a\n1:b\n"
+        );
+    }
+}
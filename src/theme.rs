@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use owo_colors::{OwoColorize, Stream, Style};
+use serde::Deserialize;
+
+use crate::errors::CcseshError;
+
+/// When to colour output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Follow `NO_COLOR` / `CLICOLOR_FORCE`, else colour only terminals
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Whether to force colour on or off under `mode`, or `None` to leave it to
+/// terminal detection. An explicit `always`/`never` beats the environment;
+/// under `auto`, a non-empty `NO_COLOR` disables colour and a non-empty
+/// `CLICOLOR_FORCE` other than `0` forces it.
+pub fn resolve_color(mode: ColorMode, env: impl Fn(&str) -> Option<String>) -> Option<bool> {
+    let set = |name: &str| env(name).is_some_and(|v| !v.is_empty());
+    match mode {
+        ColorMode::Always => Some(true),
+        ColorMode::Never => Some(false),
+        ColorMode::Auto if set("NO_COLOR") => Some(false),
+        ColorMode::Auto
+            if set("CLICOLOR_FORCE") && env("CLICOLOR_FORCE").as_deref() != Some("0") =>
+        {
+            Some(true)
+        }
+        ColorMode::Auto => None,
+    }
+}
+
+/// Apply `mode` to every `if_supports_color` call for the rest of the process.
+pub fn init_color(mode: ColorMode) {
+    match resolve_color(mode, |name| std::env::var(name).ok()) {
+        Some(enabled) => owo_colors::set_override(enabled),
+        None => owo_colors::unset_override(),
+    }
+}
+
+/// A part of the listing that a theme styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Element {
+    /// Session index
+    Index,
+    /// Relative time
+    Time,
+    /// Project path
+    Path,
+    /// First prompt
+    Prompt,
+    /// Slug, and "(empty session)", when there is no prompt
+    Slug,
+    /// Header and footer lines
+    Header,
+    /// The `●` next to open sessions
+    Active,
+    /// The "(continued ×N)" marker
+    Continued,
+}
+
+impl Element {
+    pub const ALL: [Element; 8] = [
+        Element::Index,
+        Element::Time,
+        Element::Path,
+        Element::Prompt,
+        Element::Slug,
+        Element::Header,
+        Element::Active,
+        Element::Continued,
+    ];
+
+    /// Key in the config's `[colors]` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Element::Index => "index",
+            Element::Time => "time",
+            Element::Path => "path",
+            Element::Prompt => "prompt",
+            Element::Slug => "slug",
+            Element::Header => "header",
+            Element::Active => "active",
+            Element::Continued => "continued",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Element> {
+        Element::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
+/// Styles for each `Element` of the default and short listings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub index: Style,
+    pub time: Style,
+    pub path: Style,
+    pub prompt: Style,
+    pub slug: Style,
+    pub header: Style,
+    pub active: Style,
+    pub continued: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
+
+impl Theme {
+    /// The original palette, for dark backgrounds.
+    pub const DARK: Theme = Theme {
+        index: Style::new().cyan().bold(),
+        time: Style::new().yellow(),
+        path: Style::new().green(),
+        prompt: Style::new().white(),
+        slug: Style::new().dimmed().italic(),
+        header: Style::new().dimmed(),
+        active: Style::new().green().bold(),
+        continued: Style::new().dimmed(),
+    };
+
+    /// For light backgrounds: no white or yellow text.
+    pub const LIGHT: Theme = Theme {
+        index: Style::new().blue().bold(),
+        time: Style::new().magenta(),
+        path: Style::new().green(),
+        prompt: Style::new().black(),
+        slug: Style::new().dimmed().italic(),
+        header: Style::new().dimmed(),
+        active: Style::new().green().bold(),
+        continued: Style::new().dimmed(),
+    };
+
+    /// Attributes only, for terminals with unusual palettes.
+    pub const MONO: Theme = Theme {
+        index: Style::new().bold(),
+        time: Style::new(),
+        path: Style::new(),
+        prompt: Style::new(),
+        slug: Style::new().italic(),
+        header: Style::new().dimmed(),
+        active: Style::new().bold(),
+        continued: Style::new().dimmed(),
+    };
+
+    pub const NAMES: [&'static str; 3] = ["default", "light", "mono"];
+
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "default" | "dark" => Some(Theme::DARK),
+            "light" => Some(Theme::LIGHT),
+            "mono" => Some(Theme::MONO),
+            _ => None,
+        }
+    }
+
+    /// The named theme (default if `None`) with the `[colors]` overrides from
+    /// the config file applied. Each override replaces the element's style,
+    /// e.g. `path = "blue underline"`; `"none"` removes it.
+    pub fn from_config(
+        name: Option<&str>,
+        overrides: &BTreeMap<String, String>,
+    ) -> Result<Theme, CcseshError> {
+        let invalid = |reason: String| CcseshError::InvalidTheme { reason };
+
+        let mut theme = match name {
+            Some(name) => Theme::named(name).ok_or_else(|| {
+                invalid(format!(
+                    "unknown theme '{}' (expected {})",
+                    name,
+                    Theme::NAMES.join(", ")
+                ))
+            })?,
+            None => Theme::default(),
+        };
+        for (key, spec) in overrides {
+            let element = Element::parse(key).ok_or_else(|| {
+                let names: Vec<&str> = Element::ALL.iter().map(|e| e.name()).collect();
+                invalid(format!(
+                    "unknown element '{}' in [colors] (expected {})",
+                    key,
+                    names.join(", ")
+                ))
+            })?;
+            let style = parse_style(spec)
+                .ok_or_else(|| invalid(format!("unknown style '{}' for '{}'", spec, key)))?;
+            *theme.get_mut(element) = style;
+        }
+        Ok(theme)
+    }
+
+    pub fn get(&self, element: Element) -> Style {
+        match element {
+            Element::Index => self.index,
+            Element::Time => self.time,
+            Element::Path => self.path,
+            Element::Prompt => self.prompt,
+            Element::Slug => self.slug,
+            Element::Header => self.header,
+            Element::Active => self.active,
+            Element::Continued => self.continued,
+        }
+    }
+
+    fn get_mut(&mut self, element: Element) -> &mut Style {
+        match element {
+            Element::Index => &mut self.index,
+            Element::Time => &mut self.time,
+            Element::Path => &mut self.path,
+            Element::Prompt => &mut self.prompt,
+            Element::Slug => &mut self.slug,
+            Element::Header => &mut self.header,
+            Element::Active => &mut self.active,
+            Element::Continued => &mut self.continued,
+        }
+    }
+
+    /// `text` in `element`'s style, when stdout takes colour.
+    pub fn paint(&self, element: Element, text: &str) -> String {
+        self.paint_with(self.get(element), text)
+    }
+
+    /// `text` in `style`, when stdout takes colour.
+    pub fn paint_with(&self, style: Style, text: &str) -> String {
+        text.if_supports_color(Stream::Stdout, |t| t.style(style))
+            .to_string()
+    }
+}
+
+/// A colour or text attribute, as used in `--template` styles and `[colors]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum StyleName {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Bold,
+    Dimmed,
+    Italic,
+    Underline,
+    Reversed,
+}
+
+impl StyleName {
+    pub(crate) fn parse(name: &str) -> Option<StyleName> {
+        let style = match name {
+            "black" => StyleName::Black,
+            "red" => StyleName::Red,
+            "green" => StyleName::Green,
+            "yellow" => StyleName::Yellow,
+            "blue" => StyleName::Blue,
+            "magenta" => StyleName::Magenta,
+            "cyan" => StyleName::Cyan,
+            "white" => StyleName::White,
+            "bold" => StyleName::Bold,
+            "dimmed" | "dim" => StyleName::Dimmed,
+            "italic" => StyleName::Italic,
+            "underline" => StyleName::Underline,
+            "reversed" => StyleName::Reversed,
+            _ => return None,
+        };
+        Some(style)
+    }
+
+    pub(crate) fn apply(self, style: Style) -> Style {
+        match self {
+            StyleName::Black => style.black(),
+            StyleName::Red => style.red(),
+            StyleName::Green => style.green(),
+            StyleName::Yellow => style.yellow(),
+            StyleName::Blue => style.blue(),
+            StyleName::Magenta => style.magenta(),
+            StyleName::Cyan => style.cyan(),
+            StyleName::White => style.white(),
+            StyleName::Bold => style.bold(),
+            StyleName::Dimmed => style.dimmed(),
+            StyleName::Italic => style.italic(),
+            StyleName::Underline => style.underline(),
+            StyleName::Reversed => style.reversed(),
+        }
+    }
+}
+
+/// Parse a space-separated list of style names, e.g. `"cyan bold"`. `"none"`
+/// is the plain style.
+pub fn parse_style(spec: &str) -> Option<Style> {
+    if spec.trim() == "none" {
+        return Some(Style::new());
+    }
+    let mut names = spec.split_whitespace().peekable();
+    names.peek()?;
+    names.try_fold(Style::new(), |style, name| {
+        StyleName::parse(name).map(|n| n.apply(style))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    /// Every element of `theme` styled, one `name: text` line each, with the
+    /// escape character shown as `\e`.
+    fn snapshot(theme: &Theme) -> String {
+        Element::ALL
+            .iter()
+            .map(|&e| {
+                let styled = format!("{}", e.name().style(theme.get(e)));
+                format!("{}: {}\n", e.name(), styled.replace('\x1b', "\\e"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dark_theme_snapshot() {
+        assert_eq!(
+            snapshot(&Theme::named("default").unwrap()),
+            "index: \\e[36;1mindex\\e[0m\n\
+             time: \\e[33mtime\\e[0m\n\
+             path: \\e[32mpath\\e[0m\n\
+             prompt: \\e[37mprompt\\e[0m\n\
+             slug: \\e[2;3mslug\\e[0m\n\
+             header: \\e[2mheader\\e[0m\n\
+             active: \\e[32;1mactive\\e[0m\n\
+             continued: \\e[2mcontinued\\e[0m\n"
+        );
+    }
+
+    #[test]
+    fn light_theme_snapshot() {
+        assert_eq!(
+            snapshot(&Theme::named("light").unwrap()),
+            "index: \\e[34;1mindex\\e[0m\n\
+             time: \\e[35mtime\\e[0m\n\
+             path: \\e[32mpath\\e[0m\n\
+             prompt: \\e[30mprompt\\e[0m\n\
+             slug: \\e[2;3mslug\\e[0m\n\
+             header: \\e[2mheader\\e[0m\n\
+             active: \\e[32;1mactive\\e[0m\n\
+             continued: \\e[2mcontinued\\e[0m\n"
+        );
+    }
+
+    #[test]
+    fn mono_theme_snapshot() {
+        assert_eq!(
+            snapshot(&Theme::named("mono").unwrap()),
+            "index: \\e[1mindex\\e[0m\n\
+             time: time\n\
+             path: path\n\
+             prompt: prompt\n\
+             slug: \\e[3mslug\\e[0m\n\
+             header: \\e[2mheader\\e[0m\n\
+             active: \\e[1mactive\\e[0m\n\
+             continued: \\e[2mcontinued\\e[0m\n"
+        );
+    }
+
+    #[test]
+    fn config_overrides_replace_element_styles() {
+        let overrides = BTreeMap::from([
+            ("path".to_string(), "blue underline".to_string()),
+            ("time".to_string(), "none".to_string()),
+        ]);
+        let theme = Theme::from_config(Some("light"), &overrides).unwrap();
+        assert_eq!(theme.path, Style::new().blue().underline());
+        assert_eq!(theme.time, Style::new());
+        assert_eq!(theme.index, Theme::LIGHT.index);
+    }
+
+    #[test]
+    fn config_errors_name_the_problem() {
+        let none = BTreeMap::new();
+        let err = Theme::from_config(Some("solarized"), &none).unwrap_err();
+        assert!(err.to_string().contains("unknown theme 'solarized'"));
+
+        let bad_key = BTreeMap::from([("prompts".to_string(), "red".to_string())]);
+        let err = Theme::from_config(None, &bad_key).unwrap_err();
+        assert!(err.to_string().contains("unknown element 'prompts'"));
+
+        let bad_style = BTreeMap::from([("prompt".to_string(), "red blinking".to_string())]);
+        let err = Theme::from_config(None, &bad_style).unwrap_err();
+        assert!(err.to_string().contains("unknown style 'red blinking'"));
+    }
+
+    #[test]
+    fn parse_style_combines_names() {
+        assert_eq!(parse_style("cyan bold"), Some(Style::new().cyan().bold()));
+        assert_eq!(parse_style("none"), Some(Style::new()));
+        assert_eq!(parse_style(""), None);
+        assert_eq!(parse_style("cyan sparkly"), None);
+    }
+
+    #[test]
+    fn color_mode_resolution() {
+        assert_eq!(resolve_color(ColorMode::Auto, env(&[])), None);
+        assert_eq!(
+            resolve_color(ColorMode::Auto, env(&[("NO_COLOR", "1")])),
+            Some(false)
+        );
+        assert_eq!(
+            resolve_color(ColorMode::Auto, env(&[("NO_COLOR", "")])),
+            None
+        );
+        assert_eq!(
+            resolve_color(ColorMode::Auto, env(&[("CLICOLOR_FORCE", "1")])),
+            Some(true)
+        );
+        assert_eq!(
+            resolve_color(ColorMode::Auto, env(&[("CLICOLOR_FORCE", "0")])),
+            None
+        );
+        assert_eq!(
+            resolve_color(
+                ColorMode::Auto,
+                env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")])
+            ),
+            Some(false)
+        );
+        assert_eq!(
+            resolve_color(ColorMode::Always, env(&[("NO_COLOR", "1")])),
+            Some(true)
+        );
+        assert_eq!(
+            resolve_color(ColorMode::Never, env(&[("CLICOLOR_FORCE", "1")])),
+            Some(false)
+        );
+    }
+}
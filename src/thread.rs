@@ -0,0 +1,298 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::types::TranscriptRecord;
+
+/// Why a branch is not part of the main thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BranchKind {
+    /// Left behind by an edited prompt or a retried response.
+    Abandoned,
+    /// A sub-task conversation (`isSidechain: true`).
+    Sidechain,
+}
+
+/// A path off the main thread, ending at a leaf.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Branch {
+    pub kind: BranchKind,
+    /// Record index of the main-thread record this branch splits from, if any.
+    pub fork: Option<usize>,
+    /// Record indices from the first off-thread record to the leaf.
+    pub records: Vec<usize>,
+}
+
+/// Transcript records linked into a tree by `uuid` / `parentUuid`.
+///
+/// Editing a prompt or retrying a response adds a sibling under the same
+/// parent; sidechain records hang off the message that started the sub-task;
+/// compaction restarts the chain with a `null` parent and points back through
+/// `logicalParentUuid`, which is followed as if it were the parent.
+///
+/// Records are addressed by their index in file order. Records without a
+/// `uuid` (summaries, snapshots) are kept but are not part of the tree.
+pub struct ConversationGraph {
+    records: Vec<TranscriptRecord>,
+    parents: Vec<Option<usize>>,
+    has_children: Vec<bool>,
+    /// Whether any record carries a `uuid`. Transcripts without linkage are
+    /// treated as one linear thread.
+    linked: bool,
+}
+
+impl ConversationGraph {
+    pub fn new(records: Vec<TranscriptRecord>) -> Self {
+        let mut by_uuid: HashMap<&str, usize> = HashMap::new();
+        for (i, record) in records.iter().enumerate() {
+            if let Some(uuid) = record.meta().and_then(|m| m.uuid.as_deref()) {
+                // Keep the first copy if a uuid is repeated.
+                by_uuid.entry(uuid).or_insert(i);
+            }
+        }
+
+        let mut parents = vec![None; records.len()];
+        let mut has_children = vec![false; records.len()];
+        for (i, record) in records.iter().enumerate() {
+            let is_node = record
+                .meta()
+                .and_then(|m| m.uuid.as_deref())
+                .is_some_and(|uuid| by_uuid.get(uuid) == Some(&i));
+            if !is_node {
+                continue;
+            }
+            if let Some(&parent) = parent_uuid(record).and_then(|p| by_uuid.get(p))
+                && parent != i
+            {
+                parents[i] = Some(parent);
+                has_children[parent] = true;
+            }
+        }
+
+        let linked = !by_uuid.is_empty();
+        ConversationGraph {
+            records,
+            parents,
+            has_children,
+            linked,
+        }
+    }
+
+    pub fn records(&self) -> &[TranscriptRecord] {
+        &self.records
+    }
+
+    /// Index of the record `index` replies to, if it is in this transcript.
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.parents[index]
+    }
+
+    fn is_node(&self, index: usize) -> bool {
+        self.records[index].meta().is_some_and(|m| m.uuid.is_some())
+    }
+
+    fn is_sidechain(&self, index: usize) -> bool {
+        self.records[index]
+            .meta()
+            .is_some_and(|m| m.is_sidechain == Some(true))
+    }
+
+    /// The record the user ended up on: the last non-sidechain record with a
+    /// `uuid` in file order, since Claude Code appends every new message.
+    pub fn active_leaf(&self) -> Option<usize> {
+        (0..self.records.len())
+            .rev()
+            .find(|&i| self.is_node(i) && !self.is_sidechain(i))
+    }
+
+    /// Record indices from the root to `index`. Stops at a missing parent and
+    /// is safe against cycles.
+    pub fn path_to(&self, index: usize) -> Vec<usize> {
+        let mut path = vec![index];
+        let mut seen = HashSet::from([index]);
+        let mut current = index;
+        while let Some(parent) = self.parents[current] {
+            if !seen.insert(parent) {
+                break;
+            }
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// The active thread from root to `active_leaf()`. Without any `uuid`
+    /// linkage every record is returned in file order.
+    pub fn main_thread(&self) -> Vec<usize> {
+        if !self.linked {
+            return (0..self.records.len()).collect();
+        }
+        self.active_leaf()
+            .map(|leaf| self.path_to(leaf))
+            .unwrap_or_default()
+    }
+
+    /// Every path that ends off the main thread: one per leaf, running from
+    /// where it leaves the main thread to that leaf, in file order of the
+    /// first record. Branches made only of non-message records (for example
+    /// trailing system entries) are omitted.
+    pub fn branches(&self) -> Vec<Branch> {
+        if !self.linked {
+            return vec![];
+        }
+        let main: HashSet<usize> = self.main_thread().into_iter().collect();
+
+        let mut branches = Vec::new();
+        for leaf in 0..self.records.len() {
+            if !self.is_node(leaf) || self.has_children[leaf] || main.contains(&leaf) {
+                continue;
+            }
+
+            let path = self.path_to(leaf);
+            let split = path.iter().rposition(|i| main.contains(i));
+            let fork = split.map(|s| path[s]);
+            let records = path[split.map_or(0, |s| s + 1)..].to_vec();
+
+            let has_message = records.iter().any(|&i| {
+                matches!(
+                    self.records[i],
+                    TranscriptRecord::User(_) | TranscriptRecord::Assistant(_)
+                )
+            });
+            if !has_message {
+                continue;
+            }
+
+            let kind = if records.iter().any(|&i| self.is_sidechain(i)) {
+                BranchKind::Sidechain
+            } else {
+                BranchKind::Abandoned
+            };
+            branches.push(Branch {
+                kind,
+                fork,
+                records,
+            });
+        }
+        branches.sort_by_key(|b| b.records[0]);
+        branches
+    }
+}
+
+/// The uuid this record hangs off: `parentUuid`, or `logicalParentUuid`
+/// across a compaction boundary.
+fn parent_uuid(record: &TranscriptRecord) -> Option<&str> {
+    let meta = record.meta()?;
+    if let Some(Some(parent)) = &meta.parent_uuid {
+        return Some(parent);
+    }
+    let extra = match record {
+        TranscriptRecord::User(e) | TranscriptRecord::Assistant(e) => &e.extra,
+        TranscriptRecord::System(e) => &e.extra,
+        _ => return None,
+    };
+    extra.get("logicalParentUuid").and_then(|v| v.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn load(name: &str) -> ConversationGraph {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let records = crate::transcript::read_records(&path).unwrap();
+        ConversationGraph::new(records)
+    }
+
+    /// Last 2 digits of each record's uuid, for compact assertions.
+    fn ids(graph: &ConversationGraph, indices: &[usize]) -> Vec<String> {
+        indices
+            .iter()
+            .map(|&i| {
+                let uuid = graph.records()[i].meta().unwrap().uuid.as_deref().unwrap();
+                uuid[uuid.len() - 2..].to_string()
+            })
+            .collect()
+    }
+
+    fn record(json: &str) -> TranscriptRecord {
+        TranscriptRecord::parse(json).unwrap()
+    }
+
+    #[test]
+    fn main_thread_follows_edit_and_compaction() {
+        let graph = load("branching.jsonl");
+        assert_eq!(
+            ids(&graph, &graph.main_thread()),
+            vec!["01", "02", "05", "08", "09", "10", "11"]
+        );
+        assert_eq!(ids(&graph, &[graph.active_leaf().unwrap()]), vec!["11"]);
+    }
+
+    #[test]
+    fn branches_are_dead_ends_and_sidechains() {
+        let graph = load("branching.jsonl");
+        let branches = graph.branches();
+        assert_eq!(branches.len(), 2);
+
+        assert_eq!(branches[0].kind, BranchKind::Abandoned);
+        assert_eq!(ids(&graph, &branches[0].records), vec!["03", "04"]);
+        assert_eq!(ids(&graph, &[branches[0].fork.unwrap()]), vec!["02"]);
+
+        assert_eq!(branches[1].kind, BranchKind::Sidechain);
+        assert_eq!(ids(&graph, &branches[1].records), vec!["06", "07"]);
+        assert_eq!(ids(&graph, &[branches[1].fork.unwrap()]), vec!["05"]);
+    }
+
+    #[test]
+    fn unlinked_transcript_is_linear() {
+        let graph = load("normal.jsonl");
+        assert_eq!(
+            graph.main_thread(),
+            (0..graph.records().len()).collect::<Vec<_>>()
+        );
+        assert!(graph.branches().is_empty());
+    }
+
+    #[test]
+    fn sidechain_off_linear_thread() {
+        let graph = load("tool_use.jsonl");
+        // The snapshot and summary records carry no uuid and are not on the thread.
+        assert_eq!(
+            ids(&graph, &graph.main_thread()),
+            vec!["11", "22", "33", "44", "66"]
+        );
+        let branches = graph.branches();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0].kind, BranchKind::Sidechain);
+        assert_eq!(ids(&graph, &branches[0].records), vec!["55"]);
+    }
+
+    #[test]
+    fn cycles_and_missing_parents_terminate() {
+        let graph = ConversationGraph::new(vec![
+            record(r#"{"type":"user","uuid":"a","parentUuid":"b","message":{"content":"x"}}"#),
+            record(r#"{"type":"assistant","uuid":"b","parentUuid":"a","message":{"content":"y"}}"#),
+            record(r#"{"type":"user","uuid":"c","parentUuid":"gone","message":{"content":"z"}}"#),
+        ]);
+        assert_eq!(graph.path_to(1), vec![0, 1]);
+        assert_eq!(graph.main_thread(), vec![2]);
+        assert_eq!(graph.parent(2), None);
+    }
+
+    #[test]
+    fn trailing_system_records_are_not_branches() {
+        let graph = ConversationGraph::new(vec![
+            record(r#"{"type":"user","uuid":"a","parentUuid":null,"message":{"content":"x"}}"#),
+            record(r#"{"type":"system","uuid":"s","parentUuid":"a","content":"hook ran"}"#),
+            record(r#"{"type":"assistant","uuid":"b","parentUuid":"a","message":{"content":"y"}}"#),
+        ]);
+        assert_eq!(graph.main_thread(), vec![0, 2]);
+        assert!(graph.branches().is_empty());
+    }
+}
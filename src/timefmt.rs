@@ -0,0 +1,323 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::display::{format_relative_time, format_relative_time_short};
+use crate::errors::CcseshError;
+
+/// Default `time_format`: sortable, and unambiguous across locales.
+pub const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// How the listings show when a session was last active.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeMode {
+    /// "3d ago"
+    #[default]
+    Relative,
+    /// "2026-02-15 14:03"
+    Absolute,
+    /// "3d ago (2026-02-15 14:03)"
+    Both,
+}
+
+/// The zone absolute times are shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zone {
+    /// The system zone, or `$TZ`.
+    #[default]
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+    /// An IANA zone such as `Europe/Berlin`, with its daylight saving rules.
+    Named(Tz),
+}
+
+impl Zone {
+    /// Parse a `--tz` value: `local`, `utc`, a fixed offset (`+05:30`,
+    /// `-0800`), or an IANA name such as `Europe/Berlin`.
+    pub fn parse(spec: &str) -> Result<Zone, CcseshError> {
+        let unknown = || CcseshError::InvalidTimeSetting {
+            reason: format!(
+                "unknown time zone '{}' (expected local, utc, an offset like +05:30, or a name like Europe/Berlin)",
+                spec
+            ),
+        };
+
+        match spec.to_ascii_lowercase().as_str() {
+            "local" => return Ok(Zone::Local),
+            "utc" | "z" => return Ok(Zone::Utc),
+            _ => {}
+        }
+        if spec.starts_with(['+', '-']) {
+            return parse_offset(spec).map(Zone::Fixed).ok_or_else(unknown);
+        }
+        spec.parse().map(Zone::Named).map_err(|_| unknown())
+    }
+
+    /// `time` as wall-clock time in this zone.
+    pub fn local(self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Local => time.with_timezone(&Local).naive_local(),
+            Zone::Utc => time.naive_utc(),
+            Zone::Fixed(offset) => time.with_timezone(&offset).naive_local(),
+            Zone::Named(tz) => time.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    fn format(self, time: DateTime<Utc>, format: &str) -> String {
+        match self {
+            Zone::Local => time.with_timezone(&Local).format(format).to_string(),
+            Zone::Utc => time.format(format).to_string(),
+            Zone::Fixed(offset) => time.with_timezone(&offset).format(format).to_string(),
+            Zone::Named(tz) => time.with_timezone(&tz).format(format).to_string(),
+        }
+    }
+}
+
+/// `+05:30`, `-0800`, or `+09` as an offset from UTC.
+fn parse_offset(spec: &str) -> Option<FixedOffset> {
+    let sign = if spec.starts_with('-') { -1 } else { 1 };
+    let digits: String = spec[1..].chars().filter(|&c| c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Check a strftime format up front; chrono panics on bad formats at render
+/// time.
+pub fn validate_format(format: &str) -> Result<(), CcseshError> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(CcseshError::InvalidTimeSetting {
+            reason: format!("invalid time format '{}'", format),
+        });
+    }
+    Ok(())
+}
+
+/// Parse an age such as `36h`, `7d`, or `2w` (units s, m, h, d, w).
+pub fn parse_age(spec: &str) -> Result<TimeDelta, CcseshError> {
+    let invalid = || CcseshError::InvalidTimeSetting {
+        reason: format!("invalid age '{}' (expected e.g. 36h, 7d, 2w)", spec),
+    };
+    let spec = spec.trim();
+    let split = spec
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let amount: i64 = spec[..split].parse().map_err(|_| invalid())?;
+    let seconds = match &spec[split..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 604800,
+        _ => return Err(invalid()),
+    };
+    amount
+        .checked_mul(seconds)
+        .and_then(TimeDelta::try_seconds)
+        .ok_or_else(invalid)
+}
+
+/// How the text listings render `last_active`. JSON and the other
+/// machine-readable formats always use UTC ISO 8601.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeDisplay {
+    pub mode: TimeMode,
+    /// strftime format for absolute times.
+    pub format: String,
+    pub zone: Zone,
+    /// In relative mode, show absolute times for sessions older than this.
+    pub absolute_after: Option<TimeDelta>,
+}
+
+impl Default for TimeDisplay {
+    fn default() -> Self {
+        TimeDisplay {
+            mode: TimeMode::default(),
+            format: DEFAULT_TIME_FORMAT.to_string(),
+            zone: Zone::default(),
+            absolute_after: None,
+        }
+    }
+}
+
+impl TimeDisplay {
+    /// The time column of the default listing.
+    pub fn long(&self, time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        let relative = || format_relative_time(now - time);
+        match self.mode_for(time, now) {
+            TimeMode::Relative => relative(),
+            TimeMode::Absolute => self.absolute(time),
+            TimeMode::Both => format!("{} ({})", relative(), self.absolute(time)),
+        }
+    }
+
+    /// The time column of the short listing.
+    pub fn short(&self, time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        let relative = || format_relative_time_short(now - time);
+        match self.mode_for(time, now) {
+            TimeMode::Relative => relative(),
+            TimeMode::Absolute => self.absolute(time),
+            TimeMode::Both => format!("{} {}", relative(), self.absolute(time)),
+        }
+    }
+
+    pub fn absolute(&self, time: DateTime<Utc>) -> String {
+        self.zone.format(time, &self.format)
+    }
+
+    /// A wall-clock time such as the watch header's, in the display zone.
+    pub fn clock(&self, time: DateTime<Utc>) -> String {
+        self.zone.format(time, "%H:%M:%S")
+    }
+
+    fn mode_for(&self, time: DateTime<Utc>, now: DateTime<Utc>) -> TimeMode {
+        match self.mode {
+            TimeMode::Relative if self.absolute_after.is_some_and(|age| now - time > age) => {
+                TimeMode::Absolute
+            }
+            mode => mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn utc_display(mode: TimeMode) -> TimeDisplay {
+        TimeDisplay {
+            mode,
+            zone: Zone::Utc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn modes_render_relative_absolute_and_both() {
+        let now = at("2026-02-18T12:00:00Z");
+        let then = at("2026-02-15T09:30:00Z");
+        assert_eq!(utc_display(TimeMode::Relative).long(then, now), "3d ago");
+        assert_eq!(
+            utc_display(TimeMode::Absolute).long(then, now),
+            "2026-02-15 09:30"
+        );
+        assert_eq!(
+            utc_display(TimeMode::Both).long(then, now),
+            "3d ago (2026-02-15 09:30)"
+        );
+        assert_eq!(
+            utc_display(TimeMode::Both).short(then, now),
+            "3d 2026-02-15 09:30"
+        );
+    }
+
+    #[test]
+    fn switches_to_absolute_beyond_age() {
+        let now = at("2026-02-18T12:00:00Z");
+        let display = TimeDisplay {
+            absolute_after: Some(TimeDelta::days(1)),
+            ..utc_display(TimeMode::Relative)
+        };
+        assert_eq!(display.long(at("2026-02-18T10:00:00Z"), now), "2h ago");
+        assert_eq!(
+            display.long(at("2026-02-16T10:00:00Z"), now),
+            "2026-02-16 10:00"
+        );
+        // An explicit mode is not overridden.
+        let both = TimeDisplay {
+            mode: TimeMode::Both,
+            ..display
+        };
+        assert!(both.long(at("2026-02-16T10:00:00Z"), now).contains("ago"));
+    }
+
+    #[test]
+    fn fixed_offsets_and_custom_formats() {
+        let display = TimeDisplay {
+            format: "%d.%m.%Y %H:%M %z".to_string(),
+            zone: Zone::parse("+05:30").unwrap(),
+            ..utc_display(TimeMode::Absolute)
+        };
+        assert_eq!(
+            display.absolute(at("2026-02-15T20:00:00Z")),
+            "16.02.2026 01:30 +0530"
+        );
+        assert_eq!(
+            Zone::parse("-0800").unwrap(),
+            Zone::Fixed(FixedOffset::west_opt(8 * 3600).unwrap())
+        );
+        assert_eq!(Zone::parse("UTC").unwrap(), Zone::Utc);
+        assert_eq!(Zone::parse("local").unwrap(), Zone::Local);
+    }
+
+    #[test]
+    fn local_wall_clock_time() {
+        let time = at("2026-02-15T20:00:00Z");
+        assert_eq!(
+            Zone::parse("+05:30").unwrap().local(time).to_string(),
+            "2026-02-16 01:30:00"
+        );
+        assert_eq!(Zone::Utc.local(time).to_string(), "2026-02-15 20:00:00");
+        let berlin = Zone::parse("Europe/Berlin").unwrap();
+        assert_eq!(berlin.local(time).to_string(), "2026-02-15 21:00:00");
+        assert_eq!(
+            berlin.local(at("2026-07-15T20:00:00Z")).to_string(),
+            "2026-07-15 22:00:00"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_zones() {
+        for bad in [
+            "+5",
+            "+25:00",
+            "+05:75",
+            "Mars/Olympus_Mons",
+            "../etc/passwd",
+            "",
+        ] {
+            let err = Zone::parse(bad).unwrap_err();
+            assert!(err.to_string().contains("unknown time zone"), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn validates_formats() {
+        assert!(validate_format("%Y-%m-%d %H:%M").is_ok());
+        assert!(validate_format("%a %e %b").is_ok());
+        let err = validate_format("%Y-%Q").unwrap_err();
+        assert!(err.to_string().contains("invalid time format"));
+    }
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(parse_age("36h").unwrap(), TimeDelta::hours(36));
+        assert_eq!(parse_age("2w").unwrap(), TimeDelta::weeks(2));
+        assert_eq!(parse_age("90s").unwrap(), TimeDelta::seconds(90));
+        for bad in ["", "7", "d", "7y", "-1d", "1.5d"] {
+            assert!(parse_age(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,690 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::BufRead;
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Timelike, Utc};
+use serde::Serialize;
+
+use crate::display;
+use crate::timefmt::Zone;
+use crate::types::{JsonlLine, SessionInfo};
+
+/// How far back `ccsesh timeline` looks unless `--since` says otherwise.
+pub const DEFAULT_SINCE: &str = "52w";
+
+/// Heatmap cells from no activity to the busiest day (or hour).
+const LEVELS: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Projects listed in the breakdown before the rest are summarised.
+const TOP_PROJECTS: usize = 10;
+
+const BAR_WIDTH: usize = 20;
+
+/// Widest project path in the breakdown before it is shortened.
+const PROJECT_WIDTH: usize = 40;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Activity over a range of days, in the time zone it was built for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Timeline {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_messages: usize,
+    pub total_sessions: usize,
+    /// Days with at least one message, oldest first.
+    pub days: Vec<DayActivity>,
+    /// Messages by weekday (Monday first) and hour of day.
+    pub hours: [[usize; 24]; 7],
+    /// Busiest project first.
+    pub projects: Vec<ProjectActivity>,
+    pub streaks: Streaks,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayActivity {
+    pub date: NaiveDate,
+    pub messages: usize,
+    pub sessions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectActivity {
+    pub project_dir: String,
+    pub project_dir_display: String,
+    pub sessions: usize,
+    pub messages: usize,
+    pub active_days: usize,
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+}
+
+/// A run of consecutive days with activity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Streak {
+    pub days: usize,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Streaks {
+    pub longest: Option<Streak>,
+    /// The run ending today, or yesterday if today has no activity yet.
+    pub current: Option<Streak>,
+}
+
+/// When the user and assistant messages of a transcript were written, from
+/// their `timestamp` fields. Transcripts from versions that wrote no
+/// timestamps count each message at the file's mtime instead.
+pub fn message_times(session: &SessionInfo) -> Vec<DateTime<Utc>> {
+    let Ok(file) = std::fs::File::open(&session.path) else {
+        return Vec::new();
+    };
+    let mut times = Vec::new();
+    let mut untimed = 0;
+    for line in std::io::BufReader::new(file).lines() {
+        let Ok(line) = line else { break };
+        let Ok(record) = serde_json::from_str::<JsonlLine>(&line) else {
+            continue;
+        };
+        if !matches!(record.msg_type.as_deref(), Some("user" | "assistant"))
+            || record.is_meta == Some(true)
+        {
+            continue;
+        }
+        match record.time() {
+            Some(time) => times.push(time),
+            None => untimed += 1,
+        }
+    }
+    if times.is_empty() {
+        times = vec![session.last_active; untimed];
+    }
+    times
+}
+
+/// Reads the sessions active on or after `from` and builds their timeline up
+/// to `to`. A session last active before the range cannot contribute, so its
+/// transcript is not read.
+pub fn timeline_for_sessions(
+    sessions: &[SessionInfo],
+    zone: Zone,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Timeline {
+    let activity = sessions
+        .iter()
+        .filter(|s| zone.local(s.last_active).date() >= from)
+        .map(|s| (s, message_times(s)));
+    build(activity, zone, from, to)
+}
+
+#[derive(Default)]
+struct ProjectTally<'a> {
+    display: &'a str,
+    sessions: HashSet<&'a str>,
+    messages: usize,
+    days: BTreeSet<NaiveDate>,
+}
+
+/// Aggregates message times per session into a timeline of `from..=to`,
+/// bucketing by wall-clock day and hour in `zone`.
+pub fn build<'a>(
+    activity: impl IntoIterator<Item = (&'a SessionInfo, Vec<DateTime<Utc>>)>,
+    zone: Zone,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Timeline {
+    let mut days: BTreeMap<NaiveDate, (usize, HashSet<&str>)> = BTreeMap::new();
+    let mut hours = [[0; 24]; 7];
+    let mut projects: HashMap<String, ProjectTally> = HashMap::new();
+    let mut sessions = HashSet::new();
+    let mut total_messages = 0;
+
+    for (session, times) in activity {
+        for time in times {
+            let local = zone.local(time);
+            let date = local.date();
+            if date < from || date > to {
+                continue;
+            }
+            total_messages += 1;
+            sessions.insert(session.session_id.as_str());
+            let day = days.entry(date).or_default();
+            day.0 += 1;
+            day.1.insert(&session.session_id);
+            hours[date.weekday().num_days_from_monday() as usize][local.hour() as usize] += 1;
+
+            let project = projects
+                .entry(session.project_dir.to_string_lossy().into_owned())
+                .or_default();
+            project.display = &session.project_dir_display;
+            project.sessions.insert(&session.session_id);
+            project.messages += 1;
+            project.days.insert(date);
+        }
+    }
+
+    let active_days: BTreeSet<NaiveDate> = days.keys().copied().collect();
+    let mut projects: Vec<ProjectActivity> = projects
+        .into_iter()
+        .map(|(dir, tally)| ProjectActivity {
+            project_dir_display: if tally.display.is_empty() {
+                "(unknown)".to_string()
+            } else {
+                tally.display.to_string()
+            },
+            project_dir: dir,
+            sessions: tally.sessions.len(),
+            messages: tally.messages,
+            active_days: tally.days.len(),
+            first: *tally.days.first().expect("tallied projects have a day"),
+            last: *tally.days.last().expect("tallied projects have a day"),
+        })
+        .collect();
+    projects.sort_by(|a, b| {
+        b.messages
+            .cmp(&a.messages)
+            .then_with(|| a.project_dir_display.cmp(&b.project_dir_display))
+    });
+
+    Timeline {
+        from,
+        to,
+        total_messages,
+        total_sessions: sessions.len(),
+        days: days
+            .into_iter()
+            .map(|(date, (messages, sessions))| DayActivity {
+                date,
+                messages,
+                sessions: sessions.len(),
+            })
+            .collect(),
+        hours,
+        projects,
+        streaks: streaks(&active_days, to),
+    }
+}
+
+/// The longest run of consecutive active days (the earliest, on a tie) and
+/// the run still going at `today`.
+fn streaks(active: &BTreeSet<NaiveDate>, today: NaiveDate) -> Streaks {
+    let mut runs: Vec<Streak> = Vec::new();
+    for &date in active {
+        match runs.last_mut() {
+            Some(run) if run.end.succ_opt() == Some(date) => {
+                run.end = date;
+                run.days += 1;
+            }
+            _ => runs.push(Streak {
+                days: 1,
+                start: date,
+                end: date,
+            }),
+        }
+    }
+    let longest = runs
+        .iter()
+        .copied()
+        .reduce(|best, run| if run.days > best.days { run } else { best });
+    let current = runs
+        .last()
+        .copied()
+        .filter(|run| run.end == today || run.end.succ_opt() == Some(today));
+    Streaks { longest, current }
+}
+
+/// Heatmap level for `count` out of `max`: 0 for none, otherwise 1..=4 in
+/// proportion, so any activity at all is visible.
+fn level(count: usize, max: usize) -> usize {
+    if count == 0 || max == 0 {
+        0
+    } else {
+        (4 * count).div_ceil(max).clamp(1, 4)
+    }
+}
+
+impl Timeline {
+    fn messages_on(&self, date: NaiveDate) -> usize {
+        self.days
+            .binary_search_by_key(&date, |d| d.date)
+            .map_or(0, |i| self.days[i].messages)
+    }
+}
+
+/// The calendar heatmap: one column per week (Monday first), one row per
+/// weekday, with month names above the week they start in.
+fn calendar(timeline: &Timeline) -> String {
+    let start =
+        timeline.from - TimeDelta::days(timeline.from.weekday().num_days_from_monday() as i64);
+    let weeks = ((timeline.to - start).num_days() / 7 + 1) as usize;
+    let max = timeline.days.iter().map(|d| d.messages).max().unwrap_or(0);
+
+    // Month names go above the first week of each month, as long as they do
+    // not run into the previous name.
+    let mut months = String::new();
+    let mut previous_month = None;
+    for week in 0..weeks {
+        let first = (start + TimeDelta::weeks(week as i64)).max(timeline.from);
+        if previous_month != Some(first.month()) {
+            previous_month = Some(first.month());
+            if months.is_empty() || week > months.len() {
+                months.push_str(&" ".repeat(week - months.len()));
+                months.push_str(&first.format("%b").to_string());
+            }
+        }
+    }
+
+    let mut out = format!("     {}\n", months);
+    for (row, name) in WEEKDAYS.iter().enumerate() {
+        let cells: String = (0..weeks)
+            .map(|week| {
+                let date = start + TimeDelta::days((week * 7 + row) as i64);
+                if date < timeline.from || date > timeline.to {
+                    ' '
+                } else {
+                    LEVELS[level(timeline.messages_on(date), max)]
+                }
+            })
+            .collect();
+        out.push_str(&format!("{}  {}\n", name, cells.trim_end()));
+    }
+    out.push_str(&format!(
+        "     less {} more\n",
+        LEVELS.iter().collect::<String>()
+    ));
+    out
+}
+
+/// Weekday by hour of day, two characters per hour.
+fn hour_grid(timeline: &Timeline) -> String {
+    let max = timeline.hours.iter().flatten().copied().max().unwrap_or(0);
+    let mut out = String::from("     ");
+    for hour in (0..24).step_by(3) {
+        out.push_str(&format!("{:<6}", hour));
+    }
+    let mut out = out.trim_end().to_string();
+    out.push('\n');
+    for (row, name) in WEEKDAYS.iter().enumerate() {
+        let cells: String = timeline.hours[row]
+            .iter()
+            .map(|&count| LEVELS[level(count, max)].to_string().repeat(2))
+            .collect();
+        out.push_str(&format!("{}  {}\n", name, cells));
+    }
+    out
+}
+
+/// The timeline as text: totals, the calendar and hour heatmaps, the
+/// per-project breakdown, and streaks.
+pub fn format_timeline(timeline: &Timeline) -> String {
+    if timeline.total_messages == 0 {
+        return format!(
+            "No activity between {} and {}.\n",
+            timeline.from, timeline.to
+        );
+    }
+    let mut out = format!(
+        "Activity from {} to {}: {} in {}, {}\n\n",
+        timeline.from,
+        timeline.to,
+        display::plural(timeline.total_messages, "message"),
+        display::plural(timeline.total_sessions, "session"),
+        display::plural(timeline.days.len(), "active day")
+    );
+    out.push_str(&calendar(timeline));
+    out.push_str("\nBy hour of day\n");
+    out.push_str(&hour_grid(timeline));
+
+    out.push_str("\nProjects\n");
+    let shown = &timeline.projects[..timeline.projects.len().min(TOP_PROJECTS)];
+    let names: Vec<String> = shown
+        .iter()
+        .map(|p| display::shorten_middle(&p.project_dir_display, PROJECT_WIDTH))
+        .collect();
+    let name_width = names
+        .iter()
+        .map(|n| display::display_width(n))
+        .max()
+        .unwrap_or(0);
+    let messages_width = shown
+        .iter()
+        .map(|p| p.messages.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for (project, name) in shown.iter().zip(&names) {
+        let share = project.messages as f64 / timeline.total_messages as f64;
+        let bar = ((share * BAR_WIDTH as f64).round() as usize).max(1);
+        out.push_str(&format!(
+            "  {}  {:>w$} msgs  {:>3.0}%  {}  {}, {}\n",
+            display::pad(name, name_width, true),
+            project.messages,
+            share * 100.0,
+            display::pad(&"█".repeat(bar), BAR_WIDTH, true),
+            display::plural(project.sessions, "session"),
+            display::plural(project.active_days, "day"),
+            w = messages_width
+        ));
+    }
+    let hidden = timeline.projects.len() - shown.len();
+    if hidden > 0 {
+        out.push_str(&format!(
+            "  ...and {}\n",
+            display::plural(hidden, "more project")
+        ));
+    }
+
+    out.push('\n');
+    if let Some(longest) = timeline.streaks.longest {
+        out.push_str(&format!(
+            "Longest streak: {} ({} to {})\n",
+            display::plural(longest.days, "day"),
+            longest.start,
+            longest.end
+        ));
+    }
+    match timeline.streaks.current {
+        Some(current) => out.push_str(&format!(
+            "Current streak: {} (since {})\n",
+            display::plural(current.days, "day"),
+            current.start
+        )),
+        None => out.push_str("Current streak: none\n"),
+    }
+    out
+}
+
+pub fn format_timeline_json(timeline: &Timeline) -> String {
+    serde_json::to_string_pretty(timeline).expect("timeline serializes")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn session(id: &str, project: &str) -> SessionInfo {
+        SessionInfo {
+            path: PathBuf::from(format!("/nonexistent/{}.jsonl", id)),
+            project_dir_display: project.replace("/Users/testuser", "~"),
+            last_active: at("2026-02-17T12:00:00Z"),
+            ..SessionInfo::fixture(id, project)
+        }
+    }
+
+    fn times(specs: &[&str]) -> Vec<DateTime<Utc>> {
+        specs.iter().map(|s| at(s)).collect()
+    }
+
+    #[test]
+    fn aggregates_days_hours_and_projects() {
+        let api = session("s1", "/Users/testuser/dev/api");
+        let api2 = session("s2", "/Users/testuser/dev/api");
+        let web = session("s3", "/Users/testuser/dev/web");
+        let timeline = build(
+            [
+                (
+                    &api,
+                    times(&["2026-02-16T09:00:00Z", "2026-02-16T09:30:00Z"]),
+                ),
+                (&api2, times(&["2026-02-16T14:00:00Z"])),
+                (&web, times(&["2026-02-18T22:15:00Z"])),
+            ],
+            Zone::Utc,
+            date("2026-02-01"),
+            date("2026-02-18"),
+        );
+
+        assert_eq!(timeline.total_messages, 4);
+        assert_eq!(timeline.total_sessions, 3);
+        assert_eq!(
+            timeline.days,
+            vec![
+                DayActivity {
+                    date: date("2026-02-16"),
+                    messages: 3,
+                    sessions: 2
+                },
+                DayActivity {
+                    date: date("2026-02-18"),
+                    messages: 1,
+                    sessions: 1
+                },
+            ]
+        );
+        // 2026-02-16 is a Monday.
+        assert_eq!(timeline.hours[0][9], 2);
+        assert_eq!(timeline.hours[0][14], 1);
+        assert_eq!(timeline.hours[2][22], 1);
+
+        assert_eq!(timeline.projects[0].project_dir_display, "~/dev/api");
+        assert_eq!(timeline.projects[0].sessions, 2);
+        assert_eq!(timeline.projects[0].messages, 3);
+        assert_eq!(timeline.projects[0].active_days, 1);
+        assert_eq!(timeline.projects[1].project_dir_display, "~/dev/web");
+        assert_eq!(timeline.projects[1].first, date("2026-02-18"));
+    }
+
+    #[test]
+    fn buckets_by_wall_clock_time_in_zone() {
+        let s = session("s1", "/p");
+        let timeline = build(
+            [(&s, times(&["2026-02-15T20:00:00Z"]))],
+            Zone::parse("+05:30").unwrap(),
+            date("2026-02-01"),
+            date("2026-02-28"),
+        );
+        assert_eq!(timeline.days[0].date, date("2026-02-16"));
+        assert_eq!(timeline.hours[0][1], 1);
+    }
+
+    #[test]
+    fn ignores_messages_outside_range() {
+        let s = session("s1", "/p");
+        let timeline = build(
+            [(&s, times(&["2026-01-31T23:59:59Z", "2026-02-01T00:00:00Z"]))],
+            Zone::Utc,
+            date("2026-02-01"),
+            date("2026-02-28"),
+        );
+        assert_eq!(timeline.total_messages, 1);
+        assert_eq!(timeline.projects[0].first, date("2026-02-01"));
+    }
+
+    #[test]
+    fn longest_and_current_streaks() {
+        let active: BTreeSet<NaiveDate> = [
+            "2026-02-01",
+            "2026-02-02",
+            "2026-02-03",
+            "2026-02-05",
+            "2026-02-09",
+            "2026-02-10",
+        ]
+        .iter()
+        .map(|d| date(d))
+        .collect();
+
+        let s = streaks(&active, date("2026-02-11"));
+        assert_eq!(
+            s.longest,
+            Some(Streak {
+                days: 3,
+                start: date("2026-02-01"),
+                end: date("2026-02-03")
+            })
+        );
+        // Today has nothing yet, but yesterday's run is still alive.
+        assert_eq!(s.current.map(|c| c.days), Some(2));
+
+        assert_eq!(streaks(&active, date("2026-02-12")).current, None);
+        assert_eq!(streaks(&BTreeSet::new(), date("2026-02-12")).longest, None);
+    }
+
+    #[test]
+    fn levels_scale_to_busiest() {
+        assert_eq!(level(0, 10), 0);
+        assert_eq!(level(1, 10), 1);
+        assert_eq!(level(5, 10), 2);
+        assert_eq!(level(8, 10), 4);
+        assert_eq!(level(10, 10), 4);
+    }
+
+    #[test]
+    fn calendar_grid_layout() {
+        let s = session("s1", "/p");
+        // Wednesday 2026-01-07 to Wednesday 2026-02-11: the weeks of Jan 5
+        // to Feb 9.
+        let timeline = build(
+            [(
+                &s,
+                times(&[
+                    "2026-01-07T10:00:00Z",
+                    "2026-02-02T10:00:00Z",
+                    "2026-02-02T11:00:00Z",
+                ]),
+            )],
+            Zone::Utc,
+            date("2026-01-07"),
+            date("2026-02-11"),
+        );
+        let grid = calendar(&timeline);
+        let lines: Vec<&str> = grid.lines().collect();
+        assert_eq!(lines[0], "     Jan Feb");
+        assert_eq!(lines[1], "Mon   ···█·");
+        assert_eq!(lines[3], "Wed  ▒·····");
+        assert_eq!(lines[4], "Thu  ·····");
+        assert_eq!(lines[8], "     less ·░▒▓█ more");
+    }
+
+    #[test]
+    fn formats_summary_projects_and_streaks() {
+        let api = session("s1", "/Users/testuser/dev/api");
+        let web = session("s2", "/Users/testuser/dev/web");
+        let timeline = build(
+            [
+                (
+                    &api,
+                    times(&[
+                        "2026-02-16T09:00:00Z",
+                        "2026-02-17T09:00:00Z",
+                        "2026-02-17T10:00:00Z",
+                    ]),
+                ),
+                (&web, times(&["2026-02-18T09:00:00Z"])),
+            ],
+            Zone::Utc,
+            date("2026-02-01"),
+            date("2026-02-18"),
+        );
+        let out = format_timeline(&timeline);
+        assert!(out.starts_with(
+            "Activity from 2026-02-01 to 2026-02-18: 4 messages in 2 sessions, 3 active days\n"
+        ));
+        assert!(
+            out.contains("\nBy hour of day\n     0     3     6     9     12    15    18    21\n")
+        );
+        assert!(out.contains(&format!(
+            "  ~/dev/api  3 msgs   75%  {}  1 session, 2 days\n",
+            display::pad(&"█".repeat(15), BAR_WIDTH, true)
+        )));
+        assert!(out.contains("  ~/dev/web  1 msgs   25%  █████"));
+        assert!(out.contains("Longest streak: 3 days (2026-02-16 to 2026-02-18)\n"));
+        assert!(out.ends_with("Current streak: 3 days (since 2026-02-16)\n"));
+    }
+
+    #[test]
+    fn summarises_projects_beyond_top() {
+        let sessions: Vec<SessionInfo> = (0..12)
+            .map(|i| session(&format!("s{}", i), &format!("/p/{}", i)))
+            .collect();
+        let timeline = build(
+            sessions
+                .iter()
+                .map(|s| (s, times(&["2026-02-16T09:00:00Z"]))),
+            Zone::Utc,
+            date("2026-02-01"),
+            date("2026-02-18"),
+        );
+        let out = format_timeline(&timeline);
+        assert!(out.contains("  ...and 2 more projects\n"));
+        assert!(out.ends_with("Current streak: none\n"));
+    }
+
+    #[test]
+    fn empty_timeline() {
+        let timeline = build([], Zone::Utc, date("2026-02-01"), date("2026-02-18"));
+        assert_eq!(
+            format_timeline(&timeline),
+            "No activity between 2026-02-01 and 2026-02-18.\n"
+        );
+    }
+
+    #[test]
+    fn json_shape() {
+        let s = session("s1", "/p");
+        let timeline = build(
+            [(&s, times(&["2026-02-16T09:00:00Z"]))],
+            Zone::Utc,
+            date("2026-02-01"),
+            date("2026-02-18"),
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&format_timeline_json(&timeline)).unwrap();
+        assert_eq!(json["from"], "2026-02-01");
+        assert_eq!(json["days"][0]["date"], "2026-02-16");
+        assert_eq!(json["hours"].as_array().unwrap().len(), 7);
+        assert_eq!(json["hours"][0][9], 1);
+        assert_eq!(json["projects"][0]["project_dir"], "/p");
+        assert_eq!(json["streaks"]["longest"]["days"], 1);
+        assert!(json["streaks"]["current"].is_null());
+    }
+
+    #[test]
+    fn reads_message_timestamps() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.child("s.jsonl");
+        file.write_str(concat!(
+            r#"{"type":"system","timestamp":"2026-02-16T08:00:00Z"}"#,
+            "\n",
+            r#"{"type":"user","isMeta":true,"timestamp":"2026-02-16T08:00:01Z"}"#,
+            "\n",
+            r#"{"type":"user","timestamp":"2026-02-16T09:00:00.000Z"}"#,
+            "\n",
+            r#"{"type":"assistant","timestamp":"2026-02-16T09:00:05.500Z"}"#,
+            "\n",
+            "not json\n",
+        ))
+        .unwrap();
+        let mut s = session("s", "/p");
+        s.path = file.path().to_path_buf();
+        assert_eq!(
+            message_times(&s),
+            times(&["2026-02-16T09:00:00Z", "2026-02-16T09:00:05.500Z"])
+        );
+    }
+
+    #[test]
+    fn untimed_messages_fall_back_to_mtime() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.child("s.jsonl");
+        file.write_str("{\"type\":\"user\"}\n{\"type\":\"assistant\"}\n")
+            .unwrap();
+        let mut s = session("s", "/p");
+        s.path = file.path().to_path_buf();
+        assert_eq!(message_times(&s), vec![s.last_active; 2]);
+    }
+}
@@ -0,0 +1,292 @@
+use std::io::BufRead;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::errors::CcseshError;
+use crate::thread::{BranchKind, ConversationGraph};
+use crate::types::TranscriptRecord;
+
+/// Who wrote a transcript message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+impl Role {
+    fn heading(self) -> &'static str {
+        match self {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+        }
+    }
+}
+
+/// One conversational turn with its text content.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptMessage {
+    pub role: Role,
+    /// Raw ISO 8601 timestamp from the JSONL line, if present.
+    pub timestamp: Option<String>,
+    pub text: String,
+}
+
+/// Read the conversation the user ended up on from a session JSONL file.
+///
+/// Follows the main thread of the `uuid` / `parentUuid` tree (see
+/// `thread::ConversationGraph`), so edited prompts, retried responses and
+/// sidechains are left out. Keeps user and assistant messages that carry
+/// text. Meta lines, compact summaries, and non-text content (tool calls,
+/// tool results, images, thinking) are dropped. Malformed lines are skipped,
+/// as in `parse_session`.
+pub fn read_transcript(path: &Path) -> Result<Vec<TranscriptMessage>> {
+    let graph = ConversationGraph::new(read_records(path)?);
+    Ok(messages_at(graph.records(), &graph.main_thread()))
+}
+
+/// A branch off the main thread, as messages.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BranchTranscript {
+    pub kind: BranchKind,
+    /// `uuid` of the main-thread record the branch splits from, if known.
+    pub fork_uuid: Option<String>,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+/// Read the main thread and every branch off it. Branches without any text
+/// message (e.g. a sidechain of tool calls only) are dropped.
+pub fn read_thread(path: &Path) -> Result<(Vec<TranscriptMessage>, Vec<BranchTranscript>)> {
+    let graph = ConversationGraph::new(read_records(path)?);
+    let records = graph.records();
+    let messages = messages_at(records, &graph.main_thread());
+    let branches = graph
+        .branches()
+        .into_iter()
+        .map(|branch| BranchTranscript {
+            kind: branch.kind,
+            fork_uuid: branch
+                .fork
+                .and_then(|i| records[i].meta())
+                .and_then(|m| m.uuid.clone()),
+            messages: messages_at(records, &branch.records),
+        })
+        .filter(|branch| !branch.messages.is_empty())
+        .collect();
+    Ok((messages, branches))
+}
+
+/// Read every well-formed record from a session JSONL file, in file order.
+pub fn read_records(path: &Path) -> Result<Vec<TranscriptRecord>> {
+    let file = std::fs::File::open(path).map_err(|source| CcseshError::SessionReadError {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let reader = std::io::BufReader::new(file);
+
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let Ok(line) = line else { continue };
+        if let Ok(record) = TranscriptRecord::parse(&line) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// The messages among `records` at the given indices, in that order.
+pub fn messages_at(records: &[TranscriptRecord], indices: &[usize]) -> Vec<TranscriptMessage> {
+    indices
+        .iter()
+        .filter_map(|&i| message_from_record(&records[i]))
+        .collect()
+}
+
+/// The text message a record carries, if it is a user or assistant turn.
+pub fn message_from_record(record: &TranscriptRecord) -> Option<TranscriptMessage> {
+    let (role, entry) = match record {
+        TranscriptRecord::User(entry) => (Role::User, entry),
+        TranscriptRecord::Assistant(entry) => (Role::Assistant, entry),
+        _ => return None,
+    };
+    if entry.is_meta == Some(true) || entry.is_compact_summary == Some(true) {
+        return None;
+    }
+
+    let text = entry.message.content.text()?;
+    Some(TranscriptMessage {
+        role,
+        timestamp: entry.meta.timestamp.clone(),
+        text,
+    })
+}
+
+/// Render messages as Markdown, one `## User` / `## Assistant` section each.
+pub fn render_markdown(messages: &[TranscriptMessage]) -> String {
+    let mut out = String::new();
+    for message in messages {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        match &message.timestamp {
+            Some(ts) => out.push_str(&format!("## {} ({})\n\n", message.role.heading(), ts)),
+            None => out.push_str(&format!("## {}\n\n", message.role.heading())),
+        }
+        out.push_str(&message.text);
+        out.push('\n');
+    }
+    out
+}
+
+/// Render branches as Markdown sections after a `---` rule, each headed by
+/// its kind and fork point. Empty when there are no branches.
+pub fn render_branches_markdown(branches: &[BranchTranscript]) -> String {
+    let mut out = String::new();
+    for branch in branches {
+        out.push_str("\n---\n\n");
+        let kind = match branch.kind {
+            BranchKind::Abandoned => "Abandoned branch",
+            BranchKind::Sidechain => "Sidechain",
+        };
+        match &branch.fork_uuid {
+            Some(uuid) => out.push_str(&format!("# {} (from {})\n\n", kind, uuid)),
+            None => out.push_str(&format!("# {}\n\n", kind)),
+        }
+        out.push_str(&render_markdown(&branch.messages));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn reads_user_and_assistant_turns() {
+        let messages = read_transcript(&fixture("normal.jsonl")).unwrap();
+        let roles: Vec<Role> = messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            vec![Role::User, Role::Assistant, Role::User, Role::Assistant]
+        );
+        assert_eq!(messages[0].text, "Design technical approach for ccsesh");
+        assert_eq!(
+            messages[0].timestamp.as_deref(),
+            Some("2026-02-17T22:24:18.102Z")
+        );
+    }
+
+    #[test]
+    fn skips_meta_and_compact_summary() {
+        let meta = read_transcript(&fixture("meta_only.jsonl")).unwrap();
+        assert!(
+            meta.iter()
+                .all(|m| !m.text.contains("local-command-caveat"))
+        );
+
+        let compact = read_transcript(&fixture("compact_summary.jsonl")).unwrap();
+        assert!(
+            compact
+                .iter()
+                .all(|m| !m.text.starts_with("This session is being continued"))
+        );
+    }
+
+    #[test]
+    fn keeps_text_blocks_and_drops_tool_results_and_images() {
+        let messages = read_transcript(&fixture("array_content.jsonl")).unwrap();
+        assert_eq!(messages[0].text, "Array content test prompt");
+
+        let messages = read_transcript(&fixture("image_paste.jsonl")).unwrap();
+        assert_eq!(messages[0].text, "Describe this image");
+        assert!(messages.iter().all(|m| !m.text.contains("iVBORw0KGgo")));
+    }
+
+    #[test]
+    fn missing_file_is_read_error() {
+        let err = read_transcript(Path::new("/nonexistent/session.jsonl")).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(CcseshError::SessionReadError { .. })
+        ));
+    }
+
+    #[test]
+    fn render_markdown_sections() {
+        let messages = vec![
+            TranscriptMessage {
+                role: Role::User,
+                timestamp: Some("2026-02-17T22:24:18.102Z".to_string()),
+                text: "hi".to_string(),
+            },
+            TranscriptMessage {
+                role: Role::Assistant,
+                timestamp: None,
+                text: "hello".to_string(),
+            },
+        ];
+        assert_eq!(
+            render_markdown(&messages),
+            "## User (2026-02-17T22:24:18.102Z)\n\nhi\n\n## Assistant\n\nhello\n"
+        );
+    }
+
+    #[test]
+    fn follows_main_thread_of_branching_session() {
+        let messages = read_transcript(&fixture("branching.jsonl")).unwrap();
+        assert_eq!(messages[0].text, "Write a haiku about Rust");
+        assert!(
+            messages
+                .iter()
+                .all(|m| !m.text.contains("Borrowed, never owned"))
+        );
+    }
+
+    #[test]
+    fn read_thread_collects_branches() {
+        let (messages, branches) = read_thread(&fixture("branching.jsonl")).unwrap();
+        assert_eq!(
+            messages,
+            read_transcript(&fixture("branching.jsonl")).unwrap()
+        );
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].kind, BranchKind::Abandoned);
+        assert_eq!(
+            branches[0].fork_uuid.as_deref(),
+            Some("a0000002-0000-4000-8000-000000000002")
+        );
+        assert!(
+            branches[0].messages[1]
+                .text
+                .contains("Borrowed, never owned")
+        );
+        assert_eq!(branches[1].kind, BranchKind::Sidechain);
+    }
+
+    #[test]
+    fn render_branches_markdown_headings() {
+        let branches = vec![BranchTranscript {
+            kind: BranchKind::Abandoned,
+            fork_uuid: Some("abc".to_string()),
+            messages: vec![TranscriptMessage {
+                role: Role::User,
+                timestamp: None,
+                text: "retry".to_string(),
+            }],
+        }];
+        assert_eq!(
+            render_branches_markdown(&branches),
+            "\n---\n\n# Abandoned branch (from abc)\n\n## User\n\nretry\n"
+        );
+        assert_eq!(render_branches_markdown(&[]), "");
+    }
+}
@@ -4,6 +4,7 @@ use std::time::SystemTime;
 use chrono::{DateTime, Utc};
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 /// Cheap stat-only candidate before parsing
 #[derive(Debug, Clone)]
@@ -14,6 +15,7 @@ pub struct SessionCandidate {
 
 /// Fully parsed session metadata
 #[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(Default))]
 pub struct SessionInfo {
     pub session_id: String,
     pub path: PathBuf,
@@ -22,6 +24,70 @@ pub struct SessionInfo {
     pub last_active: DateTime<Utc>,
     pub first_prompt: Option<String>,
     pub slug: Option<String>,
+    /// Whether a running Claude Code process appears to have this session open.
+    pub active: bool,
+    /// PID of that process, when known (absent for the mtime heuristic).
+    pub active_pid: Option<u32>,
+    /// How `active` was decided, when it is set.
+    pub active_match: Option<ActiveMatch>,
+    /// Pointers to earlier sessions found at the top of the transcript.
+    pub continuation: ContinuationRefs,
+    /// Earlier sessions this one continues, oldest first. Filled in by
+    /// `chain::ChainLinker`; empty for sessions that start fresh.
+    pub earlier: Vec<SessionInfo>,
+    /// Older sessions in the same project that started with the same or a
+    /// near-identical prompt, newest first. Filled in by `dedupe::collapse`
+    /// for the listing; empty elsewhere.
+    pub duplicates: Vec<SessionInfo>,
+}
+
+/// How a session was found to be open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveMatch {
+    /// A running process names it with `--resume <id>` / `--session-id <id>`.
+    SessionId,
+    /// A running process without a session ID has the session's project dir
+    /// as its cwd, and this is the newest unclaimed session there.
+    Cwd,
+    /// Modified within `active::ACTIVE_WINDOW_SECS`; no process scan.
+    RecentlyModified,
+}
+
+/// References from a session to the one it continues, as written by Claude
+/// Code when a conversation is resumed or carried over after compaction.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ContinuationRefs {
+    /// `sessionId`s other than the file's own, on records carried over from
+    /// an earlier session.
+    pub session_ids: Vec<String>,
+    /// Record uuids defined in an earlier session: `leafUuid` of summary
+    /// records, and `parentUuid` / `logicalParentUuid` values that point
+    /// outside this file.
+    pub uuids: Vec<String>,
+}
+
+#[cfg(test)]
+impl SessionInfo {
+    /// A session for tests: `id` in project `dir`, last active now, with its
+    /// transcript at `/tmp/<id>.jsonl` and nothing else set. Tests fill in
+    /// the rest with struct update syntax.
+    pub(crate) fn fixture(id: &str, dir: &str) -> Self {
+        SessionInfo {
+            session_id: id.to_string(),
+            path: PathBuf::from(format!("/tmp/{}.jsonl", id)),
+            project_dir: PathBuf::from(dir),
+            project_dir_display: dir.to_string(),
+            last_active: Utc::now(),
+            ..Default::default()
+        }
+    }
+}
+
+impl ContinuationRefs {
+    pub fn is_empty(&self) -> bool {
+        self.session_ids.is_empty() && self.uuids.is_empty()
+    }
 }
 
 /// Represents a single line in the JSONL file (loosely typed).
@@ -33,6 +99,13 @@ pub struct JsonlLine {
     #[serde(rename = "sessionId")]
     pub session_id: Option<String>,
     pub timestamp: Option<String>,
+    pub uuid: Option<String>,
+    #[serde(rename = "parentUuid")]
+    pub parent_uuid: Option<String>,
+    #[serde(rename = "logicalParentUuid")]
+    pub logical_parent_uuid: Option<String>,
+    #[serde(rename = "leafUuid")]
+    pub leaf_uuid: Option<String>,
     #[serde(rename = "isMeta")]
     pub is_meta: Option<bool>,
     #[serde(rename = "isCompactSummary")]
@@ -45,15 +118,471 @@ pub struct JsonlLine {
     pub message: Option<JsonlMessage>,
 }
 
+impl JsonlLine {
+    /// The record's `timestamp`, if present and RFC 3339.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        parse_time(self.timestamp.as_deref())
+    }
+}
+
+fn parse_time(timestamp: Option<&str>) -> Option<DateTime<Utc>> {
+    timestamp
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct JsonlMessage {
     pub content: Option<serde_json::Value>,
 }
 
+// ---- Typed transcript model ----
+//
+// `JsonlLine` above is the deliberately minimal view used by the listing hot
+// path. The types below model whole transcript records for features that need
+// more (transcripts, search, exports). Every struct keeps unrecognised fields
+// in `extra`, and unknown record or block types are kept as raw JSON, so a
+// parsed record serializes back to the JSON it came from.
+
+/// One line of a Claude Code transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawRecord", into = "RawRecord")]
+pub enum TranscriptRecord {
+    User(MessageEntry),
+    Assistant(MessageEntry),
+    System(SystemEntry),
+    Summary(SummaryEntry),
+    /// A record of a type this model does not know, or one whose known type
+    /// did not match the expected shape. Kept verbatim.
+    Other(Value),
+}
+
+impl TranscriptRecord {
+    /// Parse one JSONL line. Fails only if the line is not JSON.
+    pub fn parse(line: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(line)
+    }
+
+    /// Linkage and context fields, for user, assistant and system records.
+    pub fn meta(&self) -> Option<&EntryMeta> {
+        match self {
+            TranscriptRecord::User(e) | TranscriptRecord::Assistant(e) => Some(&e.meta),
+            TranscriptRecord::System(e) => Some(&e.meta),
+            TranscriptRecord::Summary(_) | TranscriptRecord::Other(_) => None,
+        }
+    }
+}
+
+// Only exists transiently during (de)serialization, so its size is irrelevant.
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawRecord {
+    Known(KnownRecord),
+    Other(Value),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum KnownRecord {
+    User(MessageEntry),
+    Assistant(MessageEntry),
+    System(SystemEntry),
+    Summary(SummaryEntry),
+}
+
+impl From<RawRecord> for TranscriptRecord {
+    fn from(raw: RawRecord) -> Self {
+        match raw {
+            RawRecord::Known(KnownRecord::User(e)) => TranscriptRecord::User(e),
+            RawRecord::Known(KnownRecord::Assistant(e)) => TranscriptRecord::Assistant(e),
+            RawRecord::Known(KnownRecord::System(e)) => TranscriptRecord::System(e),
+            RawRecord::Known(KnownRecord::Summary(e)) => TranscriptRecord::Summary(e),
+            RawRecord::Other(v) => TranscriptRecord::Other(v),
+        }
+    }
+}
+
+impl From<TranscriptRecord> for RawRecord {
+    fn from(record: TranscriptRecord) -> Self {
+        match record {
+            TranscriptRecord::User(e) => RawRecord::Known(KnownRecord::User(e)),
+            TranscriptRecord::Assistant(e) => RawRecord::Known(KnownRecord::Assistant(e)),
+            TranscriptRecord::System(e) => RawRecord::Known(KnownRecord::System(e)),
+            TranscriptRecord::Summary(e) => RawRecord::Known(KnownRecord::Summary(e)),
+            TranscriptRecord::Other(v) => RawRecord::Other(v),
+        }
+    }
+}
+
+/// Fields shared by user, assistant and system records.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    /// `Some(None)` for an explicit `null` (the first record of a session).
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "nullable")]
+    pub parent_uuid: Option<Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_sidechain: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Claude Code version that wrote the record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+}
+
+impl EntryMeta {
+    /// The record's `timestamp`, or `fallback` when it is missing or not
+    /// RFC 3339.
+    pub fn time_or(&self, fallback: DateTime<Utc>) -> DateTime<Utc> {
+        parse_time(self.timestamp.as_deref()).unwrap_or(fallback)
+    }
+}
+
+/// A `user` or `assistant` record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageEntry {
+    #[serde(flatten)]
+    pub meta: EntryMeta,
+    pub message: Message,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_meta: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_compact_summary: Option<bool>,
+    /// Structured tool output attached to tool-result user records.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use_result: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A `system` record (session start, hooks, errors).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemEntry {
+    #[serde(flatten)]
+    pub meta: EntryMeta,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_meta: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Message>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A `summary` record naming the leaf message it summarises.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryEntry {
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_uuid: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The API message carried by a user or assistant record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub content: MessageContent,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "nullable")]
+    pub stop_reason: Option<Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "nullable")]
+    pub stop_sequence: Option<Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Message content: a bare string or a list of content blocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Blocks(Vec<ContentBlock>),
+}
+
+impl MessageContent {
+    /// All text, with multiple text blocks separated by a blank line.
+    /// Returns `None` when there is no non-blank text.
+    pub fn text(&self) -> Option<String> {
+        let text = match self {
+            MessageContent::Text(s) => s.trim().to_string(),
+            MessageContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|b| match b {
+                    ContentBlock::Text(t) => Some(t.text.trim()),
+                    _ => None,
+                })
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        };
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// The content blocks; a bare string has none.
+    pub fn blocks(&self) -> &[ContentBlock] {
+        match self {
+            MessageContent::Text(_) => &[],
+            MessageContent::Blocks(blocks) => blocks,
+        }
+    }
+}
+
+/// Token accounting reported with assistant messages.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_input_tokens: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One block of structured message content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawBlock", into = "RawBlock")]
+pub enum ContentBlock {
+    Text(TextBlock),
+    Image(ImageBlock),
+    ToolUse(ToolUseBlock),
+    ToolResult(ToolResultBlock),
+    Thinking(ThinkingBlock),
+    /// A block type this model does not know. Kept verbatim.
+    Other(Value),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawBlock {
+    Known(KnownBlock),
+    Other(Value),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum KnownBlock {
+    Text(TextBlock),
+    Image(ImageBlock),
+    ToolUse(ToolUseBlock),
+    ToolResult(ToolResultBlock),
+    Thinking(ThinkingBlock),
+}
+
+impl From<RawBlock> for ContentBlock {
+    fn from(raw: RawBlock) -> Self {
+        match raw {
+            RawBlock::Known(KnownBlock::Text(b)) => ContentBlock::Text(b),
+            RawBlock::Known(KnownBlock::Image(b)) => ContentBlock::Image(b),
+            RawBlock::Known(KnownBlock::ToolUse(b)) => ContentBlock::ToolUse(b),
+            RawBlock::Known(KnownBlock::ToolResult(b)) => ContentBlock::ToolResult(b),
+            RawBlock::Known(KnownBlock::Thinking(b)) => ContentBlock::Thinking(b),
+            RawBlock::Other(v) => ContentBlock::Other(v),
+        }
+    }
+}
+
+impl From<ContentBlock> for RawBlock {
+    fn from(block: ContentBlock) -> Self {
+        match block {
+            ContentBlock::Text(b) => RawBlock::Known(KnownBlock::Text(b)),
+            ContentBlock::Image(b) => RawBlock::Known(KnownBlock::Image(b)),
+            ContentBlock::ToolUse(b) => RawBlock::Known(KnownBlock::ToolUse(b)),
+            ContentBlock::ToolResult(b) => RawBlock::Known(KnownBlock::ToolResult(b)),
+            ContentBlock::Thinking(b) => RawBlock::Known(KnownBlock::Thinking(b)),
+            ContentBlock::Other(v) => RawBlock::Other(v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextBlock {
+    pub text: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageBlock {
+    pub source: ImageSource,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageSource {
+    /// `base64` or `url`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUseBlock {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolResultBlock {
+    pub tool_use_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<MessageContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_error: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThinkingBlock {
+    pub thinking: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Serde helper for `Option<Option<T>>` fields: a missing key is `None`, an
+/// explicit `null` is `Some(None)`. Pair with `skip_serializing_if`.
+mod nullable {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+
+    pub fn serialize<S, T>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        match value {
+            Some(inner) => inner.serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
 #[derive(Clone, ValueEnum)]
 pub enum OutputFormat {
     Default,
     Short,
+    /// One JSON object per line
+    Ndjson,
+    Csv,
+    Tsv,
+    /// Markdown (report only)
+    #[value(alias = "markdown")]
+    Md,
+}
+
+/// A column of the machine-readable formats. Names and order match the
+/// fields of `display::JsonSession`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum SessionField {
+    Index,
+    SessionId,
+    ProjectDir,
+    ProjectDirDisplay,
+    LastActive,
+    LastActiveRelative,
+    FirstPrompt,
+    Slug,
+    Active,
+    ContinuedFrom,
+    Duplicates,
+    ResumeCommand,
+}
+
+impl SessionField {
+    pub const ALL: [SessionField; 12] = [
+        SessionField::Index,
+        SessionField::SessionId,
+        SessionField::ProjectDir,
+        SessionField::ProjectDirDisplay,
+        SessionField::LastActive,
+        SessionField::LastActiveRelative,
+        SessionField::FirstPrompt,
+        SessionField::Slug,
+        SessionField::Active,
+        SessionField::ContinuedFrom,
+        SessionField::Duplicates,
+        SessionField::ResumeCommand,
+    ];
+
+    /// The JSON key, which is also the CSV/TSV header.
+    pub fn name(self) -> &'static str {
+        match self {
+            SessionField::Index => "index",
+            SessionField::SessionId => "session_id",
+            SessionField::ProjectDir => "project_dir",
+            SessionField::ProjectDirDisplay => "project_dir_display",
+            SessionField::LastActive => "last_active",
+            SessionField::LastActiveRelative => "last_active_relative",
+            SessionField::FirstPrompt => "first_prompt",
+            SessionField::Slug => "slug",
+            SessionField::Active => "active",
+            SessionField::ContinuedFrom => "continued_from",
+            SessionField::Duplicates => "duplicates",
+            SessionField::ResumeCommand => "resume_command",
+        }
+    }
 }
 
 /// Wraps a string in single quotes, escaping internal single quotes as `'\''`.
@@ -77,6 +606,194 @@ pub fn shell_escape_single_quote(s: &str) -> String {
 mod tests {
     use super::*;
 
+    fn fixture_lines(name: &str) -> Vec<String> {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn parse_fixture(name: &str) -> Vec<TranscriptRecord> {
+        fixture_lines(name)
+            .iter()
+            .map(|l| TranscriptRecord::parse(l).unwrap())
+            .collect()
+    }
+
+    // ---- Typed transcript model tests ----
+
+    #[test]
+    fn every_fixture_round_trips() {
+        let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let name = entry.unwrap().file_name().into_string().unwrap();
+            for line in fixture_lines(&name) {
+                // Malformed lines (truncated.jsonl) are not JSON at all.
+                let Ok(original) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                let record = TranscriptRecord::parse(&line).unwrap();
+                let reserialized = serde_json::to_value(&record).unwrap();
+                assert_eq!(
+                    reserialized, original,
+                    "round trip changed {}: {}",
+                    name, line
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn known_records_are_typed_in_fixtures() {
+        for name in ["normal.jsonl", "array_content.jsonl", "image_paste.jsonl"] {
+            for record in parse_fixture(name) {
+                assert!(
+                    !matches!(record, TranscriptRecord::Other(_)),
+                    "{} has an untyped record: {:?}",
+                    name,
+                    record
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn typed_assistant_record() {
+        let records = parse_fixture("tool_use.jsonl");
+        let TranscriptRecord::Assistant(entry) = &records[2] else {
+            panic!("expected assistant record, got {:?}", records[2]);
+        };
+        assert_eq!(
+            entry.meta.parent_uuid,
+            Some(Some("11111111-1111-4111-8111-111111111111".to_string()))
+        );
+        assert_eq!(entry.meta.version.as_deref(), Some("2.1.3"));
+        assert_eq!(entry.request_id.as_deref(), Some("req_01A"));
+        assert_eq!(entry.message.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(
+            entry.message.stop_reason,
+            Some(Some("tool_use".to_string()))
+        );
+        assert_eq!(entry.message.stop_sequence, Some(None));
+
+        let usage = entry.message.usage.as_ref().unwrap();
+        assert_eq!(usage.input_tokens, Some(1200));
+        assert_eq!(usage.cache_read_input_tokens, Some(9000));
+        assert_eq!(usage.extra["service_tier"], "standard");
+
+        let blocks = entry.message.content.blocks();
+        assert!(matches!(&blocks[0], ContentBlock::Thinking(t) if t.signature.is_some()));
+        assert!(matches!(&blocks[1], ContentBlock::Text(t) if t.text.starts_with("Let me look")));
+        let ContentBlock::ToolUse(tool) = &blocks[2] else {
+            panic!("expected tool_use");
+        };
+        assert_eq!(tool.name, "Read");
+        assert_eq!(
+            tool.input["file_path"],
+            "/Users/testuser/dev/paginate/src/users.rs"
+        );
+    }
+
+    #[test]
+    fn typed_user_and_tool_result_records() {
+        let records = parse_fixture("tool_use.jsonl");
+        let TranscriptRecord::User(first) = &records[1] else {
+            panic!("expected user record");
+        };
+        assert_eq!(first.meta.parent_uuid, Some(None));
+        assert_eq!(first.meta.is_sidechain, Some(false));
+        assert_eq!(first.meta.git_branch.as_deref(), Some("main"));
+        assert!(first.extra.contains_key("thinkingMetadata"));
+
+        let TranscriptRecord::User(result) = &records[3] else {
+            panic!("expected user record");
+        };
+        let ContentBlock::ToolResult(block) = &result.message.content.blocks()[0] else {
+            panic!("expected tool_result");
+        };
+        assert_eq!(block.tool_use_id, "toolu_01X");
+        assert!(result.tool_use_result.is_some());
+
+        let TranscriptRecord::User(sidechain) = &records[5] else {
+            panic!("expected user record");
+        };
+        assert_eq!(sidechain.meta.is_sidechain, Some(true));
+    }
+
+    #[test]
+    fn system_summary_and_unknown_records() {
+        let records = parse_fixture("tool_use.jsonl");
+        assert!(
+            matches!(&records[0], TranscriptRecord::Other(v) if v["type"] == "file-history-snapshot")
+        );
+
+        let TranscriptRecord::System(system) = &records[6] else {
+            panic!("expected system record");
+        };
+        assert_eq!(system.subtype.as_deref(), Some("compact_boundary"));
+        assert!(system.extra.contains_key("compactMetadata"));
+        assert!(records[6].meta().is_some());
+
+        let TranscriptRecord::Summary(summary) = &records[7] else {
+            panic!("expected summary record");
+        };
+        assert_eq!(
+            summary.leaf_uuid.as_deref(),
+            Some("44444444-4444-4444-8444-444444444444")
+        );
+        assert!(records[7].meta().is_none());
+    }
+
+    #[test]
+    fn unknown_block_type_kept_verbatim() {
+        let records = parse_fixture("tool_use.jsonl");
+        let TranscriptRecord::Assistant(entry) = &records[4] else {
+            panic!("expected assistant record");
+        };
+        assert!(matches!(
+            &entry.message.content.blocks()[0],
+            ContentBlock::Other(v) if v["type"] == "redacted_thinking"
+        ));
+    }
+
+    #[test]
+    fn image_block_is_typed() {
+        let records = parse_fixture("image_paste.jsonl");
+        let TranscriptRecord::User(entry) = &records[1] else {
+            panic!("expected user record");
+        };
+        let ContentBlock::Image(image) = &entry.message.content.blocks()[0] else {
+            panic!("expected image block");
+        };
+        assert_eq!(image.source.kind, "base64");
+        assert_eq!(image.source.media_type.as_deref(), Some("image/png"));
+    }
+
+    #[test]
+    fn mismatched_known_type_falls_back_to_other() {
+        let record = TranscriptRecord::parse(r#"{"type":"user","message":42}"#).unwrap();
+        assert!(matches!(record, TranscriptRecord::Other(_)));
+        assert!(TranscriptRecord::parse("not json").is_err());
+    }
+
+    #[test]
+    fn message_content_text() {
+        let content: MessageContent = serde_json::from_value(serde_json::json!([
+            {"type": "text", "text": "first"},
+            {"type": "tool_use", "id": "t", "name": "Bash", "input": {}},
+            {"type": "text", "text": "  second  "},
+        ]))
+        .unwrap();
+        assert_eq!(content.text().as_deref(), Some("first\n\nsecond"));
+        assert_eq!(MessageContent::Text("   ".into()).text(), None);
+        assert!(MessageContent::Text("x".into()).blocks().is_empty());
+    }
+
     #[test]
     fn shell_escape_normal_string() {
         assert_eq!(shell_escape_single_quote("/tmp/project"), "'/tmp/project'");
@@ -0,0 +1,555 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::discover;
+use crate::display::{self, JsonSession};
+use crate::errors::CcseshError;
+use crate::hyperlink::Hyperlinks;
+use crate::parse;
+use crate::sessions;
+use crate::shell::ResumeTemplate;
+use crate::theme::Theme;
+use crate::timefmt::TimeDisplay;
+use crate::types::SessionInfo;
+
+/// Redraw at least this often so relative times stay fresh without changes.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the polling fallback re-stats the projects directory.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Claude Code appends several lines per turn; coalesce the resulting burst of
+/// filesystem events into one refresh.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// How a session differs between two consecutive listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Updated,
+    Removed,
+}
+
+/// A change to one session. For `Removed`, `session` and `index` describe the
+/// session as it appeared in the previous listing.
+#[derive(Debug, Clone)]
+pub struct SessionChange {
+    pub kind: ChangeKind,
+    pub index: usize,
+    pub session: SessionInfo,
+}
+
+/// Compare two listings by session ID. Added and updated sessions are reported
+/// in `next` order, followed by removed sessions in `prev` order.
+pub fn diff_sessions(prev: &[SessionInfo], next: &[SessionInfo]) -> Vec<SessionChange> {
+    let prev_by_id: HashMap<&str, &SessionInfo> =
+        prev.iter().map(|s| (s.session_id.as_str(), s)).collect();
+    let next_ids: HashSet<&str> = next.iter().map(|s| s.session_id.as_str()).collect();
+
+    let mut changes = Vec::new();
+    for (index, session) in next.iter().enumerate() {
+        let kind = match prev_by_id.get(session.session_id.as_str()) {
+            None => ChangeKind::Added,
+            Some(old) if session_changed(old, session) => ChangeKind::Updated,
+            Some(_) => continue,
+        };
+        changes.push(SessionChange {
+            kind,
+            index,
+            session: session.clone(),
+        });
+    }
+    for (index, session) in prev.iter().enumerate() {
+        if !next_ids.contains(session.session_id.as_str()) {
+            changes.push(SessionChange {
+                kind: ChangeKind::Removed,
+                index,
+                session: session.clone(),
+            });
+        }
+    }
+    changes
+}
+
+fn session_changed(old: &SessionInfo, new: &SessionInfo) -> bool {
+    old.last_active != new.last_active
+        || old.first_prompt != new.first_prompt
+        || old.slug != new.slug
+        || old.active != new.active
+        || old.project_dir != new.project_dir
+}
+
+/// Parsed sessions keyed by path. A file is only reparsed when its mtime
+/// differs from the cached entry.
+#[derive(Default)]
+pub struct SessionCache {
+    entries: HashMap<PathBuf, (SystemTime, Option<SessionInfo>)>,
+    reparsed: usize,
+}
+
+impl SessionCache {
+    /// Load sessions like `sessions::load_sessions`, reusing cached parses.
+    /// An empty projects directory yields an empty list rather than an error,
+    /// since sessions may appear later.
+    pub fn load(&mut self, home_dir: &str, limit: usize) -> Result<Vec<SessionInfo>> {
+        self.reparsed = 0;
+        let mut seen = HashSet::new();
+
+        let result = sessions::load_sessions_with(home_dir, limit, |candidate| {
+            seen.insert(candidate.path.clone());
+            if let Some((mtime, info)) = self.entries.get(&candidate.path)
+                && *mtime == candidate.mtime
+            {
+                return info.clone();
+            }
+            let info = parse::parse_session(candidate, home_dir).ok();
+            self.reparsed += 1;
+            self.entries
+                .insert(candidate.path.clone(), (candidate.mtime, info.clone()));
+            info
+        });
+
+        self.entries.retain(|path, _| seen.contains(path));
+
+        match result {
+            Ok(sessions) => Ok(sessions),
+            Err(e) if matches!(e.downcast_ref(), Some(CcseshError::NoSessionsFound)) => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Number of files parsed (rather than served from cache) by the last `load`.
+    pub fn reparsed(&self) -> usize {
+        self.reparsed
+    }
+}
+
+/// Which change-notification mechanism `spawn_watcher` ended up using.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchBackend {
+    Inotify,
+    Poll,
+}
+
+/// Start a background thread that sends `()` whenever something under
+/// `{home_dir}/.claude/projects` changes. Uses inotify on Linux unless
+/// `force_poll` is set or inotify is unavailable, otherwise polls mtimes.
+/// The thread exits once the receiver is dropped.
+pub fn spawn_watcher(home_dir: &str, force_poll: bool) -> (Receiver<()>, WatchBackend) {
+    let (tx, rx) = mpsc::channel();
+    let projects_dir = Path::new(home_dir).join(".claude").join("projects");
+
+    #[cfg(target_os = "linux")]
+    if !force_poll && inotify::spawn(&projects_dir, tx.clone()).is_ok() {
+        return (rx, WatchBackend::Inotify);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (force_poll, &projects_dir);
+
+    spawn_poller(home_dir.to_string(), tx, POLL_INTERVAL);
+    (rx, WatchBackend::Poll)
+}
+
+/// Snapshot of every session file's path and mtime, used by the poller.
+fn fingerprint(home_dir: &str) -> Vec<(PathBuf, SystemTime)> {
+    let mut files: Vec<(PathBuf, SystemTime)> = discover::discover_sessions(home_dir, usize::MAX)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| (c.path, c.mtime))
+        .collect();
+    files.sort();
+    files
+}
+
+fn spawn_poller(home_dir: String, tx: Sender<()>, interval: Duration) {
+    // Take the baseline before returning so changes made right after
+    // `spawn_watcher` are not folded into it.
+    let mut last = fingerprint(&home_dir);
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(interval);
+            let current = fingerprint(&home_dir);
+            if current != last {
+                last = current;
+                if tx.send(()).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::ffi::CString;
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::{AsRawFd, FromRawFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::mpsc::Sender;
+
+    const ROOT_MASK: u32 =
+        libc::IN_CREATE | libc::IN_DELETE | libc::IN_MOVED_FROM | libc::IN_MOVED_TO;
+    const PROJECT_MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MODIFY
+        | libc::IN_CLOSE_WRITE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO;
+
+    /// Size of `struct inotify_event` without the trailing name.
+    const EVENT_HEADER: usize = 16;
+
+    fn add_watch(fd: i32, path: &Path, mask: u32) -> io::Result<i32> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: `fd` is a live inotify descriptor and `c_path` is a valid
+        // NUL-terminated string for the duration of the call.
+        let wd = unsafe { libc::inotify_add_watch(fd, c_path.as_ptr(), mask) };
+        if wd < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(wd)
+        }
+    }
+
+    /// Watch the projects dir (for new project folders) and every project
+    /// folder in it (for session file changes).
+    pub fn spawn(projects_dir: &Path, tx: Sender<()>) -> io::Result<()> {
+        // SAFETY: plain syscall; the returned descriptor is checked below.
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` was just returned by inotify_init1 and is owned solely
+        // by this `File`, which closes it on drop.
+        let mut file = unsafe { File::from_raw_fd(fd) };
+
+        let root_wd = add_watch(fd, projects_dir, ROOT_MASK)?;
+        if let Ok(entries) = std::fs::read_dir(projects_dir) {
+            for entry in entries.flatten() {
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    let _ = add_watch(fd, &entry.path(), PROJECT_MASK);
+                }
+            }
+        }
+
+        let projects_dir = projects_dir.to_path_buf();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = match file.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+
+                let mut offset = 0;
+                while offset + EVENT_HEADER <= n {
+                    let field = |at: usize| {
+                        u32::from_ne_bytes(buf[offset + at..offset + at + 4].try_into().unwrap())
+                    };
+                    let wd = field(0) as i32;
+                    let mask = field(4);
+                    let len = field(12) as usize;
+                    let name_end = (offset + EVENT_HEADER + len).min(n);
+                    let name = &buf[offset + EVENT_HEADER..name_end];
+                    let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+
+                    // New project folder: start watching it too.
+                    if wd == root_wd
+                        && mask & libc::IN_ISDIR != 0
+                        && mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0
+                    {
+                        let dir = projects_dir.join(std::ffi::OsStr::from_bytes(name));
+                        let _ = add_watch(file.as_raw_fd(), &dir, PROJECT_MASK);
+                    }
+
+                    offset += EVENT_HEADER + len;
+                }
+
+                if tx.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Options for `run_watch`.
+pub struct WatchOptions<'a> {
+    pub limit: usize,
+    pub json: bool,
+    pub force_poll: bool,
+    pub template: &'a ResumeTemplate,
+    pub args: &'a [String],
+    pub hyperlinks: Option<&'a Hyperlinks>,
+    pub theme: Theme,
+    pub time: &'a TimeDisplay,
+}
+
+/// One NDJSON record emitted by `ccsesh watch --json`.
+#[derive(Serialize)]
+struct WatchEvent {
+    event: ChangeKind,
+    session: JsonSession,
+}
+
+/// Run watch mode until interrupted (or stdout is closed).
+pub fn run_watch(home_dir: &str, options: &WatchOptions) -> Result<()> {
+    let projects_dir = Path::new(home_dir).join(".claude").join("projects");
+    if !projects_dir.is_dir() {
+        return Err(CcseshError::ProjectsDirNotFound { path: projects_dir }.into());
+    }
+
+    let (rx, _backend) = spawn_watcher(home_dir, options.force_poll);
+    let mut cache = SessionCache::default();
+    let mut previous: Option<Vec<SessionInfo>> = None;
+    let mut stdout = std::io::stdout();
+
+    loop {
+        let sessions = cache.load(home_dir, options.limit)?;
+        let now = Utc::now();
+        let changes = diff_sessions(previous.as_deref().unwrap_or_default(), &sessions);
+
+        if options.json {
+            for change in &changes {
+                let event = WatchEvent {
+                    event: change.kind,
+                    session: display::json_session(
+                        change.index,
+                        &change.session,
+                        now,
+                        options.template,
+                        options.args,
+                    ),
+                };
+                writeln!(stdout, "{}", serde_json::to_string(&event)?)?;
+            }
+        } else {
+            // Nothing is "just updated" on the very first render.
+            let updated: HashSet<String> = if previous.is_some() {
+                changes
+                    .iter()
+                    .filter(|c| c.kind != ChangeKind::Removed)
+                    .map(|c| c.session.session_id.clone())
+                    .collect()
+            } else {
+                HashSet::new()
+            };
+            // Clear screen and home the cursor before redrawing.
+            write!(
+                stdout,
+                "\x1b[2J\x1b[H{}",
+                display::format_watch(
+                    &sessions,
+                    now,
+                    &updated,
+                    &display::ListingOptions {
+                        term_width: display::terminal_width(),
+                        hyperlinks: options.hyperlinks,
+                        theme: options.theme,
+                        time: options.time.clone(),
+                    }
+                )
+            )?;
+        }
+        stdout.flush()?;
+        previous = Some(sessions);
+
+        match rx.recv_timeout(REFRESH_INTERVAL) {
+            Ok(()) => {
+                std::thread::sleep(DEBOUNCE);
+                while rx.try_recv().is_ok() {}
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            // The watcher thread died (e.g. the projects dir was removed);
+            // keep refreshing on the timer alone.
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeDelta};
+    use std::fs;
+
+    const UUID_A: &str = "eb53d999-8692-42ce-a376-4f82206a086d";
+    const UUID_B: &str = "ab53d999-8692-42ce-a376-4f82206a086d";
+
+    fn make_session(id: &str, last_active: DateTime<Utc>, prompt: &str) -> SessionInfo {
+        SessionInfo {
+            project_dir_display: "~/dev".to_string(),
+            last_active,
+            first_prompt: Some(prompt.to_string()),
+            ..SessionInfo::fixture(id, "/home/user/dev")
+        }
+    }
+
+    /// Create `.claude/projects/-proj/` under `root` and return the project dir.
+    fn setup_project(root: &Path) -> PathBuf {
+        let project = root.join(".claude").join("projects").join("-proj");
+        fs::create_dir_all(&project).unwrap();
+        project
+    }
+
+    fn write_session(project: &Path, id: &str, prompt: &str, mtime: SystemTime) {
+        let path = project.join(format!("{}.jsonl", id));
+        fs::write(
+            &path,
+            format!(
+                "{{\"type\":\"user\",\"cwd\":\"/tmp/proj\",\"message\":{{\"content\":\"{}\"}}}}\n",
+                prompt
+            ),
+        )
+        .unwrap();
+        let times = fs::FileTimes::new().set_modified(mtime);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+    }
+
+    #[test]
+    fn diff_detects_added_updated_removed() {
+        let now = Utc::now();
+        let prev = vec![
+            make_session(UUID_A, now - TimeDelta::seconds(60), "a"),
+            make_session(UUID_B, now - TimeDelta::seconds(120), "b"),
+        ];
+        let next = vec![
+            make_session("new", now, "c"),
+            make_session(UUID_A, now - TimeDelta::seconds(10), "a"),
+        ];
+        let changes = diff_sessions(&prev, &next);
+        let summary: Vec<(ChangeKind, &str, usize)> = changes
+            .iter()
+            .map(|c| (c.kind, c.session.session_id.as_str(), c.index))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ChangeKind::Added, "new", 0),
+                (ChangeKind::Updated, UUID_A, 1),
+                (ChangeKind::Removed, UUID_B, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_identical_listings_is_empty() {
+        let now = Utc::now();
+        let sessions = vec![make_session(UUID_A, now, "a")];
+        assert!(diff_sessions(&sessions, &sessions).is_empty());
+    }
+
+    #[test]
+    fn diff_from_empty_is_all_added() {
+        let now = Utc::now();
+        let next = vec![
+            make_session(UUID_A, now, "a"),
+            make_session(UUID_B, now, "b"),
+        ];
+        let changes = diff_sessions(&[], &next);
+        assert!(changes.iter().all(|c| c.kind == ChangeKind::Added));
+        assert_eq!(changes.len(), 2);
+    }
+
+    #[test]
+    fn cache_only_reparses_changed_files() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let project = setup_project(tmp.path());
+        let home = tmp.path().to_str().unwrap();
+        let now = SystemTime::now();
+        write_session(&project, UUID_A, "first", now - Duration::from_secs(100));
+        write_session(&project, UUID_B, "second", now - Duration::from_secs(200));
+
+        let mut cache = SessionCache::default();
+        let sessions = cache.load(home, 10).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(cache.reparsed(), 2);
+
+        let again = cache.load(home, 10).unwrap();
+        assert_eq!(again.len(), 2);
+        assert_eq!(cache.reparsed(), 0);
+
+        write_session(&project, UUID_B, "second, edited", now);
+        let updated = cache.load(home, 10).unwrap();
+        assert_eq!(cache.reparsed(), 1);
+        assert_eq!(updated[0].session_id, UUID_B);
+        assert_eq!(updated[0].first_prompt.as_deref(), Some("second, edited"));
+    }
+
+    #[test]
+    fn cache_empty_projects_dir_is_empty_listing() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        setup_project(tmp.path());
+        let mut cache = SessionCache::default();
+        assert!(
+            cache
+                .load(tmp.path().to_str().unwrap(), 5)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn poller_signals_on_change() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let project = setup_project(tmp.path());
+        let (tx, rx) = mpsc::channel();
+        spawn_poller(
+            tmp.path().to_str().unwrap().to_string(),
+            tx,
+            Duration::from_millis(20),
+        );
+
+        std::thread::sleep(Duration::from_millis(50));
+        write_session(&project, UUID_A, "hello", SystemTime::now());
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn inotify_signals_on_write_and_new_project() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        let project = setup_project(tmp.path());
+        let (rx, backend) = spawn_watcher(tmp.path().to_str().unwrap(), false);
+        assert_eq!(backend, WatchBackend::Inotify);
+
+        write_session(&project, UUID_A, "hello", SystemTime::now());
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        while rx.try_recv().is_ok() {}
+
+        // Files in a project folder created after the watcher started are seen too.
+        let late = tmp.path().join(".claude").join("projects").join("-late");
+        fs::create_dir_all(&late).unwrap();
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        std::thread::sleep(Duration::from_millis(50));
+        while rx.try_recv().is_ok() {}
+        write_session(&late, UUID_B, "late", SystemTime::now());
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn force_poll_uses_poll_backend() {
+        let tmp = assert_fs::TempDir::new().unwrap();
+        setup_project(tmp.path());
+        let (_rx, backend) = spawn_watcher(tmp.path().to_str().unwrap(), true);
+        assert_eq!(backend, WatchBackend::Poll);
+    }
+}